@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Result;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Mutex;
+
+use crate::app::{ui, App};
+use crate::container_management::NotificationLevel;
+use crate::io::IoEvent;
+
+/// Time between two checks for changes
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+const COMMANDS: &str = "Commands: list, errors, quit.";
+
+/// Run without drawing: changes of the containers are printed as plain lines,
+/// one at a time, for screen readers. Commands are read from the standard input.
+pub async fn start_accessible(app: &Arc<Mutex<App>>) -> Result<()> {
+    println!("bctop accessible mode. {}", COMMANDS);
+    app.lock().await.dispatch(IoEvent::StartMonitoring).await;
+
+    let mut input = BufReader::new(tokio::io::stdin()).lines();
+    let mut tick = tokio::time::interval(ANNOUNCE_INTERVAL);
+    let mut announcer = Announcer::default();
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {
+                let mut app = app.lock().await;
+                app.update_on_tick().await;
+                for line in announcer.changes(&app) {
+                    println!("{}", line);
+                }
+            }
+            line = input.next_line() => {
+                // The end of the input quits, like `quit`
+                let line = match line? {
+                    Some(line) => line,
+                    None => break,
+                };
+                let app = app.lock().await;
+                match line.trim() {
+                    "" => {}
+                    "q" | "quit" | "exit" => break,
+                    "l" | "list" => {
+                        for line in summary(&app) {
+                            println!("{}", line);
+                        }
+                    }
+                    "e" | "errors" => {
+                        if app.errors().is_empty() {
+                            println!("No errors.");
+                        }
+                        for e in app.errors() {
+                            println!("Error, {} times: {}", e.count, e.message);
+                        }
+                    }
+                    other => println!("Unknown command {}. {}", other, COMMANDS),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One line per listed container, its state and usage in words
+fn summary(app: &App) -> Vec<String> {
+    let containers = app.visible_containers();
+    let mut lines = vec![format!("{} containers.", containers.len())];
+    lines.extend(containers.iter().map(|c| {
+        format!(
+            "{}: {}, CPU {:.1} percent, memory {}.",
+            c.name,
+            c.status,
+            c.cpu_usage,
+            ui::label_for_size(c.memory_usage_bytes, app.byte_units())
+        )
+    }));
+    lines
+}
+
+/// What was last announced, to only announce what changed since
+#[derive(Debug, Default)]
+struct Announcer {
+    /// Name and status of the listed containers, by id
+    containers: Option<HashMap<String, (String, String)>>,
+    notification: Option<String>,
+    /// Message and count of the latest error
+    error: Option<(String, usize)>,
+}
+
+impl Announcer {
+    fn changes(&mut self, app: &App) -> Vec<String> {
+        let mut lines = Vec::new();
+        let current: HashMap<String, (String, String)> = app
+            .visible_containers()
+            .iter()
+            .map(|c| (c.id.clone(), (c.name.clone(), c.status.to_string())))
+            .collect();
+        match &self.containers {
+            // The first list is announced as a whole
+            None if !current.is_empty() => {
+                let running = current.values().filter(|(_, s)| s == "running").count();
+                lines.push(format!(
+                    "{} containers, {} running. Type list for details.",
+                    current.len(),
+                    running
+                ));
+                self.containers = Some(current);
+            }
+            None => {}
+            Some(previous) => {
+                for (id, (name, status)) in &current {
+                    match previous.get(id) {
+                        None => lines.push(format!("{} added, {}.", name, status)),
+                        Some((_, old)) if old != status => {
+                            lines.push(format!("{} is now {}.", name, status))
+                        }
+                        Some(_) => {}
+                    }
+                }
+                for (id, (name, _)) in previous {
+                    if !current.contains_key(id) {
+                        lines.push(format!("{} removed.", name));
+                    }
+                }
+                self.containers = Some(current);
+            }
+        }
+
+        // Errors are announced below, from the error list
+        let notification = app
+            .notification()
+            .filter(|n| n.level != NotificationLevel::Error)
+            .map(|n| n.message.clone());
+        if notification.is_some() && notification != self.notification {
+            lines.extend(notification.clone());
+        }
+        self.notification = notification;
+
+        let error = app.errors().back().map(|e| (e.message.clone(), e.count));
+        if error.is_some() && error != self.error {
+            lines.extend(
+                error
+                    .as_ref()
+                    .map(|(message, _)| format!("Error: {}", message)),
+            );
+        }
+        self.error = error;
+        lines
+    }
+}
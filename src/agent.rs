@@ -0,0 +1,654 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::{bail, eyre, Result};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::app::App;
+use crate::config::EngineProfile;
+use crate::container_management::{
+    stream_logs, ContainerManagement, Docker, EngineSummary, LogLine,
+};
+use crate::io::IoEvent;
+
+/// Time between two snapshots sent to a client
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+/// Time before connecting again to an agent that went away
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Lines of the split view, like with a local engine
+pub const PREVIEW_LOG_LINES: usize = 100;
+/// Requests waiting to be sent to the agent
+const REQUEST_QUEUE: usize = 16;
+/// Batches of log lines waiting to be sent to a client
+const LOG_QUEUE: usize = 16;
+/// Time a client has to authenticate once connected
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Bytes read at once from the compressed stream of the agent
+const READ_CHUNK: usize = 16 * 1024;
+
+/// What a client asks the agent, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "t", rename_all = "snake_case")]
+pub enum Request {
+    /// First line of a client, with the token the agent was started with
+    Hello {
+        token: Option<String>,
+    },
+    /// Follow the logs of a container, in the log viewer or the split view
+    Logs {
+        container: String,
+        tail: Option<usize>,
+        preview: bool,
+    },
+    StopLogs {
+        preview: bool,
+    },
+    Operation {
+        operation: Operation,
+        container: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Stop,
+    Pause,
+    Restart,
+    Remove,
+}
+
+/// What the agent sends its clients, one JSON object per line in a deflate
+/// stream flushed after each frame, so the compression carries over frames.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "t", rename_all = "snake_case")]
+enum Frame {
+    /// The token of the client is wrong, the connection is closed
+    Rejected,
+    /// A request of the client was refused
+    Error {
+        message: String,
+    },
+    Engine(EngineSummary),
+    /// Fields of the containers that changed since the previous snapshot,
+    /// along with their id
+    Containers {
+        changed: Vec<Map<String, Value>>,
+        removed: Vec<String>,
+    },
+    Logs {
+        container: String,
+        preview: bool,
+        lines: Vec<LogLine>,
+    },
+}
+
+/// Run the backend only and serve the containers and their logs to the
+/// interfaces connecting on `listen` with `token`, until interrupted. Without
+/// a token only local clients, like SSH tunnels, can reach the agent. Only
+/// local clients can stop, pause, restart or remove containers since the
+/// connection is not encrypted.
+pub async fn start_agent(
+    app: &Arc<Mutex<App>>,
+    docker: Docker,
+    listen: &str,
+    token: Option<String>,
+) -> Result<()> {
+    let listener = TcpListener::bind(listen).await?;
+    let address = listener.local_addr()?;
+    // Clients follow the logs and the environment of the containers
+    if token.is_none() && !address.ip().is_loopback() {
+        bail!(
+            "refusing to listen on {} without a token, set --token or BCTOP_AGENT_TOKEN",
+            address
+        );
+    }
+    let token: Option<Arc<str>> = token.map(Into::into);
+    app.lock().await.dispatch(IoEvent::StartMonitoring).await;
+    info!("Agent listening on {}", address);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("Error accepting an agent client: {}", e);
+                        continue;
+                    }
+                };
+                info!("Agent client connected: {}", peer);
+                let app = Arc::clone(app);
+                let docker = docker.clone();
+                let token = token.clone();
+                let operations = peer.ip().is_loopback();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, app, docker, token, operations).await {
+                        info!("Agent client {} left: {}", peer, e);
+                    }
+                });
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    info!("Agent stopped");
+    Ok(())
+}
+
+/// Logs followed for a client, stopped when replaced
+struct LogStream {
+    container: String,
+    task: JoinHandle<()>,
+    lines: mpsc::Receiver<Vec<LogLine>>,
+}
+
+impl LogStream {
+    fn start(docker: Docker, container: String, tail: Option<usize>) -> Self {
+        let (tx, lines) = mpsc::channel(LOG_QUEUE);
+        let id = container.clone();
+        let task = tokio::spawn(async move {
+            stream_logs(docker, id, tail, tx).await;
+        });
+        Self {
+            container,
+            task,
+            lines,
+        }
+    }
+}
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Next lines of a stream, waiting forever without one
+async fn next_lines(stream: &mut Option<LogStream>) -> (String, Vec<LogLine>) {
+    if let Some(stream) = stream {
+        if let Some(lines) = stream.lines.recv().await {
+            return (stream.container.clone(), lines);
+        }
+    }
+    std::future::pending().await
+}
+
+async fn serve(
+    stream: TcpStream,
+    app: Arc<Mutex<App>>,
+    docker: Docker,
+    token: Option<Arc<str>>,
+    operations: bool,
+) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let (reader, writer) = stream.into_split();
+    let mut writer = FrameWriter::new(writer);
+    let mut requests = BufReader::new(reader).lines();
+    // Nothing is sent before the client is authenticated
+    let hello = tokio::time::timeout(HANDSHAKE_TIMEOUT, requests.next_line())
+        .await
+        .map_err(|_| eyre!("no handshake"))??
+        .ok_or_else(|| eyre!("connection closed"))?;
+    let given = match serde_json::from_str(&hello) {
+        Ok(Request::Hello { token }) => token,
+        _ => bail!("invalid handshake"),
+    };
+    if !token_matches(token.as_deref(), given.as_deref()) {
+        writer.send(&Frame::Rejected).await?;
+        bail!("wrong token");
+    }
+    let mut snapshot = Snapshot::default();
+    let mut logs = None;
+    let mut preview = None;
+    let mut tick = tokio::time::interval(SNAPSHOT_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {
+                let frames = snapshot.frames(&*app.lock().await);
+                for frame in frames {
+                    writer.send(&frame).await?;
+                }
+            }
+            (container, lines) = next_lines(&mut logs) => {
+                writer.send(&Frame::Logs { container, preview: false, lines }).await?;
+            }
+            (container, lines) = next_lines(&mut preview) => {
+                writer.send(&Frame::Logs { container, preview: true, lines }).await?;
+            }
+            line = requests.next_line() => {
+                let line = line?.ok_or_else(|| eyre!("connection closed"))?;
+                let request = match serde_json::from_str(&line) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        warn!("Invalid agent request {}: {}", line, e);
+                        continue;
+                    }
+                };
+                match request {
+                    Request::Hello { .. } => {}
+                    Request::Logs { container, tail, preview: false } => {
+                        logs = Some(LogStream::start(docker.clone(), container, tail));
+                    }
+                    Request::Logs { container, tail, preview: true } => {
+                        preview = Some(LogStream::start(docker.clone(), container, tail));
+                    }
+                    Request::StopLogs { preview: false } => logs = None,
+                    Request::StopLogs { preview: true } => preview = None,
+                    Request::Operation { .. } if !operations => {
+                        let message = "the agent only takes operations from local clients, \
+                            reach it through an SSH tunnel"
+                            .to_string();
+                        writer.send(&Frame::Error { message }).await?;
+                    }
+                    Request::Operation { operation, container } => {
+                        let event = match operation {
+                            Operation::Stop => IoEvent::StopContainer(container),
+                            Operation::Pause => IoEvent::PauseContainer(container),
+                            Operation::Restart => IoEvent::RestartContainer(container),
+                            Operation::Remove => IoEvent::RemoveContainer(container),
+                        };
+                        app.lock().await.dispatch(event).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the token of a client is the one of the agent, any token being
+/// accepted when the agent has none. Compared in constant time so it can not
+/// be guessed byte by byte.
+fn token_matches(expected: Option<&str>, given: Option<&str>) -> bool {
+    let expected = match expected {
+        Some(expected) => expected.as_bytes(),
+        None => return true,
+    };
+    let given = given.unwrap_or_default().as_bytes();
+    expected.len() == given.len()
+        && expected
+            .iter()
+            .zip(given)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// What a client already received, to only send what changed
+#[derive(Debug, Default)]
+struct Snapshot {
+    containers: HashMap<String, Map<String, Value>>,
+    engine: bool,
+}
+
+impl Snapshot {
+    fn frames(&mut self, app: &App) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        if !self.engine {
+            if let Some(summary) = app.engine_summary() {
+                frames.push(Frame::Engine(summary.clone()));
+                self.engine = true;
+            }
+        }
+        let mut changed = Vec::new();
+        let mut seen = HashSet::new();
+        for c in app.containers() {
+            let fields = match serde_json::to_value(c) {
+                Ok(Value::Object(fields)) => fields,
+                _ => continue,
+            };
+            let sent = self.containers.get(&c.id);
+            let delta: Map<String, Value> = fields
+                .iter()
+                .filter(|(k, v)| k.as_str() == "id" || sent.and_then(|s| s.get(*k)) != Some(*v))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if delta.len() > 1 {
+                changed.push(delta);
+            }
+            seen.insert(c.id.clone());
+            self.containers.insert(c.id.clone(), fields);
+        }
+        let removed: Vec<String> = self
+            .containers
+            .keys()
+            .filter(|id| !seen.contains(*id))
+            .cloned()
+            .collect();
+        for id in &removed {
+            self.containers.remove(id);
+        }
+        if !changed.is_empty() || !removed.is_empty() {
+            frames.push(Frame::Containers { changed, removed });
+        }
+        frames
+    }
+}
+
+async fn send(writer: &mut (impl AsyncWrite + Unpin), message: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+/// Compress `input` into the deflate stream, flushed so that the receiver can
+/// read all of it without waiting for the next one
+fn deflate(compress: &mut Compress, input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len() / 2 + 64);
+    let mut consumed = 0;
+    loop {
+        let before = compress.total_in();
+        compress.compress_vec(&input[consumed..], &mut output, FlushCompress::Sync)?;
+        consumed += (compress.total_in() - before) as usize;
+        // The flush is over once it leaves room in the output
+        if consumed == input.len() && output.len() < output.capacity() {
+            return Ok(output);
+        }
+        output.reserve(output.capacity());
+    }
+}
+
+/// Append to `output` what `input` decompresses to
+fn inflate(decompress: &mut Decompress, mut input: &[u8], output: &mut Vec<u8>) -> Result<()> {
+    loop {
+        output.reserve(input.len() * 4 + 64);
+        let (before_in, before_out) = (decompress.total_in(), decompress.total_out());
+        decompress.decompress_vec(input, output, FlushDecompress::None)?;
+        input = &input[(decompress.total_in() - before_in) as usize..];
+        let produced = decompress.total_out() - before_out;
+        if input.is_empty() && output.len() < output.capacity() {
+            return Ok(());
+        }
+        if produced == 0 && decompress.total_in() == before_in {
+            bail!("corrupted stream from the agent");
+        }
+    }
+}
+
+/// Frames of the agent, compressed on the way to the client
+struct FrameWriter<W> {
+    writer: W,
+    compress: Compress,
+}
+
+impl<W: AsyncWrite + Unpin> FrameWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            compress: Compress::new(Compression::default(), false),
+        }
+    }
+
+    async fn send(&mut self, frame: &Frame) -> Result<()> {
+        let mut line = serde_json::to_vec(frame)?;
+        line.push(b'\n');
+        self.writer
+            .write_all(&deflate(&mut self.compress, &line)?)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Frames of the agent, decompressed by the client
+struct FrameReader<R> {
+    reader: R,
+    decompress: Decompress,
+    /// Decompressed bytes not making a whole line yet
+    pending: Vec<u8>,
+    chunk: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> FrameReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decompress: Decompress::new(false),
+            pending: Vec::new(),
+            chunk: vec![0; READ_CHUNK],
+        }
+    }
+
+    /// Next frame, `None` once the agent closed the connection
+    async fn next(&mut self) -> Result<Option<Frame>> {
+        loop {
+            if let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.pending.drain(..=end).collect();
+                return Ok(Some(serde_json::from_slice(&line)?));
+            }
+            let read = self.reader.read(&mut self.chunk).await?;
+            if read == 0 {
+                return Ok(None);
+            }
+            inflate(&mut self.decompress, &self.chunk[..read], &mut self.pending)?;
+        }
+    }
+}
+
+/// Connection of the interface to an agent, in place of the engine. It
+/// connects again when the agent goes away.
+pub struct AgentClient {
+    requests: mpsc::Sender<Request>,
+    task: JoinHandle<()>,
+}
+
+impl AgentClient {
+    /// Connect to the agent of a `bctop://` profile, `None` for the other
+    /// profiles
+    pub fn connect(
+        profile: &EngineProfile,
+        manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
+    ) -> Option<Self> {
+        let address = profile.agent()?.to_string();
+        let hello = Request::Hello {
+            token: profile.agent_token.clone(),
+        };
+        let (requests, rx) = mpsc::channel(REQUEST_QUEUE);
+        let task = tokio::spawn(async move {
+            run_client(address, hello, rx, manager).await;
+        });
+        Some(Self { requests, task })
+    }
+
+    pub async fn send(&self, request: Request) -> Result<()> {
+        self.requests
+            .send(request)
+            .await
+            .map_err(|_| eyre!("the connection to the agent is closed"))
+    }
+}
+
+impl Drop for AgentClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Logs followed through the agent, asked again after connecting again
+#[derive(Debug, Default)]
+struct Followed {
+    logs: Option<Request>,
+    preview: Option<Request>,
+}
+
+impl Followed {
+    fn track(&mut self, request: &Request) {
+        match request {
+            Request::Logs { preview: false, .. } => self.logs = Some(request.clone()),
+            Request::Logs { preview: true, .. } => self.preview = Some(request.clone()),
+            Request::StopLogs { preview: false } => self.logs = None,
+            Request::StopLogs { preview: true } => self.preview = None,
+            Request::Hello { .. } | Request::Operation { .. } => {}
+        }
+    }
+}
+
+async fn run_client<M: ContainerManagement>(
+    address: String,
+    hello: Request,
+    mut requests: mpsc::Receiver<Request>,
+    manager: Arc<Mutex<M>>,
+) {
+    let mut followed = Followed::default();
+    // Only report the first failure until connected again
+    let mut reported = false;
+    loop {
+        let result = match TcpStream::connect(&address).await {
+            Ok(stream) => {
+                info!("Connected to agent {}", address);
+                reported = false;
+                session(stream, &hello, &mut requests, &mut followed, &manager).await
+            }
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            // The handler dropped the client
+            Ok(()) => return,
+            Err(e) if !reported => {
+                let message = format!("Connection to agent {} failed: {}", address, e);
+                warn!("{}", message);
+                manager.lock().await.report_error(message);
+                reported = true;
+            }
+            Err(_) => {}
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn session<M: ContainerManagement>(
+    stream: TcpStream,
+    hello: &Request,
+    requests: &mut mpsc::Receiver<Request>,
+    followed: &mut Followed,
+    manager: &Arc<Mutex<M>>,
+) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let (reader, mut writer) = stream.into_split();
+    let mut frames = FrameReader::new(reader);
+    send(&mut writer, hello).await?;
+    for request in followed.logs.iter().chain(followed.preview.iter()) {
+        send(&mut writer, request).await?;
+    }
+    // Every field of the containers, the snapshots only carry the changes
+    let mut containers = HashMap::new();
+    loop {
+        tokio::select! {
+            frame = frames.next() => {
+                let frame = frame?.ok_or_else(|| eyre!("connection closed by the agent"))?;
+                if let Frame::Rejected = frame {
+                    bail!("the agent refused the token, check agent_token");
+                }
+                apply(frame, &mut containers, &mut *manager.lock().await);
+            }
+            request = requests.recv() => {
+                let request = match request {
+                    Some(request) => request,
+                    None => return Ok(()),
+                };
+                followed.track(&request);
+                send(&mut writer, &request).await?;
+            }
+        }
+    }
+}
+
+fn apply(
+    frame: Frame,
+    containers: &mut HashMap<String, Map<String, Value>>,
+    manager: &mut impl ContainerManagement,
+) {
+    match frame {
+        Frame::Rejected => {}
+        Frame::Error { message } => manager.report_error(message),
+        Frame::Engine(summary) => manager.set_engine_summary(summary),
+        Frame::Containers { changed, removed } => {
+            for fields in changed {
+                let id = match fields.get("id").and_then(Value::as_str) {
+                    Some(id) => id.to_string(),
+                    None => continue,
+                };
+                let known = containers.entry(id).or_default();
+                known.extend(fields);
+                match serde_json::from_value(Value::Object(known.clone())) {
+                    Ok(c) => manager.update_containers(c),
+                    Err(e) => warn!("Invalid container from the agent: {}", e),
+                }
+            }
+            for id in removed {
+                containers.remove(&id);
+                manager.remove_container(&id);
+            }
+        }
+        Frame::Logs {
+            container,
+            preview: true,
+            lines,
+        } => {
+            let lines = lines
+                .iter()
+                .flat_map(|l| l.text.lines())
+                .map(str::to_string);
+            manager.add_preview_logs(container, lines.collect());
+        }
+        Frame::Logs { lines, .. } => manager.add_logs(lines),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        assert!(token_matches(None, None));
+        assert!(token_matches(None, Some("anything")));
+        assert!(token_matches(Some("secret"), Some("secret")));
+        assert!(!token_matches(Some("secret"), Some("secreT")));
+        assert!(!token_matches(Some("secret"), Some("secret2")));
+        assert!(!token_matches(Some("secret"), Some("")));
+        assert!(!token_matches(Some("secret"), None));
+    }
+
+    #[test]
+    fn frames_survive_any_split_of_the_stream() {
+        let mut compress = Compress::new(Compression::default(), false);
+        let frames = ["first line\n", "second\n", &"repeated ".repeat(2000), "\n"];
+        let stream: Vec<u8> = frames
+            .iter()
+            .flat_map(|frame| deflate(&mut compress, frame.as_bytes()).unwrap())
+            .collect();
+        assert!(stream.len() < frames.concat().len() / 10);
+        for chunk in [1, 7, stream.len()] {
+            let mut decompress = Decompress::new(false);
+            let mut output = Vec::new();
+            for part in stream.chunks(chunk) {
+                inflate(&mut decompress, part, &mut output).unwrap();
+            }
+            assert_eq!(output, frames.concat().as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn frames_round_trip() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.send(&Frame::Rejected).await.unwrap();
+        let message = "refused".to_string();
+        writer.send(&Frame::Error { message }).await.unwrap();
+        let mut reader = FrameReader::new(writer.writer.as_slice());
+        assert!(matches!(
+            reader.next().await.unwrap(),
+            Some(Frame::Rejected)
+        ));
+        match reader.next().await.unwrap() {
+            Some(Frame::Error { message }) => assert_eq!(message, "refused"),
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+        assert!(reader.next().await.unwrap().is_none());
+    }
+}
@@ -9,35 +9,148 @@ use crate::inputs::key::Key;
 pub enum Action {
     Quit,
     ShowLogs,
-    //ExecCommands,
+    ShowStackLogs,
+    ExecCommands,
     SendCMD,
     Next,
     Previous,
     ScrollUp,
     ScrollDown,
     Search,
+    NextMatch,
+    PreviousMatch,
     Remove,
     // Container control
     StopContainer,
     PauseContainer,
+    SortBy,
+    Inspect,
+    CycleFilter,
+    SwitchProfile,
+    ToggleDebugConsole,
+    SwitchRecent,
+    EditNotes,
+    ReverseSort,
+    FilterContainers,
+    ShowStartOrder,
+    ToggleShowAll,
+    Copy,
+    ToggleMark,
+    MarkMatching,
+    RestartContainer,
+    RemoveContainer,
+    NextTab,
+    Snippets,
+    BroadcastExec,
+    ComparePackages,
+    TailFile,
+    EditLogFilters,
+    ShowGraph,
+    OpenSource,
+    PortForward,
+    ShowForwards,
+    ToggleMemoryCalculation,
+    Probe,
+    ShowErrors,
+    ShowSharedVolumes,
+    EditEnv,
+    RecreateContainer,
+    SwitchView,
+    ToggleSplit,
+    ResizeSplit,
+    ScrollColumns,
+    ToggleMatchesOnly,
+    StackFilter,
+    LogTimeRange,
+    LoadMoreLogs,
+    ExportLogs,
+    OpenPager,
+    CycleJsonLogs,
+    ToggleCaseSensitive,
+    ToggleWrap,
+    CycleLogStreams,
+    ToggleFullNames,
+    ToggleDryRun,
+    /// Remove the stopped containers, dangling images or unused volumes
+    Prune,
+    PageUp,
+    PageDown,
+    ScrollTop,
+    ScrollBottom,
 }
 
 impl Action {
     /// All available actions
     pub fn iterator() -> Iter<'static, Action> {
-        static ACTIONS: [Action; 11] = [
+        static ACTIONS: [Action; 68] = [
             Action::Quit,
             Action::ShowLogs,
-            //Action::ExecCommands,
+            Action::ShowStackLogs,
+            Action::ExecCommands,
             Action::SendCMD,
             Action::Next,
             Action::Previous,
             Action::ScrollUp,
             Action::ScrollDown,
             Action::Search,
+            Action::NextMatch,
+            Action::PreviousMatch,
             Action::Remove,
             Action::StopContainer,
             Action::PauseContainer,
+            Action::SortBy,
+            Action::Inspect,
+            Action::CycleFilter,
+            Action::SwitchProfile,
+            Action::ToggleDebugConsole,
+            Action::SwitchRecent,
+            Action::EditNotes,
+            Action::ReverseSort,
+            Action::FilterContainers,
+            Action::ShowStartOrder,
+            Action::ToggleShowAll,
+            Action::Copy,
+            Action::ToggleMark,
+            Action::MarkMatching,
+            Action::RestartContainer,
+            Action::RemoveContainer,
+            Action::NextTab,
+            Action::Snippets,
+            Action::BroadcastExec,
+            Action::ComparePackages,
+            Action::TailFile,
+            Action::EditLogFilters,
+            Action::ShowGraph,
+            Action::OpenSource,
+            Action::PortForward,
+            Action::ShowForwards,
+            Action::ToggleMemoryCalculation,
+            Action::Probe,
+            Action::ShowErrors,
+            Action::ShowSharedVolumes,
+            Action::EditEnv,
+            Action::RecreateContainer,
+            Action::SwitchView,
+            Action::ToggleSplit,
+            Action::ResizeSplit,
+            Action::ScrollColumns,
+            Action::ToggleMatchesOnly,
+            Action::StackFilter,
+            Action::LogTimeRange,
+            Action::LoadMoreLogs,
+            Action::ExportLogs,
+            Action::OpenPager,
+            Action::CycleJsonLogs,
+            Action::ToggleCaseSensitive,
+            Action::ToggleWrap,
+            Action::CycleLogStreams,
+            Action::ToggleFullNames,
+            Action::ToggleDryRun,
+            Action::Prune,
+            Action::PageUp,
+            Action::PageDown,
+            Action::ScrollTop,
+            Action::ScrollBottom,
         ];
         ACTIONS.iter()
     }
@@ -47,16 +160,78 @@ impl Action {
         match self {
             Action::Quit => &[Key::Char('q'), Key::Ctrl('c'), Key::Esc],
             Action::ShowLogs => &[Key::Char('l'), Key::Enter],
-            //Action::ExecCommands => &[Key::Char('e')],
+            Action::ShowStackLogs => &[Key::Char('S')],
+            Action::ExecCommands => &[Key::Char('I')],
             Action::SendCMD => &[Key::Enter],
             Action::Next => &[Key::Down],
             Action::Previous => &[Key::Up],
             Action::Search => &[Key::Char('/'), Key::Enter],
+            Action::NextMatch => &[Key::Char('n')],
+            Action::PreviousMatch => &[Key::Char('N')],
             Action::ScrollUp => &[Key::Up],
             Action::ScrollDown => &[Key::Down],
             Action::Remove => &[Key::Backspace],
             Action::StopContainer => &[Key::Char('s')],
             Action::PauseContainer => &[Key::Char('p')],
+            Action::SortBy => &[Key::Char('o')],
+            Action::Inspect => &[Key::Char('i')],
+            Action::CycleFilter => &[Key::Char('f')],
+            Action::SwitchProfile => &[Key::Char('e')],
+            Action::ToggleDebugConsole => &[Key::F12],
+            Action::SwitchRecent => &[Key::Tab],
+            Action::EditNotes => &[Key::Char('n')],
+            Action::ReverseSort => &[Key::Char('O')],
+            Action::FilterContainers => &[Key::Char('/')],
+            Action::ShowStartOrder => &[Key::Char('t')],
+            Action::ToggleShowAll => &[Key::Char('a')],
+            Action::ToggleSplit => &[Key::Char('L')],
+            Action::ResizeSplit => &[Key::CtrlUp, Key::CtrlDown],
+            Action::ScrollColumns => &[Key::Left, Key::Right],
+            Action::ToggleMatchesOnly => &[Key::Ctrl('o')],
+            Action::StackFilter => &[Key::Ctrl('g')],
+            Action::LogTimeRange => &[Key::Char('T')],
+            Action::LoadMoreLogs => &[Key::Char('+')],
+            Action::ExportLogs => &[Key::Char('W')],
+            Action::OpenPager => &[Key::Char('V')],
+            Action::CycleJsonLogs => &[Key::Char('J')],
+            Action::ToggleCaseSensitive => &[Key::Ctrl('t')],
+            Action::ToggleWrap => &[Key::Char('w')],
+            Action::CycleLogStreams => &[Key::Char('O')],
+            Action::ToggleFullNames => &[Key::Char('N')],
+            Action::ToggleDryRun => &[Key::Char('D')],
+            Action::Prune => &[Key::Char('U')],
+            Action::PageUp => &[Key::PageUp],
+            Action::PageDown => &[Key::PageDown],
+            Action::ScrollTop => &[Key::Home],
+            Action::ScrollBottom => &[Key::End],
+            Action::Copy => &[Key::Char('y')],
+            Action::ToggleMark => &[Key::Char(' ')],
+            Action::MarkMatching => &[Key::Char('M')],
+            Action::RestartContainer => &[Key::Char('r')],
+            Action::RemoveContainer => &[Key::Char('d')],
+            Action::NextTab => &[Key::Tab],
+            Action::Snippets => &[Key::Char('x')],
+            Action::BroadcastExec => &[Key::Char('X')],
+            Action::ComparePackages => &[Key::Char('c')],
+            Action::TailFile => &[Key::Char('F')],
+            Action::EditLogFilters => &[Key::Char('f')],
+            Action::ShowGraph => &[Key::Char('g')],
+            Action::OpenSource => &[Key::Char('o')],
+            Action::PortForward => &[Key::Char('P')],
+            Action::ShowForwards => &[Key::Char('w')],
+            Action::ToggleMemoryCalculation => &[Key::Char('m')],
+            Action::Probe => &[Key::Char('H')],
+            Action::ShowErrors => &[Key::Char('E')],
+            Action::ShowSharedVolumes => &[Key::Char('v')],
+            Action::EditEnv => &[Key::Char('v')],
+            Action::RecreateContainer => &[Key::Char('R')],
+            Action::SwitchView => &[
+                Key::Char('1'),
+                Key::Char('2'),
+                Key::Char('3'),
+                Key::Char('4'),
+                Key::Char('5'),
+            ],
         }
     }
 }
@@ -67,16 +242,72 @@ impl Display for Action {
         let str = match self {
             Action::Quit => "Quit",
             Action::ShowLogs => "Show Logs",
-            //Action::ExecCommands => "Exec CMD",
+            Action::ShowStackLogs => "Stack Logs",
+            Action::ExecCommands => "Exec",
             Action::SendCMD => "Send CMD",
             Action::Next => "Next",
             Action::Previous => "Previous",
             Action::Search => "Search",
+            Action::NextMatch => "Next Match",
+            Action::PreviousMatch => "Previous Match",
             Action::ScrollUp => "Scroll Up",
             Action::ScrollDown => "Scroll Down",
             Action::Remove => "Remove",
             Action::StopContainer => "Stop Container",
             Action::PauseContainer => "Pause Container",
+            Action::SortBy => "Sort By",
+            Action::Inspect => "Inspect",
+            Action::CycleFilter => "Filter",
+            Action::SwitchProfile => "Engine",
+            Action::ToggleDebugConsole => "Debug Console",
+            Action::SwitchRecent => "Recent",
+            Action::EditNotes => "Notes",
+            Action::ReverseSort => "Reverse Sort",
+            Action::FilterContainers => "Search",
+            Action::ShowStartOrder => "Start Order",
+            Action::ToggleShowAll => "All/Running",
+            Action::ToggleSplit => "Split Logs",
+            Action::ResizeSplit => "Resize Split",
+            Action::ScrollColumns => "Scroll Columns",
+            Action::ToggleMatchesOnly => "Matches Only",
+            Action::StackFilter => "Filter",
+            Action::LogTimeRange => "Time Range",
+            Action::LoadMoreLogs => "Load More",
+            Action::ExportLogs => "Export",
+            Action::OpenPager => "Pager",
+            Action::CycleJsonLogs => "JSON",
+            Action::ToggleCaseSensitive => "Match Case",
+            Action::ToggleWrap => "Wrap",
+            Action::CycleLogStreams => "Streams",
+            Action::ToggleFullNames => "Full Names",
+            Action::ToggleDryRun => "Dry Run",
+            Action::Prune => "Prune",
+            Action::PageUp => "Page Up",
+            Action::PageDown => "Page Down",
+            Action::ScrollTop => "Top",
+            Action::ScrollBottom => "Bottom",
+            Action::Copy => "Copy",
+            Action::ToggleMark => "Mark",
+            Action::MarkMatching => "Mark Matching",
+            Action::RestartContainer => "Restart Container",
+            Action::RemoveContainer => "Remove Container",
+            Action::NextTab => "Next Tab",
+            Action::Snippets => "Snippets",
+            Action::BroadcastExec => "Broadcast Exec",
+            Action::ComparePackages => "Compare Packages",
+            Action::TailFile => "Tail File",
+            Action::EditLogFilters => "Log Filters",
+            Action::ShowGraph => "Graph",
+            Action::OpenSource => "Open Source",
+            Action::PortForward => "Port Forward",
+            Action::ShowForwards => "Forwards",
+            Action::ToggleMemoryCalculation => "Memory Calc",
+            Action::Probe => "HTTP Probe",
+            Action::ShowErrors => "Errors",
+            Action::ShowSharedVolumes => "Shared Volumes",
+            Action::EditEnv => "Edit Env",
+            Action::RecreateContainer => "Recreate",
+            Action::SwitchView => "Views 1-5",
         };
         let key = self.keys().first().unwrap();
         write!(f, "{} {}", key, str)
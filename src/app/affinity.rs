@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use crate::container_management::Container;
+
+/// Named volume or host path mounted by more than one container
+#[derive(Debug, Clone)]
+pub struct SharedMount {
+    /// Volume name, or the host path of a bind mount
+    pub source: String,
+    pub volume: bool,
+    pub users: Vec<MountUser>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MountUser {
+    pub container: String,
+    pub destination: String,
+    pub rw: bool,
+}
+
+impl SharedMount {
+    /// Containers mounting it read-write
+    pub fn writers(&self) -> usize {
+        self.users.iter().filter(|u| u.rw).count()
+    }
+
+    /// Several writers can corrupt files they do not expect to share
+    pub fn conflicting(&self) -> bool {
+        self.writers() > 1
+    }
+}
+
+/// Volumes and host paths shared between containers, the conflicting ones first
+pub fn shared_mounts(containers: &[Container]) -> Vec<SharedMount> {
+    let mut mounts: BTreeMap<(bool, String), Vec<MountUser>> = BTreeMap::new();
+    for c in containers {
+        for m in &c.mounts {
+            let key = match (&m.name, m.kind.as_str()) {
+                (Some(name), "volume") => (true, name.clone()),
+                (_, "bind") => (false, m.source.clone()),
+                _ => continue, // tmpfs and pipes are private to the container
+            };
+            mounts.entry(key).or_default().push(MountUser {
+                container: c.name.clone(),
+                destination: m.destination.clone(),
+                rw: m.rw,
+            });
+        }
+    }
+    let mut shared: Vec<SharedMount> = mounts
+        .into_iter()
+        .filter(|(_, users)| users.len() > 1)
+        .map(|((volume, source), mut users)| {
+            users.sort_by(|a, b| a.container.cmp(&b.container));
+            SharedMount {
+                source,
+                volume,
+                users,
+            }
+        })
+        .collect();
+    shared.sort_by_key(|s| !s.conflicting());
+    shared
+}
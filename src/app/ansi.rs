@@ -0,0 +1,127 @@
+use tui::style::{Color, Modifier, Style};
+
+const ESC: char = '\x1b';
+
+/// Split a line into its text segments and the style their SGR escape
+/// sequences give them. Other escape sequences are dropped.
+pub fn parse(line: &str) -> Vec<(String, Style)> {
+    let mut segments = Vec::new();
+    let mut style = Style::default();
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            text.push(c);
+            continue;
+        }
+        if chars.peek() != Some(&'[') {
+            // Two-character sequence, e.g. a charset selection
+            chars.next();
+            continue;
+        }
+        chars.next();
+        let mut params = String::new();
+        let mut command = None;
+        for c in chars.by_ref() {
+            if ('\x40'..='\x7e').contains(&c) {
+                command = Some(c);
+                break;
+            }
+            params.push(c);
+        }
+        if command == Some('m') {
+            if !text.is_empty() {
+                segments.push((std::mem::take(&mut text), style));
+            }
+            style = apply_sgr(style, &params);
+        }
+    }
+    if !text.is_empty() || segments.is_empty() {
+        segments.push((text, style));
+    }
+    segments
+}
+
+/// The text of a line without its escape sequences
+pub fn strip(line: &str) -> String {
+    if !line.contains(ESC) {
+        return line.to_string();
+    }
+    parse(line).into_iter().map(|(text, _)| text).collect()
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|p| if p.is_empty() { Ok(0) } else { p.parse::<u8>() });
+    while let Some(Ok(code)) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(basic_color(code - 30)),
+            90..=97 => style.fg(bright_color(code - 90)),
+            40..=47 => style.bg(basic_color(code - 40)),
+            100..=107 => style.bg(bright_color(code - 100)),
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(Ok(5)) => codes.next().and_then(Result::ok).map(Color::Indexed),
+                    Some(Ok(2)) => {
+                        let mut rgb = || codes.next().and_then(Result::ok);
+                        match (rgb(), rgb(), rgb()) {
+                            (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                match (code, color) {
+                    (38, Some(color)) => style.fg(color),
+                    (_, Some(color)) => style.bg(color),
+                    _ => style,
+                }
+            }
+            39 => Style { fg: None, ..style },
+            49 => Style { bg: None, ..style },
+            _ => style,
+        };
+    }
+    style
+}
+
+fn basic_color(index: u8) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(index: u8) -> Color {
+    match index {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
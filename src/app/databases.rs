@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use crate::config::DatabaseEngine;
+
+/// Connections, running and slow (active for more than 5s) queries and size
+/// of the default database, as `name value` lines
+const POSTGRES_COMMAND: &str = "\
+psql -U \"${POSTGRES_USER:-postgres}\" -d \"${POSTGRES_DB:-${POSTGRES_USER:-postgres}}\" -At -F ' ' -c \"\
+select 'connections', count(*) from pg_stat_activity \
+union all select 'active', count(*) from pg_stat_activity where state = 'active' \
+union all select 'slow', count(*) from pg_stat_activity where state = 'active' and now() - query_start > interval '5 seconds' \
+union all select 'max_connections', setting::bigint from pg_settings where name = 'max_connections' \
+union all select 'database_size', pg_database_size(current_database())\"";
+
+/// Global status counters as `name<TAB>value` lines
+const MYSQL_COMMAND: &str = "\
+MYSQL_PWD=\"${MYSQL_ROOT_PASSWORD:-$MARIADB_ROOT_PASSWORD}\" \
+$(command -v mysql || command -v mariadb) -uroot -N -B -e \"SHOW GLOBAL STATUS WHERE Variable_name IN \
+('Threads_connected', 'Threads_running', 'Slow_queries', 'Questions', 'Innodb_buffer_pool_bytes_data', 'Uptime')\"";
+
+/// `INFO` followed by the length of the slow log, as `name:value` lines
+const REDIS_COMMAND: &str = "\
+redis-cli ${REDIS_PASSWORD:+-a \"$REDIS_PASSWORD\" --no-auth-warning} INFO; \
+echo \"slowlog_len:$(redis-cli ${REDIS_PASSWORD:+-a \"$REDIS_PASSWORD\" --no-auth-warning} SLOWLOG LEN)\"";
+
+/// Number shown in the database tab
+pub struct Field {
+    /// Name in the output of the status command
+    pub key: &'static str,
+    pub label: &'static str,
+    /// The value is a size in bytes
+    pub bytes: bool,
+}
+
+const fn field(key: &'static str, label: &'static str, bytes: bool) -> Field {
+    Field { key, label, bytes }
+}
+
+const POSTGRES_FIELDS: [Field; 5] = [
+    field("connections", "Connections", false),
+    field("max_connections", "Max connections", false),
+    field("active", "Active queries", false),
+    field("slow", "Slow queries", false),
+    field("database_size", "Database size", true),
+];
+
+const MYSQL_FIELDS: [Field; 6] = [
+    field("Threads_connected", "Connections", false),
+    field("Threads_running", "Running threads", false),
+    field("Slow_queries", "Slow queries", false),
+    field("Questions", "Queries", false),
+    field("Innodb_buffer_pool_bytes_data", "Buffer pool data", true),
+    field("Uptime", "Uptime (s)", false),
+];
+
+const REDIS_FIELDS: [Field; 6] = [
+    field("connected_clients", "Connections", false),
+    field("blocked_clients", "Blocked clients", false),
+    field("used_memory", "Memory", true),
+    field("used_memory_peak", "Memory peak", true),
+    field("instantaneous_ops_per_sec", "Ops/s", false),
+    field("slowlog_len", "Slow log entries", false),
+];
+
+/// Default status command of an engine, run with `sh -c`
+pub fn command(engine: DatabaseEngine) -> &'static str {
+    match engine {
+        DatabaseEngine::Postgres => POSTGRES_COMMAND,
+        DatabaseEngine::Mysql => MYSQL_COMMAND,
+        DatabaseEngine::Redis => REDIS_COMMAND,
+    }
+}
+
+/// Numbers of an engine worth showing, in display order
+pub fn fields(engine: DatabaseEngine) -> &'static [Field] {
+    match engine {
+        DatabaseEngine::Postgres => &POSTGRES_FIELDS,
+        DatabaseEngine::Mysql => &MYSQL_FIELDS,
+        DatabaseEngine::Redis => &REDIS_FIELDS,
+    }
+}
+
+/// Parse the output of a status command, by name. Names and values are
+/// separated by a colon, a tab or a space depending on the engine.
+pub fn parse(output: &[String]) -> BTreeMap<String, String> {
+    output
+        .iter()
+        .filter_map(|line| line.trim().split_once([':', '\t', ' ']))
+        .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+        .collect()
+}
@@ -0,0 +1,68 @@
+use crate::container_management::{Container, ContainerStatus};
+use crate::io::IoEvent;
+
+/// Operation skipped in dry-run mode, with the engine API calls it would make
+#[derive(Debug, Clone)]
+pub struct DryRunCall {
+    /// Name of the container or volume it targets, or short id of the image
+    pub target: String,
+    pub calls: Vec<String>,
+}
+
+/// Engine API call removing an image or a volume, `None` for the other events
+pub fn removal_call(event: &IoEvent) -> Option<DryRunCall> {
+    let (target, call) = match event {
+        IoEvent::RemoveImage(id) => {
+            let id = id.trim_start_matches("sha256:");
+            let id = &id[..id.len().min(12)];
+            (id.to_string(), format!("DELETE /images/{}", id))
+        }
+        IoEvent::RemoveVolume(name) => (name.clone(), format!("DELETE /volumes/{}", name)),
+        _ => return None,
+    };
+    Some(DryRunCall {
+        target,
+        calls: vec![call],
+    })
+}
+
+/// Engine API calls made for a stop, pause, restart, recreation or removal
+/// of the container, `None` for the other events
+pub fn api_calls(event: &IoEvent, c: &Container) -> Option<Vec<String>> {
+    let id = &c.id[..c.id.len().min(12)];
+    let remove = format!("DELETE /containers/{}?force=true", id);
+    let stop = format!("POST /containers/{}/stop?t=10", id);
+    let calls = match event {
+        IoEvent::StopContainer(_) => match c.status {
+            ContainerStatus::Running => vec![stop],
+            ContainerStatus::Exited | ContainerStatus::Created => vec![remove],
+            _ => vec![format!("none, {} can not be stopped", c.status)],
+        },
+        IoEvent::PauseContainer(_) if matches!(c.status, ContainerStatus::Paused) => {
+            vec![format!("POST /containers/{}/unpause", id)]
+        }
+        IoEvent::PauseContainer(_) => vec![format!("POST /containers/{}/pause", id)],
+        IoEvent::RestartContainer(_) => vec![format!("POST /containers/{}/restart?t=10", id)],
+        IoEvent::RemoveContainer(_) => vec![remove],
+        IoEvent::RecreateContainer(_) => {
+            let running = matches!(c.status, ContainerStatus::Running);
+            let mut calls = vec![format!("GET /containers/{}/json", id)];
+            if running {
+                calls.push(stop);
+            }
+            calls.push(format!(
+                "POST /containers/{}/rename?name={}_bctop_old",
+                id, c.name
+            ));
+            calls.push(format!("POST /containers/create?name={}", c.name));
+            calls.push("POST /networks/<network>/connect for each extra network".to_string());
+            calls.push(format!("DELETE /containers/{}", id));
+            if running {
+                calls.push("POST /containers/<new id>/start".to_string());
+            }
+            calls
+        }
+        _ => return None,
+    };
+    Some(calls)
+}
@@ -0,0 +1,30 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+
+use super::ansi;
+
+/// Write log lines to a file, without their escape sequences, returning how
+/// many were written. A leading `~/` stands for the home directory.
+pub fn write_logs<'a>(
+    path: &str,
+    lines: impl IntoIterator<Item = &'a String>,
+) -> io::Result<usize> {
+    let mut file = BufWriter::new(File::create(expand(path))?);
+    let mut count = 0;
+    for line in lines {
+        writeln!(file, "{}", ansi::strip(line).trim_end_matches(['\r', '\n']))?;
+        count += 1;
+    }
+    file.flush()?;
+    Ok(count)
+}
+
+fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
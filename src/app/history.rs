@@ -0,0 +1,42 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Number of samples kept per container, about ten minutes of refreshes
+const HISTORY_LIMIT: usize = 600;
+
+/// Resource usage of a container at one refresh
+#[derive(Debug, Clone, Copy)]
+pub struct StatsSample {
+    pub at: Instant,
+    /// CPU percentage
+    pub cpu: f32,
+    /// Memory usage in bytes
+    pub memory: f32,
+}
+
+/// Rolling resource usage of every container, by container id
+#[derive(Debug, Default)]
+pub struct StatsHistory(HashMap<String, VecDeque<StatsSample>>);
+
+impl StatsHistory {
+    pub fn push(&mut self, id: &str, sample: StatsSample) {
+        let samples = self.0.entry(id.to_string()).or_default();
+        samples.push_back(sample);
+        if samples.len() > HISTORY_LIMIT {
+            samples.pop_front();
+        }
+    }
+
+    /// Samples of a container, the oldest first
+    pub fn get(&self, id: &str) -> Option<&VecDeque<StatsSample>> {
+        self.0.get(id)
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.0.remove(id);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
@@ -0,0 +1,39 @@
+use serde_json::{Map, Value};
+
+use crate::config::JsonLogs;
+
+/// Lines showing a JSON log line the way the mode asks for, `None` for the
+/// lines that are not JSON objects or when they are shown as they are
+pub fn render(line: &str, mode: JsonLogs, fields: &[String]) -> Option<Vec<String>> {
+    if mode == JsonLogs::Raw {
+        return None;
+    }
+    let trimmed = line.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    let object: Map<String, Value> = serde_json::from_str(trimmed).ok()?;
+    match mode {
+        JsonLogs::Raw => None,
+        JsonLogs::Pretty => {
+            let pretty = serde_json::to_string_pretty(&object).ok()?;
+            Some(pretty.lines().map(str::to_string).collect())
+        }
+        JsonLogs::Fields => {
+            let values: Vec<String> = fields
+                .iter()
+                .filter_map(|f| object.get(f))
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                })
+                .collect();
+            // Keep the line when none of the fields is there
+            if values.is_empty() {
+                None
+            } else {
+                Some(vec![values.join(" ")])
+            }
+        }
+    }
+}
@@ -1,19 +1,266 @@
 pub mod actions;
+pub mod affinity;
+pub mod ansi;
+pub mod databases;
+pub mod dry_run;
+pub mod export;
+pub mod history;
+pub mod json_logs;
 use crate::container_management;
+pub mod packages;
+pub mod search;
 pub mod state;
+pub mod totals;
 pub mod ui;
+pub mod watchdog;
 
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::clipboard;
+use crate::config::{
+    has_label, ByteUnits, Column, Config, ContainerFilter, DatabaseEngine, DatabaseStats,
+    EngineProfile, KeyPreset, MemoryCalculation, Snippet, SortColumn, SortDirection, SortOrder,
+    Thresholds, SPLIT_RATIO_MAX, SPLIT_RATIO_MIN,
+};
+use crate::env_overrides::{EnvOverride, EnvOverrides};
+use crate::log_filters::{CompiledLogFilter, LogFilter, LogFilters};
+use crate::notes::Notes;
+use crate::opener;
+use crate::telemetry;
 use crate::{inputs::key::Key, io::IoEvent};
 use actions::{Action, Actions};
-use log::debug;
-use state::AppState;
+use chrono::{DateTime, Local, Utc};
+use history::{StatsHistory, StatsSample};
+use log::{debug, info, warn};
+use regex::Regex;
+use state::{AppState, InspectTab, KioskPanel, View};
+use tui::layout::Rect;
+use unicode_width::UnicodeWidthStr;
+use watchdog::{Trigger, Watchdog};
+
+use self::container_management::{
+    Container, ContainerDetails, ContainerManagement, ContainerStatus, CrashReport, EngineEvent,
+    EngineSummary, ExecResult, HealthStatus, ImageDetails, ImageSummary, LogLine, LogRange,
+    NetworkSummary, Notification, NotificationLevel, PortForward, ProbeResult, ProcessInfo,
+    VolumeSummary,
+};
+use self::dry_run::DryRunCall;
+
+/// Maximum number of health transitions kept per container
+const HEALTH_HISTORY_LIMIT: usize = 500;
+/// Results kept per HTTP probe
+const PROBE_RESULTS_LIMIT: usize = 60;
+/// Number of containers remembered by the recent containers switcher
+const RECENT_CONTAINERS_LIMIT: usize = 5;
+/// Longest gap between two samples credited to availability, so pauses of
+/// the monitoring (e.g. while reading logs) don't count as observed time
+const AVAILABILITY_MAX_SAMPLE: Duration = Duration::from_secs(5);
+/// How often the processes tab of the detail view is refreshed
+const PROCESSES_REFRESH: Duration = Duration::from_secs(2);
+/// Time between two reads of the database status while its tab is shown
+const DATABASE_REFRESH: Duration = Duration::from_secs(10);
+/// Time an outcome stays in the status bar
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(5);
+/// Time an operation is shown as in progress when its outcome never comes
+const PROGRESS_TIMEOUT: Duration = Duration::from_secs(60);
+/// Errors kept for the error panel
+const ERRORS_LIMIT: usize = 100;
+/// Period of the refresh of the images, volumes or networks view
+const RESOURCES_REFRESH: Duration = Duration::from_secs(5);
+/// Columns the log lines move by when scrolled sideways
+const LOG_SCROLL_STEP: usize = 8;
+/// Engine events kept for the events view
+const ENGINE_EVENTS_LIMIT: usize = 500;
+/// Percentage points the table grows or shrinks by in the split view
+const SPLIT_RATIO_STEP: u16 = 5;
+/// Log lines kept for the split view
+const PREVIEW_LOGS_LIMIT: usize = 500;
+/// Line between two groups of matches that are apart in the stream
+const LOG_GAP: &str = "--";
+/// Command reading the clock of a container
+const CLOCK_COMMAND: &str = "date +%s";
+/// Time the startup summary stays once the engine answered
+const SPLASH_DURATION: Duration = Duration::from_secs(2);
+/// Time the startup summary stays at most, waiting for the engine
+const SPLASH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Health state of a container from a point in time onwards
+#[derive(Debug, Clone)]
+pub struct HealthTransition {
+    pub at: DateTime<Utc>,
+    pub health: Option<HealthStatus>,
+}
+
+/// Time a service has been observed up during the session
+#[derive(Debug, Clone, Default)]
+pub struct Availability {
+    pub up: Duration,
+    pub total: Duration,
+}
+
+impl Availability {
+    /// Share of the observed time the service was up, `None` before the first sample
+    pub fn ratio(&self) -> Option<f32> {
+        if self.total.is_zero() {
+            None
+        } else {
+            Some(self.up.as_secs_f32() / self.total.as_secs_f32())
+        }
+    }
+}
+
+/// Last status read from the database running in a container
+#[derive(Debug, Clone)]
+pub struct DatabaseReport {
+    pub engine: DatabaseEngine,
+    /// Parsed output of the status command, by name
+    pub values: BTreeMap<String, String>,
+    /// Output of the status command when it failed
+    pub error: Option<String>,
+}
+
+/// Process placed in the process tree of a container
+pub struct ProcessRow<'a> {
+    /// Number of ancestors shown above it
+    pub depth: usize,
+    pub process: &'a ProcessInfo,
+    pub children: usize,
+}
+
+/// Row of the monitoring table
+pub enum TableRow<'a> {
+    /// Swarm stack or compose project, with all its visible containers
+    Group {
+        stack: String,
+        containers: Vec<&'a Container>,
+        collapsed: bool,
+    },
+    Container(&'a Container),
+}
+
+/// Snippets popup, listing the snippets that apply to a container
+#[derive(Debug, Clone)]
+pub struct SnippetPicker {
+    pub container: String,
+    pub snippets: Vec<Snippet>,
+    pub selected: usize,
+    /// Waiting for the user to confirm running the selected snippet
+    pub confirming: bool,
+}
+
+/// Popup showing the output of a command run in one or many containers
+#[derive(Debug, Clone)]
+pub struct ExecView {
+    pub title: String,
+    /// Containers the command runs in, by id
+    pub containers: Vec<String>,
+    /// Results received so far, by container id
+    pub results: HashMap<String, ExecResult>,
+    pub scroll: u16,
+}
+
+/// Failure of the engine or of an operation, kept until dismissed
+#[derive(Debug, Clone)]
+pub struct ReportedError {
+    /// Last time it happened
+    pub at: DateTime<Utc>,
+    pub message: String,
+    /// Times it happened in a row
+    pub count: usize,
+}
+
+/// Kind of engine object a confirmation targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Container,
+    Image,
+    Volume,
+}
+
+impl Resource {
+    pub fn noun(&self) -> &'static str {
+        match self {
+            Resource::Container => "container",
+            Resource::Image => "image",
+            Resource::Volume => "volume",
+        }
+    }
+}
+
+/// Stop, restart or removal of containers, or removal of images or volumes,
+/// waiting for the user to confirm it
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    /// Capitalized verb, e.g. `Stop`
+    pub verb: &'static str,
+    pub resource: Resource,
+    pub event: fn(String) -> IoEvent,
+    /// Container or image ids, or volume names
+    pub targets: Vec<String>,
+    /// Phrase to type instead of `y` when protected containers are among the targets
+    pub phrase: Option<String>,
+    /// What was typed so far
+    pub typed: String,
+}
 
-use self::container_management::{Container, ContainerManagement};
+/// Single line popup asking for some text before running an action
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    /// Command to run in several containers
+    Broadcast,
+    /// Path of a file to follow in the selected container
+    TailFile,
+    /// `local:container` ports to forward, or a single port used for both
+    PortForward,
+    /// Selector of the containers to mark, like `label:team=payments`
+    MarkMatching,
+    /// File to write the logs of the viewer to
+    ExportLogs,
+    /// Period of the logs to show, like `last 15m`
+    LogRange,
+    /// Command to run in the selected container with the terminal attached
+    Exec,
+}
+
+/// HTTP probe of a service and its latest results
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub url: String,
+    /// The oldest first
+    pub results: VecDeque<ProbeResult>,
+}
+
+/// Popup comparing the packages installed in two containers
+#[derive(Debug, Clone)]
+pub struct PackageComparison {
+    /// Container ids, changes go from the first one to the second one
+    pub containers: [String; 2],
+    /// Package listings received so far, by container id
+    pub results: HashMap<String, ExecResult>,
+    pub scroll: u16,
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppReturn {
     Exit,
     Continue,
+    /// Leave the terminal to `$PAGER` to show the file, then draw again
+    Page(PathBuf),
+    /// Leave the terminal to a command run in a container until it exits or
+    /// is detached from, then draw again
+    Session {
+        container: String,
+        command: String,
+    },
 }
 
 pub struct App {
@@ -23,44 +270,628 @@ pub struct App {
     /// Contextual actions
     actions: Actions,
     state: AppState,
+    /// Text being edited in the notes popup of the inspected container
+    notes_editor: Option<String>,
+    notes: Notes,
+    /// Text being edited in the env overrides popup of the inspected container
+    env_editor: Option<String>,
+    env_overrides: EnvOverrides,
+    /// Container to inspect again on the next tick, replacing the one shown
+    pending_inspect: Option<String>,
+    /// Container whose logs are opened as soon as it is listed
+    startup_logs: Option<String>,
+    images: Vec<ImageSummary>,
+    volumes: Vec<VolumeSummary>,
+    networks: Vec<NetworkSummary>,
+    /// Events of the engine since the events view was first opened, the latest last
+    engine_events: VecDeque<EngineEvent>,
+    /// Engine events received, including the ones dropped from the list
+    engine_events_total: usize,
+    /// Selected row of the images, volumes, networks or events view
+    resource_selected: usize,
+    resources_requested_at: Instant,
+    watchdog: Watchdog,
+    /// Columns of the table scrolled out on the left
+    column_offset: usize,
+    /// Size of the terminal, to know whether columns are hidden on the right
+    /// and how long a page of logs is
+    screen: Rect,
+    /// First `g` of a `gg` of the vim preset
+    pending_g: bool,
+    /// Container whose logs are followed for the split view
+    preview_container: Option<String>,
+    preview_logs: VecDeque<String>,
+    snippet_picker: Option<SnippetPicker>,
+    prompt: Option<Prompt>,
+    confirmation: Option<Confirmation>,
+    /// Latest notification and when it came
+    notification: Option<(Notification, Instant)>,
+    /// Errors not dismissed yet, the latest last
+    errors: VecDeque<ReportedError>,
+    /// Scroll of the error panel while it is open
+    errors_view: Option<u16>,
+    engine_summary: Option<EngineSummary>,
+    /// The startup summary is shown until then, unless a key is pressed
+    splash_until: Option<Instant>,
+    /// Destructive operations only list the API calls they would make
+    dry_run: bool,
+    /// Operations skipped by the dry run, shown in a popup until dismissed
+    dry_run_calls: Vec<DryRunCall>,
+    /// Active port forwards, by local port
+    forwards: Vec<PortForward>,
+    /// Selected forward while the forwards popup is open
+    forwards_view: Option<usize>,
+    /// Running HTTP probes, by service key
+    probes: HashMap<String, Probe>,
+    exec_view: Option<ExecView>,
+    package_comparison: Option<PackageComparison>,
+    /// Show the application's own logs on top of the current view
+    debug_console: bool,
+    config: Config,
+    sort: SortOrder,
+    /// Index of the saved filter in use, if any
+    filter: Option<usize>,
+    filter_regex: Option<Regex>,
+    /// Why the `name_regex` of the filter in use could not be compiled
+    filter_error: Option<String>,
+    /// Compiled `name_rules` of the config
+    name_rules: Vec<(Regex, String)>,
+    /// Index of the connection profile in use, `None` for the local engine
+    profile: Option<usize>,
+    /// Profile in use before the last switch, restored when the new engine
+    /// can not be reached
+    previous_profile: Option<usize>,
+    /// Profile matching the host name of the engine, used when none is selected
+    auto_profile: Option<usize>,
+    /// Architecture of the engine host, to spot emulated containers
+    engine_arch: Option<String>,
+    /// When the current kiosk panel was shown
+    kiosk_panel_since: Instant,
     selected_container: Option<String>,
+    /// Project row selected in the monitoring table, exclusive with `selected_container`
+    selected_group: Option<String>,
+    /// Projects whose containers are hidden in the monitoring table
+    collapsed_groups: HashSet<String>,
+    /// Containers marked for batch operations, by id
+    marked: HashSet<String>,
+    /// Health transitions recorded per container id
+    health_history: HashMap<String, Vec<HealthTransition>>,
+    stats_history: StatsHistory,
+    /// When the restart count of a container went up, by container id
+    restarts: HashMap<String, VecDeque<Instant>>,
+    /// Last crash per service key, surviving the recreation of the container
+    crashes: HashMap<String, CrashReport>,
+    /// Availability per service key
+    availability: HashMap<String, Availability>,
+    /// Last time each container was sampled, by container id
+    last_sample: HashMap<String, Instant>,
+    /// Digest and provenance of the inspected images, by image reference
+    image_details: HashMap<String, ImageDetails>,
+    /// Last `inspect` of the container shown in the detail view
+    container_details: Option<ContainerDetails>,
+    /// Lines scrolled in the current tab of the detail view, or the selected
+    /// variable in the environment tab
+    inspect_scroll: u16,
+    /// Last process listing of the inspected container, by container id
+    processes: HashMap<String, Vec<ProcessInfo>>,
+    processes_requested_at: Instant,
+    /// Status command sent to a database container, by container id
+    database_commands: HashMap<String, String>,
+    database_reports: HashMap<String, DatabaseReport>,
+    database_requested_at: Instant,
+    /// Host time when the clock of a container was asked, by container id
+    clock_requested_at: HashMap<String, i64>,
+    /// Seconds the clock of a container is ahead of the host, `None` when it
+    /// could not be read
+    clock_skew: HashMap<String, Option<i64>>,
+    /// Text narrowing down the environment tab
+    env_search: Option<String>,
+    /// Keys go to `env_search` rather than to the actions
+    env_search_typing: bool,
     // Logging attributes
-    logs: Vec<String>,
+    logs: Vec<LogLine>,
     log_position: usize, // Reverse index from where to start taking log lines
+    /// Lines received while scrolled back, merged when returning to the bottom
+    pending_logs: Vec<LogLine>,
+    /// Only the logs of this period are shown, all of them when unset
+    log_range: Option<LogRange>,
+    /// Last lines fetched, all of them when unset
+    log_tail: Option<usize>,
+    search: Option<String>,
+    /// Keys go to `search`, always while it is open unless using the vim preset
+    search_typing: bool,
+    /// Only list the lines matching the search, with some context
+    matches_only: bool,
+    /// Searches the shown lines must all match, stacked with ctrl+g
+    log_grep: Vec<String>,
+    /// First column of the log lines shown while they are not wrapped
+    log_offset: usize,
+    log_streams: LogStreams,
+    /// Text typed to narrow down the monitoring table
+    container_search: Option<String>,
+    /// Last searched text, offered again when opening the search
+    last_search: Option<String>,
+    log_filters: LogFilters,
+    /// Filter of the service whose logs are shown, applied to incoming lines
+    log_filter: Option<CompiledLogFilter>,
+    /// Text being edited in the log filters popup
+    log_filter_editor: Option<String>,
+    /// Log viewer state of the containers whose logs were left, by container id
+    log_views: HashMap<String, LogView>,
+    /// Line to show at the bottom again once it arrives, for a revisited
+    /// container
+    log_anchor: Option<DateTime<Utc>>,
+    /// Containers whose logs were opened, the most recent first
+    recent_containers: VecDeque<String>,
+}
+
+/// Output streams of the containers shown by the log viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogStreams {
+    #[default]
+    Both,
+    Stdout,
+    Stderr,
+}
+
+impl LogStreams {
+    fn next(self) -> Self {
+        match self {
+            LogStreams::Both => LogStreams::Stdout,
+            LogStreams::Stdout => LogStreams::Stderr,
+            LogStreams::Stderr => LogStreams::Both,
+        }
+    }
+
+    fn shows(self, line: &LogLine) -> bool {
+        match self {
+            LogStreams::Both => true,
+            LogStreams::Stdout => !line.stderr,
+            LogStreams::Stderr => line.stderr,
+        }
+    }
+}
+
+/// Log viewer state remembered when leaving the logs of a container
+#[derive(Debug, Clone, Default)]
+struct LogView {
+    /// Time of the bottom line of the view, `None` when following
+    anchor: Option<DateTime<Utc>>,
     search: Option<String>,
+    last_search: Option<String>,
+    grep: Vec<String>,
+    streams: LogStreams,
 }
 
 impl App {
-    pub fn new(io_tx: tokio::sync::mpsc::Sender<IoEvent>) -> Self {
-        let state = AppState::default();
+    pub fn new(
+        io_tx: tokio::sync::mpsc::Sender<IoEvent>,
+        config: Config,
+        profile: Option<usize>,
+    ) -> Self {
+        let state = if config.kiosk.enabled {
+            AppState::Kiosk {
+                panel: KioskPanel::Leaderboard,
+            }
+        } else {
+            AppState::default()
+        };
         let actions = state.get_actions();
         let containers = Vec::new();
+        let sort = config.sort;
+        let name_rules = config
+            .name_rules
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(re) => Some((re, rule.replace.clone())),
+                Err(e) => {
+                    warn!("Invalid name rule {}: {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
 
         Self {
+            name_rules,
             containers,
             io_tx,
             actions,
             state,
+            notes_editor: None,
+            notes: Notes::load(),
+            env_editor: None,
+            env_overrides: EnvOverrides::load(),
+            pending_inspect: None,
+            startup_logs: None,
+            images: Vec::new(),
+            volumes: Vec::new(),
+            networks: Vec::new(),
+            engine_events: VecDeque::new(),
+            engine_events_total: 0,
+            resource_selected: 0,
+            resources_requested_at: Instant::now(),
+            watchdog: Watchdog::default(),
+            column_offset: 0,
+            screen: Rect::default(),
+            pending_g: false,
+            preview_container: None,
+            preview_logs: VecDeque::new(),
+            snippet_picker: None,
+            prompt: None,
+            confirmation: None,
+            notification: None,
+            errors: VecDeque::new(),
+            errors_view: None,
+            engine_summary: None,
+            splash_until: Some(Instant::now() + SPLASH_TIMEOUT),
+            dry_run: config.dry_run,
+            dry_run_calls: Vec::new(),
+            forwards: Vec::new(),
+            forwards_view: None,
+            probes: HashMap::new(),
+            exec_view: None,
+            package_comparison: None,
+            debug_console: false,
+            config,
+            sort,
+            filter: None,
+            filter_regex: None,
+            filter_error: None,
+            profile,
+            previous_profile: profile,
+            auto_profile: None,
+            engine_arch: None,
+            kiosk_panel_since: Instant::now(),
             selected_container: None,
+            selected_group: None,
+            collapsed_groups: HashSet::new(),
+            marked: HashSet::new(),
+            health_history: HashMap::new(),
+            stats_history: StatsHistory::default(),
+            restarts: HashMap::new(),
+            crashes: HashMap::new(),
+            availability: HashMap::new(),
+            last_sample: HashMap::new(),
+            image_details: HashMap::new(),
+            container_details: None,
+            inspect_scroll: 0,
+            processes: HashMap::new(),
+            processes_requested_at: Instant::now(),
+            database_commands: HashMap::new(),
+            database_reports: HashMap::new(),
+            database_requested_at: Instant::now(),
+            clock_requested_at: HashMap::new(),
+            clock_skew: HashMap::new(),
+            env_search: None,
+            env_search_typing: false,
             logs: Vec::new(),
             log_position: 0,
+            pending_logs: Vec::new(),
+            log_range: None,
+            log_tail: None,
             search: None,
+            search_typing: false,
+            matches_only: false,
+            log_grep: Vec::new(),
+            log_offset: 0,
+            container_search: None,
+            last_search: None,
+            log_filters: LogFilters::load(),
+            log_filter: None,
+            log_streams: LogStreams::default(),
+            log_filter_editor: None,
+            log_views: HashMap::new(),
+            log_anchor: None,
+            recent_containers: VecDeque::new(),
+        }
+    }
+
+    /// Start with the container filter filled in, as if typed after `/`
+    pub fn start_filtered(&mut self, pattern: String) {
+        self.container_search = Some(pattern);
+    }
+
+    /// Start in dry-run mode whatever the config says
+    pub fn start_dry_run(&mut self) {
+        self.dry_run = true;
+    }
+
+    /// Open the logs of the container with this name, or whose id starts with
+    /// it, once the engine lists it
+    pub fn start_logging(&mut self, name: String) {
+        let waiting = Notification::progress(format!("Waiting for {}…", name));
+        self.notification = Some((waiting, Instant::now()));
+        self.startup_logs = Some(name);
+    }
+
+    /// Handle a left click: on a row of the container table it selects the
+    /// row, on an action of the help bar it triggers the action
+    pub async fn click(&mut self, column: u16, row: u16, size: Rect) -> AppReturn {
+        if self.popup_open() || self.state.is_kiosk() {
+            return AppReturn::Continue;
         }
+        // Tab bar on the first row, titles padded by a space and split by a divider
+        if row == 0 {
+            let mut start = 1;
+            for (i, view) in View::ALL.iter().enumerate() {
+                let end = start + format!("{} {}", i + 1, view).chars().count() as u16;
+                if column + 1 >= start && column <= end {
+                    self.switch_view(*view).await;
+                    break;
+                }
+                start = end + 3;
+            }
+            return AppReturn::Continue;
+        }
+        // The help bar takes the last two rows, its text is on the last one
+        if row + 1 == size.height {
+            let mut start = 0;
+            let mut clicked = None;
+            for action in self.actions.actions() {
+                let end = start + action.to_string().chars().count() as u16;
+                if column >= start && column < end {
+                    clicked = Some(action.keys()[0]);
+                    break;
+                }
+                start = end + " | ".len() as u16;
+            }
+            return match clicked {
+                Some(key) => self.do_action(key).await,
+                None => AppReturn::Continue,
+            };
+        }
+        if self.state.is_monitoring() {
+            // Below the tab bar, the top border, the header and its margin, above
+            // the totals
+            let top = 4;
+            let mut table_height = size.height.saturating_sub(3);
+            if self.config.split_logs {
+                table_height = ui::split_table_height(table_height, self.config.split_ratio());
+            }
+            let visible = table_height.saturating_sub(4) as usize;
+            if row < top || row >= top + visible as u16 {
+                return AppReturn::Continue;
+            }
+            // The table scrolls just enough to show the selected row
+            let offset = match self.selected_row_index() {
+                Some(i) if i >= visible => i + 1 - visible,
+                _ => 0,
+            };
+            let index = offset + (row - top) as usize;
+            if index < self.table_rows().len() {
+                self.select_row(index);
+            }
+        }
+        AppReturn::Continue
+    }
+
+    /// Move the border between the table and the logs, up to shrink the table
+    fn resize_split(&mut self, key: Key) {
+        if !self.config.split_logs {
+            return;
+        }
+        let ratio = self.config.split_ratio();
+        self.config.split_ratio = if key == Key::CtrlUp {
+            ratio.saturating_sub(SPLIT_RATIO_STEP)
+        } else {
+            ratio + SPLIT_RATIO_STEP
+        }
+        .clamp(SPLIT_RATIO_MIN, SPLIT_RATIO_MAX);
+        if let Err(e) = self.config.save() {
+            warn!("Error saving config: {}", e);
+        }
+    }
+
+    /// Scroll the columns of the table, the service staying in place
+    fn scroll_columns(&mut self, key: Key) {
+        if self.state.is_logging() {
+            self.scroll_log_columns(key);
+            return;
+        }
+        if key == Key::Left {
+            self.column_offset = self.column_offset.saturating_sub(1);
+            return;
+        }
+        let (_, _, hidden) =
+            ui::scrolled_columns(self.columns(), self.screen.width, self.column_offset);
+        if hidden > 0 {
+            self.column_offset += 1;
+        }
+    }
+
+    /// Scroll the log lines sideways while they are not wrapped, up to the end
+    /// of the longest one
+    fn scroll_log_columns(&mut self, key: Key) {
+        if self.config.log_viewer.wrap {
+            return;
+        }
+        if key == Key::Left {
+            self.log_offset = self.log_offset.saturating_sub(LOG_SCROLL_STEP);
+            return;
+        }
+        let longest = self
+            .shown_logs()
+            .iter()
+            .map(|l| ansi::strip(l).width())
+            .max()
+            .unwrap_or(0);
+        if self.log_offset + LOG_SCROLL_STEP < longest {
+            self.log_offset += LOG_SCROLL_STEP;
+        }
+    }
+
+    pub fn log_offset(&self) -> usize {
+        self.log_offset
+    }
+
+    /// A popup or a text input is taking the keys
+    fn popup_open(&self) -> bool {
+        self.exec_view.is_some()
+            || self.package_comparison.is_some()
+            || self.errors_view.is_some()
+            || !self.dry_run_calls.is_empty()
+            || self.confirmation.is_some()
+            || self.snippet_picker.is_some()
+            || self.prompt.is_some()
+            || self.forwards_view.is_some()
+            || self.log_filter_editor.is_some()
+            || self.search.is_some()
+            || self.env_search_typing
+            || self.container_search.is_some()
+            || self.notes_editor.is_some()
+            || self.env_editor.is_some()
     }
 
     /// Handle a user action
     pub async fn do_action(&mut self, key: Key) -> AppReturn {
-        if self.search().is_some() {
-            if let Some(c) = key.get_char() {
+        if self.splash_visible() {
+            self.splash_until = None;
+            return AppReturn::Continue;
+        }
+        if self.exec_view.is_some() {
+            self.scroll_exec_view(key);
+            return AppReturn::Continue;
+        }
+        if let Some(comparison) = self.package_comparison.as_mut() {
+            match key {
+                Key::Esc | Key::Char('q') => self.package_comparison = None,
+                Key::Up => comparison.scroll = comparison.scroll.saturating_sub(1),
+                Key::Down => comparison.scroll = comparison.scroll.saturating_add(1),
+                _ => {}
+            }
+            return AppReturn::Continue;
+        }
+        if !self.dry_run_calls.is_empty() {
+            if let Key::Esc | Key::Char('q') | Key::Enter = key {
+                self.dry_run_calls.clear();
+            }
+            return AppReturn::Continue;
+        }
+        if let Some(scroll) = self.errors_view {
+            match key {
+                Key::Esc | Key::Char('q') | Key::Char('E') => self.errors_view = None,
+                Key::Char('c') => {
+                    self.errors.clear();
+                    self.errors_view = None;
+                }
+                Key::Up => self.errors_view = Some(scroll.saturating_sub(1)),
+                Key::Down => self.errors_view = Some(scroll.saturating_add(1)),
+                _ => {}
+            }
+            return AppReturn::Continue;
+        }
+        if self.confirmation.is_some() {
+            self.answer_confirmation(key).await;
+            return AppReturn::Continue;
+        }
+        if self.snippet_picker.is_some() {
+            self.pick_snippet(key).await;
+            return AppReturn::Continue;
+        }
+        if self.prompt.is_some() {
+            return self.edit_prompt(key).await;
+        }
+        if self.forwards_view.is_some() {
+            self.browse_forwards(key).await;
+            return AppReturn::Continue;
+        }
+        if self.log_filter_editor.is_some() {
+            self.edit_log_filter(key).await;
+            return AppReturn::Continue;
+        }
+        if self.search().is_some() && self.search_typing {
+            // Control keys stay available to the actions while searching
+            if let Key::Char(c) = key {
                 self.search = Some(format!("{}{}", self.search().as_ref().unwrap(), c));
                 return AppReturn::Continue;
             }
         }
+        if self.env_search_typing {
+            if let (Some(text), Some(c)) = (self.env_search.as_mut(), key.get_char()) {
+                text.push(c);
+                self.inspect_scroll = 0;
+                return AppReturn::Continue;
+            }
+        }
+        if let Some(text) = self.container_search.as_mut() {
+            if let Some(c) = key.get_char() {
+                text.push(c);
+                self.select_visible_container();
+                return AppReturn::Continue;
+            }
+        }
+        if self.notes_editor.is_some() {
+            self.edit_notes(key);
+            return AppReturn::Continue;
+        }
+        if self.env_editor.is_some() {
+            self.edit_env(key);
+            return AppReturn::Continue;
+        }
+        let key = match self.vim_motion(key) {
+            Some(key) => key,
+            None => return AppReturn::Continue,
+        };
+        // Edit the search again once done typing it
+        if key == Key::Char('/') && self.search.is_some() && !self.search_typing {
+            self.search_typing = true;
+            return AppReturn::Continue;
+        }
         if let Some(action) = self.actions.find(key) {
+            telemetry::record(
+                "action",
+                SystemTime::now(),
+                None,
+                &[("action", &action.to_string())],
+            );
+            if *action == Action::ToggleDebugConsole {
+                self.debug_console = !self.debug_console;
+                return AppReturn::Continue;
+            }
+            if *action == Action::ShowErrors {
+                self.errors_view = Some(0);
+                return AppReturn::Continue;
+            }
+            if *action == Action::SwitchView {
+                if let Some(view) = key.get_char().and_then(View::from_key) {
+                    self.switch_view(view).await;
+                }
+                return AppReturn::Continue;
+            }
+            if *action == Action::ResizeSplit {
+                self.resize_split(key);
+                return AppReturn::Continue;
+            }
+            if *action == Action::ScrollColumns {
+                self.scroll_columns(key);
+                return AppReturn::Continue;
+            }
             if self.state.is_monitoring() {
                 self.do_state_monitoring_actions(*action).await
             } else if self.state.is_logging() {
                 self.do_state_logging_actions(*action).await
+            } else if self.state.is_inspecting() {
+                self.do_state_inspecting_actions(*action).await
+            } else if self.state.is_resource_view() {
+                self.do_state_resource_actions(*action)
+            } else if self.state.is_start_order()
+                || self.state.is_graph()
+                || self.state.is_shared_volumes()
+            {
+                match action {
+                    Action::Quit => {
+                        self.state = AppState::Monitoring;
+                        self.actions = self.state.get_actions();
+                        AppReturn::Continue
+                    }
+                    _ => AppReturn::Continue,
+                }
+            } else if self.state.is_kiosk() {
+                // Read-only display: the only thing to do is leaving
+                match action {
+                    Action::Quit => AppReturn::Exit,
+                    _ => AppReturn::Continue,
+                }
             } else {
                 AppReturn::Continue
             }
@@ -71,19 +902,62 @@ impl App {
 
     async fn do_state_monitoring_actions(&mut self, action: Action) -> AppReturn {
         match action {
-            Action::Quit => AppReturn::Exit,
+            Action::Quit => {
+                if self.container_search.is_some() {
+                    self.container_search = None;
+                    return AppReturn::Continue;
+                }
+                AppReturn::Exit
+            }
+            Action::FilterContainers => {
+                if self.container_search.is_none() {
+                    self.container_search = Some(String::new());
+                }
+                AppReturn::Continue
+            }
+            Action::Remove => {
+                if let Some(text) = self.container_search.as_mut() {
+                    text.pop();
+                    self.select_visible_container();
+                }
+                AppReturn::Continue
+            }
             Action::ShowLogs => {
+                if let Some(stack) = self.selected_group.clone() {
+                    if !self.collapsed_groups.remove(&stack) {
+                        self.collapsed_groups.insert(stack);
+                    }
+                    return AppReturn::Continue;
+                }
                 if self.selected_container.is_none() {
                     return AppReturn::Continue; // No container selected, do nothing
                 }
-                self.state = AppState::Logging {
-                    container: self.selected_container.clone().unwrap(),
-                };
-                self.actions = self.state.get_actions();
-                self.dispatch(IoEvent::ShowLogs(self.selected_container.clone().unwrap()))
+                self.open_logs(self.selected_container.clone().unwrap())
                     .await;
                 AppReturn::Continue
             }
+            Action::ShowStackLogs => {
+                let stack = match &self.selected_group {
+                    Some(stack) => Some(stack.clone()),
+                    None => self
+                        .selected_container
+                        .as_ref()
+                        .and_then(|id| self.containers.iter().find(|c| c.id == *id))
+                        .map(|c| c.stack()),
+                };
+                if let Some(stack) = stack.filter(|s| !s.is_empty()) {
+                    self.open_stack_logs(stack).await;
+                }
+                AppReturn::Continue
+            }
+            Action::SwitchRecent => {
+                if let Some(id) = self.recent_containers.front().cloned() {
+                    self.selected_container = Some(id.clone());
+                    self.selected_group = None;
+                    self.open_logs(id).await;
+                }
+                AppReturn::Continue
+            }
             Action::Next => {
                 self.next();
                 AppReturn::Continue
@@ -92,24 +966,228 @@ impl App {
                 self.previous();
                 AppReturn::Continue
             }
-            Action::StopContainer => {
-                if self.selected_container.is_none() {
-                    return AppReturn::Continue; // No container selected, do nothing
+            Action::Snippets => {
+                let container = match self.selected_container.clone() {
+                    Some(c) => c,
+                    None => return AppReturn::Continue,
+                };
+                let snippets = self.snippets_for(&container);
+                if snippets.is_empty() {
+                    debug!("No snippet for container {}", container);
+                } else {
+                    self.snippet_picker = Some(SnippetPicker {
+                        container,
+                        snippets,
+                        selected: 0,
+                        confirming: false,
+                    });
                 }
-                self.dispatch(IoEvent::StopContainer(
-                    self.selected_container.clone().unwrap(),
-                ))
-                .await;
+                AppReturn::Continue
+            }
+            Action::ExecCommands => {
+                if self.selected_container.is_some() {
+                    self.prompt = Some(Prompt {
+                        kind: PromptKind::Exec,
+                        text: "sh".to_string(),
+                    });
+                }
+                AppReturn::Continue
+            }
+            Action::BroadcastExec => {
+                if !self.broadcast_targets().is_empty() {
+                    self.prompt = Some(Prompt {
+                        kind: PromptKind::Broadcast,
+                        text: String::new(),
+                    });
+                }
+                AppReturn::Continue
+            }
+            Action::ComparePackages => {
+                if self.marked.len() != 2 {
+                    debug!("Mark two containers to compare their packages");
+                    return AppReturn::Continue;
+                }
+                let mut marked: Vec<String> = self.marked.iter().cloned().collect();
+                // Oldest first, so the changes read as an upgrade
+                marked.sort_by_key(|id| {
+                    self.containers
+                        .iter()
+                        .find(|c| c.id == *id)
+                        .and_then(|c| c.started_at)
+                });
+                let containers = [marked[0].clone(), marked[1].clone()];
+                self.package_comparison = Some(PackageComparison {
+                    containers: containers.clone(),
+                    results: HashMap::new(),
+                    scroll: 0,
+                });
+                for container in containers {
+                    self.dispatch(IoEvent::Exec {
+                        container,
+                        command: packages::LIST_COMMAND.to_string(),
+                    })
+                    .await;
+                }
+                AppReturn::Continue
+            }
+            Action::PortForward => {
+                if self.selected_container.is_some() {
+                    self.prompt = Some(Prompt {
+                        kind: PromptKind::PortForward,
+                        text: String::new(),
+                    });
+                }
+                AppReturn::Continue
+            }
+            Action::ShowForwards => {
+                self.forwards_view = Some(0);
+                AppReturn::Continue
+            }
+            Action::TailFile => {
+                if self.selected_container.is_some() {
+                    self.prompt = Some(Prompt {
+                        kind: PromptKind::TailFile,
+                        text: String::new(),
+                    });
+                }
+                AppReturn::Continue
+            }
+            Action::ToggleMark => {
+                self.toggle_mark();
+                AppReturn::Continue
+            }
+            Action::MarkMatching => {
+                self.prompt = Some(Prompt {
+                    kind: PromptKind::MarkMatching,
+                    text: String::new(),
+                });
+                AppReturn::Continue
+            }
+            Action::StopContainer => {
+                self.confirm_batch("Stop", IoEvent::StopContainer).await;
                 AppReturn::Continue
             }
             Action::PauseContainer => {
+                self.dispatch_batch(IoEvent::PauseContainer).await;
+                AppReturn::Continue
+            }
+            Action::RecreateContainer => {
+                self.confirm_batch("Recreate", IoEvent::RecreateContainer)
+                    .await;
+                AppReturn::Continue
+            }
+            Action::RestartContainer => {
+                self.confirm_batch("Restart", IoEvent::RestartContainer)
+                    .await;
+                AppReturn::Continue
+            }
+            Action::RemoveContainer => {
+                self.confirm_batch("Force remove", IoEvent::RemoveContainer)
+                    .await;
+                AppReturn::Continue
+            }
+            Action::Prune => {
+                self.confirm_prune().await;
+                AppReturn::Continue
+            }
+            Action::Inspect => {
                 if self.selected_container.is_none() {
                     return AppReturn::Continue; // No container selected, do nothing
                 }
-                self.dispatch(IoEvent::PauseContainer(
-                    self.selected_container.clone().unwrap(),
-                ))
-                .await;
+                let container = self.selected_container.clone().unwrap();
+                self.container_details = None;
+                self.inspect_scroll = 0;
+                self.env_search = None;
+                self.env_search_typing = false;
+                self.state = AppState::Inspecting {
+                    container: container.clone(),
+                    tab: InspectTab::Overview,
+                };
+                self.actions = self.state.get_actions();
+                self.dispatch(IoEvent::InspectContainer(container.clone()))
+                    .await;
+                self.check_clock(container).await;
+                if let Some(image) = self.inspected_container().map(|c| c.image.clone()) {
+                    self.dispatch(IoEvent::InspectImage(image)).await;
+                }
+                AppReturn::Continue
+            }
+            Action::CycleFilter => {
+                self.cycle_filter();
+                AppReturn::Continue
+            }
+            Action::SwitchProfile => {
+                if self.config.profiles.is_empty() {
+                    return AppReturn::Continue; // Only the local engine, nothing to switch to
+                }
+                self.cycle_profile().await;
+                AppReturn::Continue
+            }
+            Action::ShowGraph => {
+                if let Some(container) = self.selected_container.clone() {
+                    self.state = AppState::Graph { container };
+                    self.actions = self.state.get_actions();
+                }
+                AppReturn::Continue
+            }
+            Action::ShowStartOrder => {
+                let project = self.selected_group.clone().or_else(|| {
+                    self.selected_container
+                        .as_ref()
+                        .and_then(|id| self.containers.iter().find(|c| c.id == *id))
+                        .map(|c| c.stack())
+                });
+                if let Some(project) = project {
+                    self.state = AppState::StartOrder { project };
+                    self.actions = self.state.get_actions();
+                }
+                AppReturn::Continue
+            }
+            Action::ShowSharedVolumes => {
+                self.state = AppState::SharedVolumes;
+                self.actions = self.state.get_actions();
+                AppReturn::Continue
+            }
+            Action::ToggleShowAll => {
+                self.config.show_all = !self.config.show_all;
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
+                self.select_visible_container();
+                AppReturn::Continue
+            }
+            Action::ToggleDryRun => {
+                self.dry_run = !self.dry_run;
+                AppReturn::Continue
+            }
+            Action::ToggleFullNames => {
+                self.config.full_names = !self.config.full_names;
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
+                AppReturn::Continue
+            }
+            Action::ToggleSplit => {
+                self.config.split_logs = !self.config.split_logs;
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
+                AppReturn::Continue
+            }
+
+            Action::SortBy => {
+                let column = self.sort.column.next();
+                self.set_sort(SortOrder {
+                    column,
+                    direction: column.default_direction(),
+                });
+                AppReturn::Continue
+            }
+            Action::ReverseSort => {
+                self.set_sort(SortOrder {
+                    column: self.sort.column,
+                    direction: self.sort.direction.reverse(),
+                });
                 AppReturn::Continue
             }
             _ => AppReturn::Continue,
@@ -120,51 +1198,198 @@ impl App {
         match action {
             Action::Quit => {
                 if self.search.is_some() {
+                    self.set_matches_only(false);
                     self.search = None;
                     return AppReturn::Continue;
                 }
+                self.close_logs();
                 self.state = AppState::Monitoring;
-                self.logs.clear();
-                self.log_position = 0;
                 self.actions = self.state.get_actions();
                 self.dispatch(IoEvent::StartMonitoring).await;
                 AppReturn::Continue
             }
-            Action::ScrollDown => {
-                self.log_position = if self.log_position > 0 {
-                    self.log_position - 1
-                } else {
-                    0
-                };
-                AppReturn::Continue
-            }
-            Action::ScrollUp => {
-                self.log_position = if self.log_position + 1 < self.logs.len() {
-                    self.log_position + 1
-                } else {
-                    self.log_position
-                };
+            Action::CycleJsonLogs => {
+                let viewer = &mut self.config.log_viewer;
+                viewer.json = viewer.json.next();
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
                 AppReturn::Continue
             }
-            Action::Search => {
-                if let Some(search_text) = self.search() {
-                    if let Some(line) = self
-                        .logs()
-                        .iter()
-                        .rev()
-                        .skip(self.log_position + 1)
-                        .position(|line| {
-                            line.to_lowercase()
-                                .contains(&search_text.clone().to_lowercase())
-                        })
-                    {
-                        self.log_position += line + 1;
+            Action::StackFilter => {
+                // The search becomes a filter, or the last filter goes away
+                match self.search.take().filter(|s| !s.is_empty()) {
+                    Some(search) => {
+                        self.last_search = Some(search.clone());
+                        self.log_grep.push(search);
+                    }
+                    None => {
+                        self.log_grep.pop();
+                    }
+                }
+                self.search_typing = false;
+                self.matches_only = false;
+                self.log_position = 0;
+                AppReturn::Continue
+            }
+            Action::CycleLogStreams => {
+                self.log_streams = self.log_streams.next();
+                self.matches_only = false;
+                self.log_position = 0;
+                AppReturn::Continue
+            }
+            Action::ToggleWrap => {
+                let viewer = &mut self.config.log_viewer;
+                viewer.wrap = !viewer.wrap;
+                self.log_offset = 0;
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
+                AppReturn::Continue
+            }
+            Action::ToggleCaseSensitive => {
+                let viewer = &mut self.config.log_viewer;
+                viewer.case_sensitive = !viewer.case_sensitive;
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
+                AppReturn::Continue
+            }
+            Action::LoadMoreLogs => {
+                if let Some(tail) = self.log_tail {
+                    self.log_tail = Some(tail + self.config.log_viewer.tail);
+                    self.fetch_logs().await;
+                }
+                AppReturn::Continue
+            }
+            Action::OpenPager => {
+                let name = match &self.state {
+                    AppState::Logging { container, .. } => container.clone(),
+                    AppState::StackLogs { stack } => stack.clone(),
+                    _ => return AppReturn::Continue,
+                };
+                let path = std::env::temp_dir().join(format!("bctop-{}.log", name));
+                let lines = self.logs.iter().chain(self.pending_logs.iter());
+                match export::write_logs(&path.to_string_lossy(), lines.map(|l| &l.text)) {
+                    Ok(_) => AppReturn::Page(path),
+                    Err(e) => {
+                        self.notify(Notification::error(format!(
+                            "Error writing {}: {}",
+                            path.display(),
+                            e
+                        )));
+                        AppReturn::Continue
                     }
+                }
+            }
+            Action::ExportLogs => {
+                let name = match &self.state {
+                    AppState::Logging {
+                        file: Some(path), ..
+                    } => path.rsplit('/').next().unwrap_or(path).to_string(),
+                    AppState::Logging { container, .. } => self
+                        .containers
+                        .iter()
+                        .find(|c| c.id == *container)
+                        .map_or(container.clone(), |c| c.name.clone()),
+                    AppState::StackLogs { stack } => stack.clone(),
+                    _ => return AppReturn::Continue,
+                };
+                self.prompt = Some(Prompt {
+                    kind: PromptKind::ExportLogs,
+                    text: format!("{}.log", name),
+                });
+                AppReturn::Continue
+            }
+            Action::LogTimeRange => {
+                if let AppState::Logging { file: None, .. } = &self.state {
+                    self.prompt = Some(Prompt {
+                        kind: PromptKind::LogRange,
+                        text: String::new(),
+                    });
+                }
+                AppReturn::Continue
+            }
+            Action::EditLogFilters => {
+                if let Some(key) = self.logged_service_key() {
+                    let filter = self.log_filters.get(&key).cloned().unwrap_or_default();
+                    self.log_filter_editor = Some(filter.to_text());
+                }
+                AppReturn::Continue
+            }
+            Action::SwitchRecent => {
+                // The current container is the first one, go back to the previous
+                if let Some(id) = self.recent_containers.get(1).cloned() {
+                    self.close_logs();
+                    self.selected_container = Some(id.clone());
+                    self.selected_group = None;
+                    self.open_logs(id).await;
+                }
+                AppReturn::Continue
+            }
+            Action::ScrollDown => {
+                self.log_position = if self.log_position > 0 {
+                    self.log_position - 1
+                } else {
+                    0
+                };
+                if self.log_position == 0 {
+                    self.logs.append(&mut self.pending_logs);
+                }
+                AppReturn::Continue
+            }
+            Action::PageUp => {
+                self.scroll_logs(self.log_page().max(1) as isize);
+                AppReturn::Continue
+            }
+            Action::PageDown => {
+                self.scroll_logs(-(self.log_page().max(1) as isize));
+                AppReturn::Continue
+            }
+            Action::ScrollTop => {
+                self.scroll_logs(isize::MAX);
+                AppReturn::Continue
+            }
+            Action::ScrollBottom => {
+                self.scroll_logs(isize::MIN);
+                AppReturn::Continue
+            }
+            Action::ToggleMatchesOnly => {
+                if self.search.is_none() {
+                    self.search = Some(self.last_search.clone().unwrap_or_default());
+                    self.search_typing = true;
+                }
+                self.set_matches_only(!self.matches_only);
+                AppReturn::Continue
+            }
+            Action::ScrollUp => {
+                self.log_position = if self.log_position + 1 < self.shown_logs().len() {
+                    self.log_position + 1
+                } else {
+                    self.log_position
+                };
+                AppReturn::Continue
+            }
+            Action::Search => {
+                if let Some(search_text) = self.search.clone() {
+                    self.jump_to_match(true);
+                    self.last_search = Some(search_text);
+                    // Enter ends the typing so n and N can move between matches
+                    self.search_typing = false;
                 } else {
-                    self.search = Some("".to_string());
+                    self.search = Some(self.last_search.clone().unwrap_or_default());
+                    self.search_typing = true;
                 }
                 AppReturn::Continue
             }
+            Action::NextMatch => {
+                self.jump_to_match(true);
+                AppReturn::Continue
+            }
+            Action::PreviousMatch => {
+                self.jump_to_match(false);
+                AppReturn::Continue
+            }
             Action::Remove => {
                 if let Some(search_text) = self.search() {
                     let mut new_text = search_text.clone();
@@ -177,103 +1402,2569 @@ impl App {
         }
     }
 
-    /// We could update the app or dispatch event on tick
-    pub async fn update_on_tick(&mut self) -> AppReturn {
+    fn do_state_resource_actions(&mut self, action: Action) -> AppReturn {
+        match action {
+            Action::Quit => {
+                self.state = AppState::Monitoring;
+                self.actions = self.state.get_actions();
+            }
+            Action::Next => {
+                let last = self.resource_count().saturating_sub(1);
+                self.resource_selected = (self.resource_selected + 1).min(last);
+            }
+            Action::Previous => {
+                self.resource_selected = self.resource_selected.saturating_sub(1);
+            }
+            _ => {}
+        }
         AppReturn::Continue
     }
 
-    /// Send a network event to the IO thread
-    pub async fn dispatch(&mut self, action: IoEvent) {
-        if let Err(_e) = self.io_tx.send(action).await {
-            // error!("Error from dispatch {}", e);
+    /// Show the tab of a resource type, fetching what it lists
+    async fn switch_view(&mut self, view: View) {
+        self.state = view.state();
+        self.actions = self.state.get_actions();
+        self.resource_selected = 0;
+        if view == View::Events {
+            self.dispatch(IoEvent::WatchEvents).await;
+        } else {
+            self.request_resources().await;
+        }
+    }
+
+    async fn request_resources(&mut self) {
+        self.resources_requested_at = Instant::now();
+        let event = match self.state {
+            AppState::Images => IoEvent::ListImages,
+            AppState::Volumes => IoEvent::ListVolumes,
+            AppState::Networks => IoEvent::ListNetworks,
+            _ => return,
         };
+        self.dispatch(event).await;
     }
 
-    pub fn actions(&self) -> &Actions {
-        &self.actions
+    /// Rows of the images, volumes, networks or events view
+    fn resource_count(&self) -> usize {
+        match self.state {
+            AppState::Images => self.images.len(),
+            AppState::Volumes => self.volumes.len(),
+            AppState::Networks => self.networks.len(),
+            AppState::Events => self.engine_events.len(),
+            _ => 0,
+        }
     }
-    pub fn state(&self) -> &AppState {
-        &self.state
+
+    async fn do_state_inspecting_actions(&mut self, action: Action) -> AppReturn {
+        match action {
+            Action::EditEnv => {
+                let overrides = self
+                    .inspected_container()
+                    .and_then(|c| self.env_overrides.get(&c.service_key()))
+                    .map(EnvOverride::to_text);
+                if self.inspected_container().is_some() {
+                    self.env_editor = Some(overrides.unwrap_or_default());
+                }
+                AppReturn::Continue
+            }
+            Action::RecreateContainer => {
+                self.confirm_batch("Recreate", IoEvent::RecreateContainer)
+                    .await;
+                AppReturn::Continue
+            }
+            Action::EditNotes => {
+                let note = self
+                    .inspected_container()
+                    .and_then(|c| self.notes.get(&c.service_key()).cloned());
+                if self.inspected_container().is_some() {
+                    self.notes_editor = Some(note.unwrap_or_default());
+                }
+                AppReturn::Continue
+            }
+            Action::NextTab => {
+                if let AppState::Inspecting { container, tab } = &self.state {
+                    let container = container.clone();
+                    let tab = tab.next();
+                    self.state = AppState::Inspecting {
+                        container: container.clone(),
+                        tab,
+                    };
+                    self.inspect_scroll = 0;
+                    self.env_search = None;
+                    self.env_search_typing = false;
+                    if tab == InspectTab::Processes {
+                        self.request_processes().await;
+                    }
+                    if tab == InspectTab::Database {
+                        self.request_database_stats().await;
+                    }
+                    // Refresh, the state may have changed since the view was opened
+                    self.dispatch(IoEvent::InspectContainer(container)).await;
+                }
+                AppReturn::Continue
+            }
+            Action::ScrollDown => {
+                self.inspect_scroll = self.inspect_scroll.saturating_add(1);
+                if self.inspected_tab() == Some(InspectTab::Env) {
+                    let last = self.env_vars().len().saturating_sub(1) as u16;
+                    self.inspect_scroll = self.inspect_scroll.min(last);
+                }
+                AppReturn::Continue
+            }
+            Action::ScrollUp => {
+                self.inspect_scroll = self.inspect_scroll.saturating_sub(1);
+                AppReturn::Continue
+            }
+            Action::Search => {
+                if self.inspected_tab() == Some(InspectTab::Env) {
+                    // Enter keeps the search but gives the keys back to the actions
+                    self.env_search_typing = !self.env_search_typing;
+                    if self.env_search.is_none() {
+                        self.env_search = Some(String::new());
+                    }
+                }
+                AppReturn::Continue
+            }
+            Action::Remove => {
+                if let (true, Some(text)) = (self.env_search_typing, self.env_search.as_mut()) {
+                    text.pop();
+                }
+                AppReturn::Continue
+            }
+            Action::Copy => {
+                let text = if self.inspected_tab() == Some(InspectTab::Env) {
+                    self.env_vars()
+                        .get(self.inspect_scroll as usize)
+                        .map(|v| v.to_string())
+                } else {
+                    self.inspected_container()
+                        .and_then(|c| self.image_details(&c.image))
+                        .and_then(|d| d.digests.first().cloned())
+                };
+                match text {
+                    Some(text) => {
+                        if let Err(e) = clipboard::copy(&text) {
+                            warn!("Error copying to the clipboard: {}", e);
+                        }
+                    }
+                    None => debug!("Nothing to copy"),
+                }
+                AppReturn::Continue
+            }
+            Action::ToggleMemoryCalculation => {
+                self.config.memory_calculation = self.config.memory_calculation.toggle();
+                if let Err(e) = self.config.save() {
+                    warn!("Error saving config: {}", e);
+                }
+                AppReturn::Continue
+            }
+            Action::Probe => {
+                self.toggle_probe().await;
+                AppReturn::Continue
+            }
+            Action::OpenSource => {
+                let url = self
+                    .inspected_container()
+                    .and_then(|c| self.image_details(&c.image))
+                    .and_then(|d| d.source_url());
+                match url {
+                    Some(url) => {
+                        if let Err(e) = opener::open(&url) {
+                            warn!("Error opening {}: {}", url, e);
+                        }
+                    }
+                    None => debug!("The image has no source label"),
+                }
+                AppReturn::Continue
+            }
+            Action::Quit if self.env_search.is_some() => {
+                self.env_search = None;
+                self.env_search_typing = false;
+                AppReturn::Continue
+            }
+            Action::Quit => {
+                // Monitoring keeps running while inspecting, no need to restart it
+                self.state = AppState::Monitoring;
+                self.actions = self.state.get_actions();
+                AppReturn::Continue
+            }
+            _ => AppReturn::Continue,
+        }
     }
-    pub fn containers(&self) -> &Vec<Container> {
-        &self.containers
+
+    /// Type into the notes popup, leaving it with Esc saves the note
+    fn edit_notes(&mut self, key: Key) {
+        let text = self.notes_editor.as_mut().unwrap();
+        match key {
+            Key::Esc => {
+                let text = self.notes_editor.take().unwrap();
+                if let Some(service_key) = self.inspected_container().map(|c| c.service_key()) {
+                    self.notes.set(&service_key, text);
+                    if let Err(e) = self.notes.save() {
+                        warn!("Error saving notes: {}", e);
+                    }
+                }
+            }
+            Key::Enter => text.push('\n'),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Char(c) => text.push(c),
+            _ => {}
+        }
     }
-    pub fn selected_container(&self) -> &Option<String> {
-        &self.selected_container
+
+    fn edit_env(&mut self, key: Key) {
+        let text = self.env_editor.as_mut().unwrap();
+        match key {
+            Key::Esc => {
+                let overrides = EnvOverride::parse(&self.env_editor.take().unwrap());
+                if let Some(service_key) = self.inspected_container().map(|c| c.service_key()) {
+                    self.env_overrides.set(&service_key, overrides);
+                    if let Err(e) = self.env_overrides.save() {
+                        warn!("Error saving env overrides: {}", e);
+                    }
+                }
+            }
+            Key::Enter => text.push('\n'),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Char(c) => text.push(c),
+            _ => {}
+        }
     }
-    pub fn selected_container_index(&self) -> Option<usize> {
-        self.selected_container
-            .as_ref()
-            .and_then(|id| self.containers.iter().position(|c| c.id == *id))
+
+    /// Service whose logs are shown, `None` when following a file
+    fn logged_service_key(&self) -> Option<String> {
+        match &self.state {
+            AppState::Logging {
+                container,
+                file: None,
+            } => self
+                .containers
+                .iter()
+                .find(|c| c.id == *container)
+                .map(|c| c.service_key()),
+            _ => None,
+        }
     }
-    pub fn logs(&self) -> &Vec<String> {
-        &self.logs
+
+    /// Type in the log filters popup, Ctrl+s saves and applies them and Esc
+    /// leaves them unchanged
+    async fn edit_log_filter(&mut self, key: Key) {
+        let text = self.log_filter_editor.as_mut().unwrap();
+        match key {
+            Key::Esc => self.log_filter_editor = None,
+            Key::Ctrl('s') => {
+                let filter = LogFilter::parse(&self.log_filter_editor.take().unwrap());
+                let service_key = match self.logged_service_key() {
+                    Some(k) => k,
+                    None => return,
+                };
+                self.log_filter = Some(filter.compile());
+                self.log_filters.set(&service_key, filter);
+                if let Err(e) = self.log_filters.save() {
+                    warn!("Error saving log filters: {}", e);
+                }
+                // Lines dropped by the previous filters may be kept now
+                self.fetch_logs().await;
+            }
+            Key::Enter => text.push('\n'),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Char(c) => text.push(c),
+            _ => {}
+        }
     }
-    pub fn log_position(&self) -> usize {
-        self.log_position
+
+    /// Snippets whose image pattern matches the image of a container
+    fn snippets_for(&self, id: &str) -> Vec<Snippet> {
+        let image = match self.containers.iter().find(|c| c.id == id) {
+            Some(c) => &c.image,
+            None => return Vec::new(),
+        };
+        self.config
+            .snippets
+            .iter()
+            .filter(|s| match &s.image_regex {
+                Some(pattern) => match Regex::new(pattern) {
+                    Ok(re) => re.is_match(image),
+                    Err(e) => {
+                        warn!("Invalid image_regex {}: {}", pattern, e);
+                        false
+                    }
+                },
+                None => true,
+            })
+            .cloned()
+            .collect()
     }
-    pub fn search(&self) -> &Option<String> {
-        &self.search
+
+    /// Move in the snippets popup, Enter asks to confirm and `y` runs the snippet
+    async fn pick_snippet(&mut self, key: Key) {
+        let picker = self.snippet_picker.as_mut().unwrap();
+        match key {
+            Key::Esc | Key::Char('q') | Key::Char('n') if picker.confirming => {
+                picker.confirming = false;
+            }
+            Key::Esc | Key::Char('q') => self.snippet_picker = None,
+            Key::Up => picker.selected = picker.selected.saturating_sub(1),
+            Key::Down => {
+                picker.selected = (picker.selected + 1).min(picker.snippets.len() - 1);
+            }
+            Key::Enter => picker.confirming = true,
+            Key::Char('y') if picker.confirming => {
+                let picker = self.snippet_picker.take().unwrap();
+                let snippet = picker.snippets[picker.selected].clone();
+                let name = self
+                    .containers
+                    .iter()
+                    .find(|c| c.id == picker.container)
+                    .map_or(picker.container.clone(), |c| c.name.clone());
+                self.exec_view = Some(ExecView {
+                    title: format!("{} in {}", snippet.name, name),
+                    containers: vec![picker.container.clone()],
+                    results: HashMap::new(),
+                    scroll: 0,
+                });
+                self.dispatch(IoEvent::Exec {
+                    container: picker.container,
+                    command: snippet.command,
+                })
+                .await;
+            }
+            _ => {}
+        }
     }
 
-    pub fn next(&mut self) {
-        let index = match &self.selected_container {
-            Some(i) => {
-                let idx = self.containers.iter().position(|c| c.id == *i).unwrap_or(0);
-                if idx + 1 >= self.containers.len() {
-                    idx
-                } else {
-                    idx + 1
+    /// Containers a broadcast command runs in: the marked ones, else the
+    /// containers of the selected project, else the replicas of the service
+    /// of the selected container
+    fn broadcast_targets(&self) -> Vec<String> {
+        if !self.marked.is_empty() {
+            let mut targets: Vec<String> = self.marked.iter().cloned().collect();
+            targets.sort();
+            return targets;
+        }
+        if let Some(stack) = &self.selected_group {
+            return self
+                .visible_containers()
+                .into_iter()
+                .filter(|c| c.stack() == *stack)
+                .map(|c| c.id.clone())
+                .collect();
+        }
+        let service = match self
+            .selected_container
+            .as_ref()
+            .and_then(|id| self.containers.iter().find(|c| c.id == *id))
+        {
+            Some(c) => c.service_key(),
+            None => return Vec::new(),
+        };
+        self.containers
+            .iter()
+            .filter(|c| c.service_key() == service)
+            .map(|c| c.id.clone())
+            .collect()
+    }
+
+    /// Type into the prompt, Enter runs the action it was opened for
+    async fn edit_prompt(&mut self, key: Key) -> AppReturn {
+        let prompt = self.prompt.as_mut().unwrap();
+        match key {
+            Key::Esc => self.prompt = None,
+            Key::Enter => {
+                let prompt = self.prompt.take().unwrap();
+                if prompt.text.trim().is_empty() {
+                    return AppReturn::Continue;
+                }
+                match prompt.kind {
+                    PromptKind::Broadcast => self.broadcast(prompt.text).await,
+                    PromptKind::TailFile => {
+                        if let Some(id) = self.selected_container.clone() {
+                            self.open_file(id, prompt.text.trim().to_string()).await;
+                        }
+                    }
+                    PromptKind::PortForward => self.forward(&prompt.text).await,
+                    PromptKind::MarkMatching => self.mark_matching(&prompt.text),
+                    PromptKind::LogRange => self.set_log_range(&prompt.text).await,
+                    PromptKind::ExportLogs => self.export_logs(prompt.text.trim()).await,
+                    PromptKind::Exec => {
+                        if let Some(container) = self.selected_container.clone() {
+                            return AppReturn::Session {
+                                container,
+                                command: prompt.text.trim().to_string(),
+                            };
+                        }
+                    }
                 }
             }
-            None => 0,
+            Key::Backspace => {
+                prompt.text.pop();
+            }
+            Key::Char(c) => prompt.text.push(c),
+            _ => {}
+        }
+        AppReturn::Continue
+    }
+
+    /// Write the logs of the viewer to a file, fetching them all again when
+    /// only the last lines were
+    async fn export_logs(&mut self, path: &str) {
+        let refetch = match (&self.state, self.log_tail, self.log_range) {
+            (
+                AppState::Logging {
+                    container,
+                    file: None,
+                },
+                Some(_),
+                None,
+            ) => Some(container.clone()),
+            _ => None,
         };
+        if let Some(container) = refetch {
+            self.notify(Notification::progress(format!(
+                "Exporting logs to {}",
+                path
+            )));
+            self.dispatch(IoEvent::ExportLogs {
+                container,
+                path: path.to_string(),
+            })
+            .await;
+            return;
+        }
+        let lines = self.logs.iter().chain(self.pending_logs.iter());
+        let notification = match export::write_logs(path, lines.map(|l| &l.text)) {
+            Ok(count) => Notification::success(format!("{} lines written to {}", count, path)),
+            Err(e) => Notification::error(format!("Error writing {}: {}", path, e)),
+        };
+        self.notify(notification);
+    }
 
-        self.selected_container = self
-            .containers
-            .get(index)
-            .map_or(None, |c| Some(c.id.clone()));
+    /// Forward the ports typed in the prompt to the selected container
+    async fn forward(&mut self, text: &str) {
+        let container = match self.selected_container.clone() {
+            Some(c) => c,
+            None => return,
+        };
+        let text = text.trim();
+        let (local, remote) = text.split_once(':').unwrap_or((text, text));
+        match (local.parse::<u16>(), remote.parse::<u16>()) {
+            (Ok(local_port), Ok(container_port)) => {
+                self.dispatch(IoEvent::StartForward {
+                    container,
+                    local_port,
+                    container_port,
+                })
+                .await
+            }
+            _ => warn!("Invalid ports to forward: {}", text),
+        }
     }
 
-    pub fn previous(&mut self) {
-        let index = match &self.selected_container {
-            Some(i) => {
-                let idx = self.containers.iter().position(|c| c.id == *i).unwrap_or(0);
-                if idx == 0 {
-                    idx
-                } else {
-                    idx - 1
+    /// Start probing the HTTP port of the inspected container, or stop it
+    async fn toggle_probe(&mut self) {
+        let host = self
+            .profile
+            .and_then(|i| self.config.profiles.get(i))
+            .and_then(|p| p.host())
+            .unwrap_or("localhost");
+        let (key, name, url) = match self.inspected_container() {
+            Some(c) => (
+                c.service_key(),
+                c.name.clone(),
+                c.probe_url(host, &self.config.probe.path),
+            ),
+            None => return,
+        };
+        if self.probes.remove(&key).is_some() {
+            self.dispatch(IoEvent::StopProbe(key)).await;
+            return;
+        }
+        let url = match url {
+            Some(url) => url,
+            None => {
+                warn!("{} has no published TCP port to probe", name);
+                return;
+            }
+        };
+        self.probes.insert(
+            key.clone(),
+            Probe {
+                url: url.clone(),
+                results: VecDeque::new(),
+            },
+        );
+        self.dispatch(IoEvent::StartProbe {
+            service_key: key,
+            url,
+            interval: Duration::from_secs(self.config.probe.interval_seconds),
+        })
+        .await;
+    }
+
+    /// Move in the forwards popup, `d` stops the selected forward
+    async fn browse_forwards(&mut self, key: Key) {
+        let selected = self.forwards_view.unwrap();
+        match key {
+            Key::Esc | Key::Char('q') => self.forwards_view = None,
+            Key::Up => self.forwards_view = Some(selected.saturating_sub(1)),
+            Key::Down => {
+                let last = self.forwards.len().saturating_sub(1);
+                self.forwards_view = Some((selected + 1).min(last));
+            }
+            Key::Char('d') | Key::Delete => {
+                if let Some(forward) = self.forwards.get(selected) {
+                    let port = forward.local_port;
+                    self.dispatch(IoEvent::StopForward(port)).await;
                 }
             }
-            None => 0,
+            _ => {}
+        }
+    }
+
+    /// Run a command in every broadcast target at once
+    async fn broadcast(&mut self, command: String) {
+        let containers = self.broadcast_targets();
+        self.exec_view = Some(ExecView {
+            title: format!("{} ({} containers)", command, containers.len()),
+            containers: containers.clone(),
+            results: HashMap::new(),
+            scroll: 0,
+        });
+        for container in containers {
+            self.dispatch(IoEvent::Exec {
+                container,
+                command: command.clone(),
+            })
+            .await;
+        }
+    }
+
+    fn scroll_exec_view(&mut self, key: Key) {
+        let view = self.exec_view.as_mut().unwrap();
+        match key {
+            Key::Esc | Key::Char('q') => self.exec_view = None,
+            Key::Up => view.scroll = view.scroll.saturating_sub(1),
+            Key::Down => view.scroll = view.scroll.saturating_add(1),
+            _ => {}
+        }
+    }
+
+    /// Show the logs of a container, restoring the view left on a previous visit
+    async fn open_logs(&mut self, id: String) {
+        let view = self.log_views.remove(&id).unwrap_or_default();
+        self.log_anchor = view.anchor;
+        self.search = view.search;
+        self.search_typing = false;
+        self.last_search = view.last_search;
+        self.log_grep = view.grep;
+        self.log_streams = view.streams;
+        self.recent_containers.retain(|c| *c != id);
+        self.recent_containers.push_front(id.clone());
+        self.recent_containers.truncate(RECENT_CONTAINERS_LIMIT);
+        self.state = AppState::Logging {
+            container: id.clone(),
+            file: None,
         };
+        self.actions = self.state.get_actions();
+        self.log_filter = self
+            .logged_service_key()
+            .and_then(|key| self.log_filters.get(&key))
+            .map(|f| f.compile());
+        self.log_tail = match self.config.log_viewer.tail {
+            0 => None,
+            tail => Some(tail),
+        };
+        self.dispatch(IoEvent::ShowLogs {
+            container: id,
+            range: None,
+            tail: self.log_tail,
+        })
+        .await;
+    }
 
-        self.selected_container = self
+    /// Show the logs of every container of a stack together, like
+    /// `docker compose logs -f`
+    async fn open_stack_logs(&mut self, stack: String) {
+        self.last_search = None;
+        let containers = self
             .containers
-            .get(index as usize)
-            .map_or(None, |c| Some(c.id.clone()));
+            .iter()
+            .filter(|c| c.stack() == stack)
+            .map(|c| (c.id.clone(), self.short_name(c)))
+            .collect();
+        self.state = AppState::StackLogs { stack };
+        self.actions = self.state.get_actions();
+        let tail = match self.config.log_viewer.tail {
+            0 => None,
+            tail => Some(tail),
+        };
+        self.dispatch(IoEvent::ShowStackLogs { containers, tail })
+            .await;
     }
-}
 
-impl ContainerManagement for App {
-    fn update_containers(&mut self, new_container: Container) {
-        self.containers.retain(|c| c.id != new_container.id);
-        self.containers.push(new_container);
-        self.containers.sort_by(|a, b| a.name.cmp(&b.name));
+    /// Fetch the logs of the range typed in the prompt again, `all` going back
+    /// to every line
+    async fn set_log_range(&mut self, text: &str) {
+        let range = if text.trim() == "all" {
+            None
+        } else {
+            match LogRange::parse(text, Local::now()) {
+                Ok(range) => Some(range),
+                Err(e) => {
+                    warn!("Invalid log range {}: {}", text, e);
+                    return;
+                }
+            }
+        };
+        self.log_range = range;
+        self.fetch_logs().await;
     }
 
-    fn remove_container(&mut self, id: &str) {
-        self.containers.retain(|c| c.id != id);
+    /// Fetch the logs of the container again, after a change of the range or
+    /// of the number of lines
+    async fn fetch_logs(&mut self) {
+        let container = match &self.state {
+            AppState::Logging {
+                container,
+                file: None,
+            } => container.clone(),
+            _ => return,
+        };
+        self.set_matches_only(false);
+        self.log_anchor = None;
+        self.logs.clear();
+        self.pending_logs.clear();
+        self.log_position = 0;
+        // The whole period is fetched
+        let tail = self.log_tail.filter(|_| self.log_range.is_none());
+        self.dispatch(IoEvent::ShowLogs {
+            container,
+            range: self.log_range,
+            tail,
+        })
+        .await;
     }
 
-    fn add_logs(&mut self, logs: Vec<String>) {
-        if self.log_position != 0 {
-            self.log_position += logs.len();
-        }
-        self.logs.extend(logs);
+    /// Read the clock of a container to compare it with the host one
+    async fn check_clock(&mut self, id: String) {
+        self.clock_skew.remove(&id);
+        self.clock_requested_at
+            .insert(id.clone(), Utc::now().timestamp());
+        self.dispatch(IoEvent::Exec {
+            container: id,
+            command: CLOCK_COMMAND.to_string(),
+        })
+        .await;
     }
 
-    fn add_tty_output(&mut self, output: String) {
-        debug!("TTY Output: {}", output);
+    /// Follow a file of a container in the log viewer
+    async fn open_file(&mut self, id: String, path: String) {
+        self.last_search = None;
+        self.state = AppState::Logging {
+            container: id.clone(),
+            file: Some(path.clone()),
+        };
+        self.actions = self.state.get_actions();
+        self.dispatch(IoEvent::TailFile {
+            container: id,
+            path,
+        })
+        .await;
+    }
+
+    /// Keys of the vim preset, returning the key to handle as usual or `None`
+    /// when it was used here
+    fn vim_motion(&mut self, key: Key) -> Option<Key> {
+        if self.config.keys != KeyPreset::Vim {
+            return Some(key);
+        }
+        let pending_g = std::mem::take(&mut self.pending_g);
+        match key {
+            Key::Char('j') => return Some(Key::Down),
+            Key::Char('k') => return Some(Key::Up),
+            _ => {}
+        }
+        if !self.state.is_logging() {
+            return Some(key);
+        }
+        let half_page = (self.log_page() / 2).max(1) as isize;
+        match key {
+            Key::Char('g') if pending_g => self.scroll_logs(isize::MAX),
+            Key::Char('g') => self.pending_g = true,
+            Key::Char('G') => self.scroll_logs(isize::MIN),
+            Key::Ctrl('u') => self.scroll_logs(half_page),
+            Key::Ctrl('d') => self.scroll_logs(-half_page),
+            Key::Char('/') if self.search.is_some() => {
+                self.search = Some(String::new());
+                self.search_typing = true;
+            }
+            _ => return Some(key),
+        }
+        None
+    }
+
+    /// Rows of logs on the screen, without the tab bar, the border and the help
+    fn log_page(&self) -> usize {
+        self.screen.height.saturating_sub(4) as usize
+    }
+
+    /// Move the log viewer by that many lines, positive towards the older ones
+    fn scroll_logs(&mut self, lines: isize) {
+        let last = self.shown_logs().len().saturating_sub(1) as isize;
+        let position = (self.log_position as isize).saturating_add(lines);
+        self.log_position = position.clamp(0, last) as usize;
+        if self.log_position == 0 {
+            self.logs.append(&mut self.pending_logs);
+        }
+    }
+
+    /// Bring the next line matching the search to the bottom of the viewer,
+    /// looking in the older lines or in the newer ones
+    fn jump_to_match(&mut self, older: bool) {
+        let search = match &self.search {
+            Some(s) if !s.is_empty() => s,
+            _ => return,
+        };
+        let lines = self.shown_logs();
+        let bottom = lines.len().saturating_sub(1 + self.log_position);
+        let case_sensitive = self.config.log_viewer.case_sensitive;
+        let matches = |line: &&str| search::is_match(line, search, case_sensitive);
+        let found = if older {
+            lines[..bottom].iter().rposition(matches)
+        } else {
+            lines
+                .iter()
+                .skip(bottom + 1)
+                .position(matches)
+                .map(|i| i + bottom + 1)
+        };
+        if let Some(i) = found {
+            self.log_position = lines.len() - 1 - i;
+        }
+        if self.log_position == 0 {
+            self.logs.append(&mut self.pending_logs);
+        }
+    }
+
+    /// Switch between the matching lines and the whole stream, keeping the
+    /// bottom line in view when going back to the stream
+    fn set_matches_only(&mut self, matches_only: bool) {
+        if matches_only == self.matches_only {
+            return;
+        }
+        let bottom = self
+            .log_lines()
+            .iter()
+            .rev()
+            .skip(self.log_position)
+            .find_map(|(i, _)| *i);
+        self.matches_only = matches_only;
+        self.log_position = match (matches_only, bottom) {
+            (false, Some(i)) => self
+                .log_lines()
+                .iter()
+                .rev()
+                .position(|(j, _)| *j == Some(i))
+                .unwrap_or(0),
+            _ => 0,
+        };
+    }
+
+    /// Lines of the log viewer with their position in the stream: the ones
+    /// of the shown streams matching every stacked filter, and only the matches of the search with
+    /// their context in matches only mode. `None` separates the groups of
+    /// lines that are not contiguous.
+    fn log_lines(&self) -> Vec<(Option<usize>, &str)> {
+        let case_sensitive = self.config.log_viewer.case_sensitive;
+        let filtered: Vec<(Option<usize>, &str)> = self
+            .logs
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| {
+                self.log_streams.shows(l)
+                    && self
+                        .log_grep
+                        .iter()
+                        .all(|g| search::is_match(&l.text, g, case_sensitive))
+            })
+            .map(|(i, l)| (Some(i), l.text.as_str()))
+            .collect();
+        let search = match (&self.search, self.matches_only) {
+            (Some(s), true) if !s.is_empty() => s,
+            _ => return filtered,
+        };
+        let context = self.config.log_viewer.context_lines;
+        let mut lines = Vec::new();
+        // First line not listed yet
+        let mut next = 0;
+        for (i, _) in filtered
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, l))| search::is_match(l, search, case_sensitive))
+        {
+            let start = i.saturating_sub(context).max(next);
+            if start > next && !lines.is_empty() {
+                lines.push((None, LOG_GAP));
+            }
+            let end = (i + context + 1).min(filtered.len());
+            lines.extend_from_slice(&filtered[start..end]);
+            next = end;
+        }
+        lines
+    }
+
+    /// Filters stacked on the log viewer, the first one applied first
+    pub fn log_grep(&self) -> &[String] {
+        &self.log_grep
+    }
+
+    /// Lines shown by the log viewer, the newest last
+    pub fn shown_logs(&self) -> Vec<&str> {
+        self.log_lines().into_iter().map(|(_, l)| l).collect()
+    }
+
+    /// Lines shown by the log viewer along with whether they were written to
+    /// stderr
+    pub fn shown_log_lines(&self) -> Vec<(&str, bool)> {
+        self.log_lines()
+            .into_iter()
+            .map(|(i, l)| (l, i.is_some_and(|i| self.logs[i].stderr)))
+            .collect()
+    }
+
+    pub fn log_streams(&self) -> LogStreams {
+        self.log_streams
+    }
+
+    /// Occurrences of the search in the shown lines, with the position of the
+    /// first one of the bottom line when it has some
+    pub fn search_matches(&self) -> Option<(Option<usize>, usize)> {
+        let search = self.search.as_deref().filter(|s| !s.is_empty())?;
+        let lines = self.shown_logs();
+        let bottom = lines.len().saturating_sub(1 + self.log_position);
+        let mut current = None;
+        let mut total = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let count = search::find(line, search, self.config.log_viewer.case_sensitive).len();
+            if i == bottom && count > 0 {
+                current = Some(total + 1);
+            }
+            total += count;
+        }
+        Some((current, total))
+    }
+
+    pub fn matches_only(&self) -> bool {
+        self.matches_only
+    }
+
+    /// Remember the log view of the current container and clear it
+    fn close_logs(&mut self) {
+        self.set_matches_only(false);
+        // Files are not remembered, only the logs of the containers
+        if let AppState::Logging {
+            container,
+            file: None,
+        } = &self.state
+        {
+            let anchor = if self.log_position == 0 {
+                None
+            } else {
+                self.log_lines()
+                    .iter()
+                    .rev()
+                    .skip(self.log_position)
+                    .find_map(|(i, _)| *i)
+                    .and_then(|i| self.logs[i].at)
+            };
+            self.log_views.insert(
+                container.clone(),
+                LogView {
+                    anchor,
+                    search: self.search.clone().filter(|s| !s.is_empty()),
+                    last_search: self.last_search.take(),
+                    grep: std::mem::take(&mut self.log_grep),
+                    streams: self.log_streams,
+                },
+            );
+        }
+        self.log_anchor = None;
+        self.log_filter = None;
+        self.log_range = None;
+        self.log_tail = None;
+        self.log_grep.clear();
+        self.log_offset = 0;
+        self.log_streams = LogStreams::default();
+        self.search = None;
+        self.logs.clear();
+        self.pending_logs.clear();
+        self.log_position = 0;
+    }
+
+    /// We could update the app or dispatch event on tick
+    pub async fn update_on_tick(&mut self) -> AppReturn {
+        if let Some(container) = self.pending_inspect.take() {
+            self.dispatch(IoEvent::InspectContainer(container)).await;
+        }
+        for id in self.watchdog.take_pending() {
+            self.dispatch(IoEvent::RestartContainer(id)).await;
+        }
+        // Follow the selection in the split view
+        let preview = if self.config.split_logs && self.state.is_monitoring() {
+            self.selected_container.clone()
+        } else {
+            None
+        };
+        if preview != self.preview_container {
+            self.preview_logs.clear();
+            self.preview_container = preview.clone();
+            self.dispatch(IoEvent::PreviewLogs(preview)).await;
+        }
+        if self.state.is_resource_view()
+            && self.resources_requested_at.elapsed() >= RESOURCES_REFRESH
+        {
+            self.request_resources().await;
+        }
+        if let Some(name) = &self.startup_logs {
+            let found = self
+                .containers
+                .iter()
+                .find(|c| c.name == *name || c.id.starts_with(name.as_str()))
+                .map(|c| c.id.clone());
+            if let Some(id) = found {
+                self.startup_logs = None;
+                self.notification = None;
+                self.selected_container = Some(id.clone());
+                self.selected_group = None;
+                self.open_logs(id).await;
+            }
+        }
+        if let AppState::Kiosk { panel } = self.state {
+            let cycle = Duration::from_secs(self.config.kiosk.cycle_seconds.max(1));
+            if self.kiosk_panel_since.elapsed() >= cycle {
+                self.state = AppState::Kiosk {
+                    panel: panel.next(),
+                };
+                self.kiosk_panel_since = Instant::now();
+            }
+        }
+        if self.inspected_tab() == Some(InspectTab::Processes)
+            && self.processes_requested_at.elapsed() >= PROCESSES_REFRESH
+        {
+            self.request_processes().await;
+        }
+        if self.inspected_tab() == Some(InspectTab::Database)
+            && self.database_requested_at.elapsed() >= DATABASE_REFRESH
+        {
+            self.request_database_stats().await;
+        }
+        AppReturn::Continue
+    }
+
+    /// Run the status command of the database in the inspected container
+    async fn request_database_stats(&mut self) {
+        self.database_requested_at = Instant::now();
+        let container = match self.inspected_container() {
+            Some(c) => c,
+            None => return,
+        };
+        let stats = match self.database_for(&container.image) {
+            Some(stats) => stats,
+            None => return,
+        };
+        let command = stats
+            .command
+            .clone()
+            .unwrap_or_else(|| databases::command(stats.engine).to_string());
+        let id = container.id.clone();
+        self.database_commands.insert(id.clone(), command.clone());
+        self.dispatch(IoEvent::Exec {
+            container: id,
+            command,
+        })
+        .await;
+    }
+
+    async fn request_processes(&mut self) {
+        if let AppState::Inspecting { container, .. } = &self.state {
+            self.processes_requested_at = Instant::now();
+            self.dispatch(IoEvent::ListProcesses(container.clone()))
+                .await;
+        }
+    }
+
+    /// Mark or unmark the selected container, or all the visible containers
+    /// of the selected project
+    fn toggle_mark(&mut self) {
+        let ids: Vec<String> = if let Some(stack) = &self.selected_group {
+            self.visible_containers()
+                .into_iter()
+                .filter(|c| c.stack() == *stack)
+                .map(|c| c.id.clone())
+                .collect()
+        } else {
+            self.selected_container.iter().cloned().collect()
+        };
+        if ids.iter().all(|id| self.marked.contains(id)) {
+            for id in ids {
+                self.marked.remove(&id);
+            }
+        } else {
+            self.marked.extend(ids);
+        }
+    }
+
+    /// Mark every container matching the selector, among the ones the table
+    /// can show whatever the search or the active filter
+    fn mark_matching(&mut self, selector: &str) {
+        let filter = match ContainerFilter::parse(selector) {
+            Ok(f) => f,
+            Err(e) => {
+                self.notify(Notification::error(format!("Invalid selector: {}", e)));
+                return;
+            }
+        };
+        let regex = match filter.name_regex.as_deref().map(Regex::new).transpose() {
+            Ok(re) => re,
+            Err(e) => {
+                self.notify(Notification::error(format!("Invalid selector: {}", e)));
+                return;
+            }
+        };
+        let ids: Vec<String> = self
+            .containers
+            .iter()
+            .filter(|c| self.config.show_all || c.status.is_active())
+            .filter(|c| filter.matches_labels(&c.labels))
+            .filter(|c| match &regex {
+                Some(re) => re.is_match(&c.name),
+                None => true,
+            })
+            .map(|c| c.id.clone())
+            .collect();
+        debug!("Selector {} marked {} containers", selector, ids.len());
+        self.marked.extend(ids);
+    }
+
+    /// Marked containers the table shows, or the selected one when none is
+    /// marked. Marks hidden by the search or the filter are left out.
+    fn batch_targets(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            self.selected_container.iter().cloned().collect()
+        } else {
+            self.visible_containers()
+                .into_iter()
+                .filter(|c| self.marked.contains(&c.id))
+                .map(|c| c.id.clone())
+                .collect()
+        }
+    }
+
+    /// Send an event for each marked container, or for the selected one when
+    /// none is marked. Marks are cleared once used, the hidden ones are kept.
+    async fn dispatch_batch(&mut self, event: fn(String) -> IoEvent) {
+        let targets = self.batch_targets();
+        for id in &targets {
+            self.marked.remove(id);
+        }
+        for id in targets {
+            self.dispatch(event(id)).await;
+        }
+    }
+
+    /// Like `dispatch_batch`, asking first unless confirmations are turned off
+    /// Protected containers always ask for a typed phrase
+    async fn confirm_batch(&mut self, verb: &'static str, event: fn(String) -> IoEvent) {
+        let targets = self.batch_targets();
+        let protected = targets.iter().any(|id| self.is_protected(id));
+        if !self.config.confirm_destructive && !protected {
+            self.dispatch_batch(event).await;
+            return;
+        }
+        if !targets.is_empty() {
+            self.confirmation = Some(Confirmation {
+                verb,
+                resource: Resource::Container,
+                event,
+                targets,
+                phrase: protected.then(|| format!("{} protected", verb.to_lowercase())),
+                typed: String::new(),
+            });
+        }
+    }
+
+    /// Ask to remove the stopped containers, or the dangling images or the
+    /// volumes no container mounts in their views, always listing them first
+    async fn confirm_prune(&mut self) {
+        let (resource, event, targets): (Resource, fn(String) -> IoEvent, Vec<String>) =
+            match self.state {
+                AppState::Images => (
+                    Resource::Image,
+                    IoEvent::RemoveImage,
+                    self.images
+                        .iter()
+                        .filter(|i| i.tags.is_empty() && !matches!(i.containers, Some(n) if n > 0))
+                        .map(|i| i.id.clone())
+                        .collect(),
+                ),
+                AppState::Volumes => (
+                    Resource::Volume,
+                    IoEvent::RemoveVolume,
+                    self.volumes
+                        .iter()
+                        .filter(|v| {
+                            !self.containers.iter().any(|c| {
+                                c.mounts.iter().any(|m| m.name.as_deref() == Some(&v.name))
+                            })
+                        })
+                        .map(|v| v.name.clone())
+                        .collect(),
+                ),
+                _ if self.state.is_monitoring() => (
+                    Resource::Container,
+                    IoEvent::RemoveContainer,
+                    self.containers
+                        .iter()
+                        .filter(|c| {
+                            matches!(
+                                c.status,
+                                ContainerStatus::Created
+                                    | ContainerStatus::Exited
+                                    | ContainerStatus::Stopped
+                                    | ContainerStatus::Dead
+                            )
+                        })
+                        .map(|c| c.id.clone())
+                        .collect(),
+                ),
+                _ => return,
+            };
+        if targets.is_empty() {
+            self.notify(Notification::success(format!(
+                "Nothing to prune, no {} left over",
+                resource.noun()
+            )));
+            return;
+        }
+        let protected =
+            resource == Resource::Container && targets.iter().any(|id| self.is_protected(id));
+        self.confirmation = Some(Confirmation {
+            verb: "Prune",
+            resource,
+            event,
+            targets,
+            phrase: protected.then(|| "prune protected".to_string()),
+            typed: String::new(),
+        });
+    }
+
+    /// `y`, or Enter after the phrase for protected containers, runs the
+    /// pending action. Esc cancels it keeping the marks.
+    async fn answer_confirmation(&mut self, key: Key) {
+        let confirmation = self.confirmation.as_mut().unwrap();
+        let confirmed = match (&confirmation.phrase, key) {
+            (_, Key::Esc) => {
+                self.confirmation = None;
+                return;
+            }
+            (Some(phrase), Key::Enter) => confirmation.typed == *phrase,
+            (Some(_), Key::Backspace) => {
+                confirmation.typed.pop();
+                false
+            }
+            (Some(_), Key::Char(c)) => {
+                confirmation.typed.push(c);
+                false
+            }
+            (None, Key::Char('y') | Key::Char('Y')) => true,
+            (None, Key::Char('n') | Key::Char('N') | Key::Char('q')) => {
+                self.confirmation = None;
+                return;
+            }
+            _ => false,
+        };
+        if confirmed {
+            let confirmation = self.confirmation.take().unwrap();
+            self.marked.clear();
+            for id in confirmation.targets {
+                self.dispatch((confirmation.event)(id)).await;
+            }
+        }
+    }
+
+    /// Whether the container carries the protection label
+    pub fn is_protected(&self, id: &str) -> bool {
+        let label = &self.config.protection_label;
+        !label.is_empty()
+            && self
+                .containers
+                .iter()
+                .any(|c| c.id == id && has_label(&c.labels, label))
+    }
+
+    /// Let the watchdog restart the container, recording why it did
+    fn watch(&mut self, id: &str, trigger: Trigger) {
+        let c = match self.containers.iter().find(|c| c.id == id) {
+            Some(c) => c.clone(),
+            None => return,
+        };
+        if !self.watchdog.check(&self.config.watchdog, &c, trigger) {
+            return;
+        }
+        info!("Watchdog restarting {}: {}", c.name, trigger);
+        self.add_engine_event(EngineEvent {
+            at: Utc::now(),
+            kind: "watchdog".to_string(),
+            action: "restart".to_string(),
+            actor: format!("{} ({})", c.name, trigger),
+        });
+        self.notify(Notification::progress(format!(
+            "Watchdog restarting {} ({})",
+            c.name, trigger
+        )));
+    }
+
+    /// Send a network event to the IO thread
+    pub async fn dispatch(&mut self, action: IoEvent) {
+        if self.dry_run {
+            if let IoEvent::StopContainer(id)
+            | IoEvent::PauseContainer(id)
+            | IoEvent::RestartContainer(id)
+            | IoEvent::RemoveContainer(id)
+            | IoEvent::RecreateContainer(id) = &action
+            {
+                let c = self.containers.iter().find(|c| c.id == *id);
+                if let Some(calls) = c.and_then(|c| dry_run::api_calls(&action, c)) {
+                    info!("Dry run, not sent: {:?}", action);
+                    self.dry_run_calls.push(DryRunCall {
+                        target: c.unwrap().name.clone(),
+                        calls,
+                    });
+                }
+                return;
+            }
+            if let Some(skipped) = dry_run::removal_call(&action) {
+                info!("Dry run, not sent: {:?}", action);
+                self.dry_run_calls.push(skipped);
+                return;
+            }
+        }
+        if let IoEvent::StopContainer(id)
+        | IoEvent::RemoveContainer(id)
+        | IoEvent::RecreateContainer(id) = &action
+        {
+            self.watchdog.stopped_by_user(id);
+        }
+        if let Err(_e) = self.io_tx.send(action).await {
+            // error!("Error from dispatch {}", e);
+        };
+    }
+
+    pub fn actions(&self) -> &Actions {
+        &self.actions
+    }
+    /// IO events sent but not handled yet
+    pub fn io_queue_depth(&self) -> usize {
+        self.io_tx.max_capacity() - self.io_tx.capacity()
+    }
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+    pub fn containers(&self) -> &Vec<Container> {
+        &self.containers
+    }
+    pub fn selected_container(&self) -> &Option<String> {
+        &self.selected_container
+    }
+    /// Position of the selected row in the monitoring table
+    pub fn selected_row_index(&self) -> Option<usize> {
+        self.selected_row_in(&self.table_rows())
+    }
+    /// Position of the selected row among rows already built for a draw
+    pub fn selected_row_in(&self, rows: &[TableRow]) -> Option<usize> {
+        rows.iter().position(|row| match row {
+            TableRow::Group { stack, .. } => self.selected_group.as_ref() == Some(stack),
+            TableRow::Container(c) => self.selected_container.as_ref() == Some(&c.id),
+        })
+    }
+    /// Rows of the monitoring table: each project followed by its containers
+    /// unless collapsed, then the containers that don't belong to any. The
+    /// projects come in the order of their first container in the sort.
+    pub fn table_rows(&self) -> Vec<TableRow<'_>> {
+        let mut groups: Vec<(String, Vec<&Container>)> = Vec::new();
+        let mut ungrouped = Vec::new();
+        for c in self.visible_containers() {
+            let stack = c.stack();
+            if stack.is_empty() {
+                ungrouped.push(c);
+            } else if let Some((_, containers)) = groups.iter_mut().find(|(s, _)| *s == stack) {
+                containers.push(c);
+            } else {
+                groups.push((stack, vec![c]));
+            }
+        }
+
+        let mut rows = Vec::new();
+        for (stack, containers) in groups {
+            // Searching expands everything so matches are never hidden
+            let collapsed =
+                self.container_search.is_none() && self.collapsed_groups.contains(&stack);
+            let shown = if collapsed {
+                Vec::new()
+            } else {
+                containers.clone()
+            };
+            rows.push(TableRow::Group {
+                stack,
+                containers,
+                collapsed,
+            });
+            rows.extend(shown.into_iter().map(TableRow::Container));
+        }
+        rows.extend(ungrouped.into_iter().map(TableRow::Container));
+        rows
+    }
+    /// Containers shown in the monitoring table once the active filter is applied
+    pub fn visible_containers(&self) -> Vec<&Container> {
+        self.containers
+            .iter()
+            .filter(|c| self.is_visible(c))
+            .collect()
+    }
+    /// Saved filters, the ones of the active profile after the global ones
+    pub fn filters(&self) -> Vec<&ContainerFilter> {
+        let profile_filters = self.active_profile().map(|p| p.filters.as_slice());
+        self.config
+            .filters
+            .iter()
+            .chain(profile_filters.unwrap_or_default())
+            .collect()
+    }
+    pub fn active_filter(&self) -> Option<&ContainerFilter> {
+        self.filter.and_then(|i| self.filters().get(i).copied())
+    }
+
+    /// Switch to the next saved filter, going back to no filter after the last one
+    fn cycle_filter(&mut self) {
+        let next = self.filter.map_or(0, |i| i + 1);
+        self.filter = if next < self.filters().len() {
+            Some(next)
+        } else {
+            None
+        };
+        self.filter_error = None;
+        let pattern = self.active_filter().and_then(|f| f.name_regex.clone());
+        self.filter_regex = match pattern.as_deref().map(Regex::new).transpose() {
+            Ok(re) => re,
+            Err(e) => {
+                let message = format!("invalid name_regex: {}", e);
+                self.notify(Notification::error(message.clone()));
+                self.filter_error = Some(message);
+                None
+            }
+        };
+        self.select_visible_container();
+    }
+
+    pub fn filter_error(&self) -> Option<&str> {
+        self.filter_error.as_deref()
+    }
+
+    /// Selected profile, or the one matching the engine host name
+    pub fn active_profile(&self) -> Option<&EngineProfile> {
+        self.profile
+            .or(self.auto_profile)
+            .and_then(|i| self.config.profiles.get(i))
+    }
+    /// `None` while the clock is being read, `Some(None)` if it could not be
+    pub fn clock_skew(&self, id: &str) -> Option<Option<i64>> {
+        self.clock_skew.get(id).copied()
+    }
+    pub fn memory_calculation(&self) -> MemoryCalculation {
+        self.config.memory_calculation
+    }
+    pub fn byte_units(&self) -> ByteUnits {
+        self.config.units
+    }
+    pub fn thresholds(&self) -> Thresholds {
+        self.active_profile()
+            .and_then(|p| p.thresholds)
+            .unwrap_or(self.config.thresholds)
+    }
+    pub fn columns(&self) -> &[Column] {
+        self.active_profile()
+            .and_then(|p| p.columns.as_deref())
+            .unwrap_or(&self.config.columns)
+    }
+
+    /// Connect to the next engine profile, the local engine coming after the last one
+    async fn cycle_profile(&mut self) {
+        let next = self.profile.map_or(0, |i| i + 1);
+        self.previous_profile = self.profile;
+        self.profile = if next < self.config.profiles.len() {
+            Some(next)
+        } else {
+            None
+        };
+        // Containers of the previous engine are meaningless from now on
+        self.containers.clear();
+        self.images.clear();
+        self.volumes.clear();
+        self.networks.clear();
+        self.engine_events.clear();
+        self.resource_selected = 0;
+        self.health_history.clear();
+        self.stats_history.clear();
+        self.availability.clear();
+        self.last_sample.clear();
+        self.selected_container = None;
+        self.selected_group = None;
+        // Picked again once the new engine reports its host name
+        self.auto_profile = None;
+        self.filter = None;
+        self.filter_regex = None;
+        self.filter_error = None;
+        // Probed URLs point at the previous engine host
+        for key in std::mem::take(&mut self.probes).into_keys() {
+            self.dispatch(IoEvent::StopProbe(key)).await;
+        }
+        let profile = self.profile.and_then(|i| self.config.profiles.get(i));
+        self.dispatch(IoEvent::Connect(profile.cloned())).await;
+    }
+
+    fn is_visible(&self, c: &Container) -> bool {
+        if !self.config.show_all && !c.status.is_active() {
+            return false;
+        }
+        if let Some(text) = &self.container_search {
+            let text = text.to_lowercase();
+            let matches = [c.name.clone(), c.image.clone(), c.service(), c.stack()]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&text));
+            if !matches {
+                return false;
+            }
+        }
+        let filter = match self.active_filter() {
+            Some(f) => f,
+            None => return true,
+        };
+        if !filter.matches_labels(&c.labels) {
+            return false;
+        }
+        if filter.name_regex.is_some() {
+            // An invalid expression hides everything rather than ignoring the criteria
+            match &self.filter_regex {
+                Some(re) if re.is_match(&c.name) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+    /// Name shown in the table, rewritten by the name rules
+    pub fn short_name(&self, c: &Container) -> String {
+        self.name_rules
+            .iter()
+            .fold(c.display_name(), |name, (re, replace)| {
+                re.replace_all(&name, replace.as_str()).into_owned()
+            })
+    }
+    pub fn engine_arch(&self) -> Option<&str> {
+        self.engine_arch.as_deref()
+    }
+    /// Image built for another architecture than the host, run through emulation
+    pub fn is_emulated(&self, c: &Container) -> bool {
+        match (c.arch(), self.engine_arch()) {
+            (Some(arch), Some(host)) => arch != host,
+            _ => false,
+        }
+    }
+    /// Keys declared as going to an embedded session
+    pub fn passthrough_keys(&self) -> Vec<Key> {
+        self.config
+            .session
+            .passthrough
+            .iter()
+            .filter_map(|k| match k.parse::<Key>() {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    warn!("Invalid passthrough key: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+    /// Key leaving an embedded session
+    pub fn detach_key(&self) -> Key {
+        self.config.session.detach.parse().unwrap_or(Key::Ctrl(']'))
+    }
+    fn restart_window(&self) -> Duration {
+        Duration::from_secs(self.thresholds().restart_window_minutes * 60)
+    }
+    /// Restarted more than the threshold within the restart window
+    /// Why the container needs attention, if it does
+    pub fn alert(&self, c: &Container) -> Option<String> {
+        let thresholds = self.thresholds();
+        let reason = match c.status {
+            _ if self.is_crash_looping(c) => format!("LOOP x{}", c.restart_count),
+            ContainerStatus::Exited | ContainerStatus::Stopped | ContainerStatus::Dead => {
+                "DOWN".to_string()
+            }
+            ContainerStatus::Restarting => "RESTARTING".to_string(),
+            _ if c.cpu_usage >= thresholds.cpu => format!("CPU {:.2}%", c.cpu_usage),
+            _ if ui::memory_ratio(c) >= thresholds.memory => {
+                format!("MEM {:.0}%", ui::memory_ratio(c) * 100.0)
+            }
+            _ if ui::pids_near_limit(c, thresholds) => format!("PIDS {}", ui::label_for_pids(c)),
+            _ => return None,
+        };
+        Some(reason)
+    }
+    pub fn is_crash_looping(&self, c: &Container) -> bool {
+        let window = self.restart_window();
+        let recent = self.restarts.get(&c.id).map_or(0, |restarts| {
+            restarts.iter().filter(|at| at.elapsed() < window).count()
+        });
+        recent > self.thresholds().restarts
+    }
+    /// Database stats configured for an image, the first match winning
+    pub fn database_for(&self, image: &str) -> Option<&DatabaseStats> {
+        self.config
+            .databases
+            .iter()
+            .find(|d| match Regex::new(&d.image_regex) {
+                Ok(re) => re.is_match(image),
+                Err(e) => {
+                    warn!("Invalid image_regex {}: {}", d.image_regex, e);
+                    false
+                }
+            })
+    }
+    pub fn database_report(&self, id: &str) -> Option<&DatabaseReport> {
+        self.database_reports.get(id)
+    }
+    pub fn last_crash(&self, c: &Container) -> Option<&CrashReport> {
+        self.crashes.get(&c.service_key())
+    }
+    pub fn stats_history(&self, id: &str) -> Option<&VecDeque<StatsSample>> {
+        self.stats_history.get(id)
+    }
+    pub fn health_history(&self, id: &str) -> &[HealthTransition] {
+        self.health_history
+            .get(id)
+            .map(|h| h.as_slice())
+            .unwrap_or_default()
+    }
+    /// Time the container turned healthy for the first time since it started
+    pub fn ready_at(&self, c: &Container) -> Option<DateTime<Utc>> {
+        let started_at = c.started_at?;
+        self.health_history(&c.id)
+            .iter()
+            .find(|t| t.at >= started_at && t.health == Some(HealthStatus::Healthy))
+            .map(|t| t.at)
+    }
+    /// Session availability of the service of a container
+    pub fn service_availability(&self, c: &Container) -> Option<f32> {
+        self.availability
+            .get(&c.service_key())
+            .and_then(Availability::ratio)
+    }
+    /// Session availability of all the services of a stack / project
+    pub fn project_availability(&self, stack: &str) -> Option<f32> {
+        let keys: HashSet<String> = self
+            .containers
+            .iter()
+            .filter(|c| c.stack() == stack)
+            .map(Container::service_key)
+            .collect();
+        keys.iter()
+            .filter_map(|k| self.availability.get(k))
+            .fold(None, |acc: Option<Availability>, a| {
+                let mut acc = acc.unwrap_or_default();
+                acc.up += a.up;
+                acc.total += a.total;
+                Some(acc)
+            })
+            .and_then(|a| a.ratio())
+    }
+    pub fn logs(&self) -> &Vec<LogLine> {
+        &self.logs
+    }
+    pub fn log_position(&self) -> usize {
+        self.log_position
+    }
+    /// Number of lines waiting to be shown once back at the bottom
+    pub fn pending_logs_count(&self) -> usize {
+        self.pending_logs.len()
+    }
+    pub fn container_search(&self) -> &Option<String> {
+        &self.container_search
+    }
+    pub fn recent_containers(&self) -> &VecDeque<String> {
+        &self.recent_containers
+    }
+    pub fn search(&self) -> &Option<String> {
+        &self.search
+    }
+    /// Container shown by the detail view
+    pub fn inspected_container(&self) -> Option<&Container> {
+        match &self.state {
+            AppState::Inspecting { container, .. } => {
+                self.containers.iter().find(|c| c.id == *container)
+            }
+            _ => None,
+        }
+    }
+    /// Details of the inspected container, once fetched
+    pub fn container_details(&self) -> Option<&ContainerDetails> {
+        match &self.state {
+            AppState::Inspecting { container, .. } => self
+                .container_details
+                .as_ref()
+                .filter(|d| d.id == *container),
+            _ => None,
+        }
+    }
+    /// Environment variables of the inspected container matching the search
+    pub fn env_vars(&self) -> Vec<&str> {
+        let search = self
+            .env_search
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase();
+        self.container_details()
+            .map(|d| {
+                d.env
+                    .iter()
+                    .filter(|v| v.to_lowercase().contains(&search))
+                    .map(String::as_str)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    pub fn env_search(&self) -> Option<&str> {
+        self.env_search.as_deref()
+    }
+    pub fn env_search_typing(&self) -> bool {
+        self.env_search_typing
+    }
+    fn inspected_tab(&self) -> Option<InspectTab> {
+        match &self.state {
+            AppState::Inspecting { tab, .. } => Some(*tab),
+            _ => None,
+        }
+    }
+    /// Processes of the inspected container, each one after its parent,
+    /// `None` until they are listed
+    pub fn process_tree(&self) -> Option<Vec<ProcessRow<'_>>> {
+        let id = match &self.state {
+            AppState::Inspecting { container, .. } => container,
+            _ => return None,
+        };
+        let processes = self.processes.get(id)?;
+        let pids: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+        let mut children: HashMap<u32, Vec<&ProcessInfo>> = HashMap::new();
+        let mut roots = Vec::new();
+        for p in processes {
+            match p.ppid.filter(|ppid| pids.contains(ppid) && *ppid != p.pid) {
+                Some(ppid) => children.entry(ppid).or_default().push(p),
+                None => roots.push(p),
+            }
+        }
+
+        let mut rows = Vec::new();
+        let mut stack: Vec<(usize, &ProcessInfo)> =
+            roots.into_iter().rev().map(|p| (0, p)).collect();
+        while let Some((depth, process)) = stack.pop() {
+            let kids = children
+                .get(&process.pid)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            stack.extend(kids.iter().rev().map(|p| (depth + 1, *p)));
+            rows.push(ProcessRow {
+                depth,
+                process,
+                children: kids.len(),
+            });
+        }
+        Some(rows)
+    }
+    pub fn inspect_scroll(&self) -> u16 {
+        self.inspect_scroll
+    }
+    pub fn is_marked(&self, id: &str) -> bool {
+        self.marked.contains(id)
+    }
+    pub fn marked_count(&self) -> usize {
+        self.marked.len()
+    }
+    pub fn image_details(&self, image: &str) -> Option<&ImageDetails> {
+        self.image_details.get(image)
+    }
+    pub fn notes(&self) -> &Notes {
+        &self.notes
+    }
+    pub fn snippet_picker(&self) -> Option<&SnippetPicker> {
+        self.snippet_picker.as_ref()
+    }
+    pub fn log_range(&self) -> Option<&LogRange> {
+        self.log_range.as_ref()
+    }
+    /// Lines asked for when the logs may go further back than the fetched ones
+    pub fn log_tail(&self) -> Option<usize> {
+        match self.log_tail {
+            Some(tail) if self.log_range.is_none() && self.logs.len() >= tail => Some(tail),
+            _ => None,
+        }
+    }
+    pub fn prompt(&self) -> Option<&Prompt> {
+        self.prompt.as_ref()
+    }
+    pub fn errors(&self) -> &VecDeque<ReportedError> {
+        &self.errors
+    }
+    pub fn engine_summary(&self) -> Option<&EngineSummary> {
+        self.engine_summary.as_ref()
+    }
+    /// Startup summary shown over the table while it fills
+    pub fn splash_visible(&self) -> bool {
+        self.state.is_monitoring() && matches!(self.splash_until, Some(t) if Instant::now() < t)
+    }
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+    pub fn dry_run_calls(&self) -> &[DryRunCall] {
+        &self.dry_run_calls
+    }
+    pub fn errors_view(&self) -> Option<u16> {
+        self.errors_view
+    }
+    /// Notification to show, until it expires
+    pub fn notification(&self) -> Option<&Notification> {
+        let (notification, at) = self.notification.as_ref()?;
+        let duration = match notification.level {
+            NotificationLevel::Progress => PROGRESS_TIMEOUT,
+            NotificationLevel::Success | NotificationLevel::Error => NOTIFICATION_DURATION,
+        };
+        (at.elapsed() < duration).then_some(notification)
+    }
+    pub fn confirmation(&self) -> Option<&Confirmation> {
+        self.confirmation.as_ref()
+    }
+    pub fn forwards(&self) -> &[PortForward] {
+        &self.forwards
+    }
+    pub fn probe(&self, c: &Container) -> Option<&Probe> {
+        self.probes.get(&c.service_key())
+    }
+    pub fn forwards_view(&self) -> Option<usize> {
+        self.forwards_view
+    }
+    pub fn broadcast_count(&self) -> usize {
+        self.broadcast_targets().len()
+    }
+    pub fn package_comparison(&self) -> Option<&PackageComparison> {
+        self.package_comparison.as_ref()
+    }
+    pub fn exec_view(&self) -> Option<&ExecView> {
+        self.exec_view.as_ref()
+    }
+    pub fn images(&self) -> &[ImageSummary] {
+        &self.images
+    }
+    pub fn volumes(&self) -> &[VolumeSummary] {
+        &self.volumes
+    }
+    pub fn networks(&self) -> &[NetworkSummary] {
+        &self.networks
+    }
+    pub fn column_offset(&self) -> usize {
+        self.column_offset
+    }
+    pub fn set_screen_size(&mut self, size: Rect) {
+        self.screen = size;
+    }
+    pub fn preview_logs(&self) -> &VecDeque<String> {
+        &self.preview_logs
+    }
+    pub fn engine_events(&self) -> &VecDeque<EngineEvent> {
+        &self.engine_events
+    }
+    pub fn engine_events_total(&self) -> usize {
+        self.engine_events_total
+    }
+    pub fn resource_selected(&self) -> usize {
+        self.resource_selected
+    }
+    pub fn env_editor(&self) -> Option<&str> {
+        self.env_editor.as_deref()
+    }
+    /// Env changes waiting for the next recreation of the container
+    pub fn pending_env(&self, c: &Container) -> Option<&EnvOverride> {
+        self.env_overrides.get(&c.service_key())
+    }
+    pub fn pending_env_of(&self, id: &str) -> Option<&EnvOverride> {
+        self.containers
+            .iter()
+            .find(|c| c.id == id)
+            .and_then(|c| self.pending_env(c))
+    }
+    pub fn notes_editor(&self) -> &Option<String> {
+        &self.notes_editor
+    }
+    pub fn log_filter_editor(&self) -> Option<&str> {
+        self.log_filter_editor.as_deref()
+    }
+    pub fn debug_console(&self) -> bool {
+        self.debug_console
+    }
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+    pub fn sort(&self) -> SortOrder {
+        self.sort
+    }
+
+    /// Change the order of the container table
+    pub fn set_sort(&mut self, sort: SortOrder) {
+        self.sort = sort;
+        self.sort_containers();
+    }
+
+    fn sort_containers(&mut self) {
+        let sort = self.sort;
+        let now = Utc::now();
+        self.containers
+            .sort_by(|a, b| compare_containers(a, b, sort, now));
+    }
+
+    /// Move the selection to the first row when the selected one got filtered out
+    fn select_visible_container(&mut self) {
+        if self.selected_row_index().is_none() {
+            self.select_row(0);
+        }
+    }
+
+    fn select_row(&mut self, index: usize) {
+        let (group, container) = match self.table_rows().get(index) {
+            Some(TableRow::Group { stack, .. }) => (Some(stack.clone()), None),
+            Some(TableRow::Container(c)) => (None, Some(c.id.clone())),
+            None => (None, None),
+        };
+        self.selected_group = group;
+        self.selected_container = container;
+    }
+
+    pub fn next(&mut self) {
+        let index = match self.selected_row_index() {
+            Some(i) => (i + 1).min(self.table_rows().len().saturating_sub(1)),
+            None => 0,
+        };
+        self.select_row(index);
+    }
+
+    pub fn previous(&mut self) {
+        let index = match self.selected_row_index() {
+            Some(i) => i.saturating_sub(1),
+            None => 0,
+        };
+        self.select_row(index);
+    }
+}
+
+/// Order of two rows of the table, uptimes being measured at `now`
+fn compare_containers(
+    a: &Container,
+    b: &Container,
+    sort: SortOrder,
+    now: DateTime<Utc>,
+) -> Ordering {
+    let ordering = match sort.column {
+        SortColumn::Name => a.name.cmp(&b.name),
+        SortColumn::Cpu => a
+            .cpu_usage
+            .partial_cmp(&b.cpu_usage)
+            .unwrap_or(Ordering::Equal),
+        SortColumn::Memory => a
+            .memory_usage_bytes
+            .partial_cmp(&b.memory_usage_bytes)
+            .unwrap_or(Ordering::Equal),
+        SortColumn::Disk => a.size_rw.cmp(&b.size_rw),
+        SortColumn::Uptime => a.uptime_at(now).cmp(&b.uptime_at(now)),
+        SortColumn::Status => a.status.rank().cmp(&b.status.rank()),
+    };
+    let ordering = match sort.direction {
+        SortDirection::Asc => ordering,
+        SortDirection::Desc => ordering.reverse(),
+    };
+    // Ties keep a fixed order so rows don't jump on every refresh
+    ordering
+        .then_with(|| a.name.cmp(&b.name))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+impl ContainerManagement for App {
+    fn update_containers(&mut self, mut new_container: Container) {
+        new_container.memory_usage_bytes =
+            new_container.memory.used(self.config.memory_calculation);
+        let history = self
+            .health_history
+            .entry(new_container.id.clone())
+            .or_default();
+        if history.last().map(|t| t.health) != Some(new_container.health) {
+            history.push(HealthTransition {
+                at: Utc::now(),
+                health: new_container.health,
+            });
+            if history.len() > HEALTH_HISTORY_LIMIT {
+                history.remove(0);
+            }
+        }
+        let now = Instant::now();
+        self.stats_history.push(
+            &new_container.id,
+            StatsSample {
+                at: now,
+                cpu: new_container.cpu_usage,
+                memory: new_container.memory_usage_bytes,
+            },
+        );
+        if let Some(previous) = self.last_sample.insert(new_container.id.clone(), now) {
+            let elapsed = (now - previous).min(AVAILABILITY_MAX_SAMPLE);
+            let availability = self
+                .availability
+                .entry(new_container.service_key())
+                .or_default();
+            availability.total += elapsed;
+            if new_container.is_up() {
+                availability.up += elapsed;
+            }
+        }
+        let restarted = self
+            .containers
+            .iter()
+            .find(|c| c.id == new_container.id)
+            .map_or(0, |c| new_container.restart_count - c.restart_count);
+        if restarted > 0 {
+            let window = self.restart_window();
+            let restarts = self.restarts.entry(new_container.id.clone()).or_default();
+            restarts.retain(|at| now - *at < window);
+            restarts.extend((0..restarted).map(|_| now));
+        }
+        if matches!(new_container.status, ContainerStatus::Running) {
+            self.watchdog.running(&new_container.id);
+        }
+        let (id, streak) = (
+            new_container.id.clone(),
+            new_container.health_failing_streak,
+        );
+        self.containers.retain(|c| c.id != new_container.id);
+        self.containers.push(new_container);
+        self.sort_containers();
+        if streak > 0 {
+            self.watch(&id, Trigger::Unhealthy(streak));
+        }
+    }
+
+    fn remove_container(&mut self, id: &str) {
+        self.containers.retain(|c| c.id != id);
+        self.marked.remove(id);
+        self.health_history.remove(id);
+        self.stats_history.remove(id);
+        self.restarts.remove(id);
+        self.last_sample.remove(id);
+        self.log_views.remove(id);
+        self.recent_containers.retain(|c| c != id);
+    }
+
+    fn add_logs(&mut self, logs: Vec<LogLine>) {
+        let logs = match &self.log_filter {
+            Some(filter) => logs.into_iter().filter(|l| filter.keeps(&l.text)).collect(),
+            None => logs,
+        };
+        if self.log_position != 0 {
+            // Keep the lines under the user still while scrolled back, they
+            // moved away from the line of their previous visit
+            self.log_anchor = None;
+            self.pending_logs.extend(logs);
+            return;
+        }
+        let start = self.logs.len();
+        self.logs.extend(logs);
+        // The lines of a revisited container come in several batches, go back
+        // to where the user was once a line as recent as the one they left at
+        // the bottom arrives. Lines without a time can not be placed.
+        let anchor = match self.log_anchor {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        if self.logs[start..]
+            .iter()
+            .all(|l| matches!(l.at, Some(at) if at < anchor))
+        {
+            return;
+        }
+        self.log_anchor = None;
+        // The line itself, or the one before when it fell out of the tail
+        let found = self
+            .logs
+            .iter()
+            .rposition(|l| matches!(l.at, Some(at) if at <= anchor));
+        if let Some(i) = found {
+            self.log_position = self
+                .log_lines()
+                .iter()
+                .rev()
+                .position(|(j, _)| *j == Some(i))
+                .unwrap_or(0);
+        }
+    }
+
+    fn logs_to_export(&mut self, path: String, logs: Vec<String>) {
+        let notification = match export::write_logs(&path, &logs) {
+            Ok(count) => Notification::success(format!("{} lines written to {}", count, path)),
+            Err(e) => Notification::error(format!("Error writing {}: {}", path, e)),
+        };
+        self.notify(notification);
+    }
+
+    fn add_tty_output(&mut self, output: String) {
+        debug!("TTY Output: {}", output);
+    }
+
+    fn update_container_details(&mut self, details: ContainerDetails) {
+        self.container_details = Some(details);
+    }
+
+    fn update_processes(&mut self, container_id: String, processes: Vec<ProcessInfo>) {
+        // Only the inspected container is of interest
+        self.processes.clear();
+        self.processes.insert(container_id, processes);
+    }
+
+    fn forward_started(&mut self, forward: PortForward) {
+        self.forwards.retain(|f| f.local_port != forward.local_port);
+        self.forwards.push(forward);
+        self.forwards.sort_by_key(|f| f.local_port);
+    }
+
+    fn forward_stopped(&mut self, local_port: u16, error: Option<String>) {
+        if let Some(error) = error {
+            warn!("Port forward from {} failed: {}", local_port, error);
+            self.notify(Notification::error(format!(
+                "Port forward from {} failed: {}",
+                local_port, error
+            )));
+        }
+        self.forwards.retain(|f| f.local_port != local_port);
+        if let Some(selected) = self.forwards_view {
+            self.forwards_view = Some(selected.min(self.forwards.len().saturating_sub(1)));
+        }
+    }
+
+    fn notify(&mut self, notification: Notification) {
+        if notification.level == NotificationLevel::Error {
+            self.report_error(notification.message.clone());
+        }
+        self.notification = Some((notification, Instant::now()));
+    }
+
+    fn update_images(&mut self, images: Vec<ImageSummary>) {
+        self.images = images;
+        self.resource_selected = self
+            .resource_selected
+            .min(self.resource_count().saturating_sub(1));
+    }
+
+    fn update_volumes(&mut self, volumes: Vec<VolumeSummary>) {
+        self.volumes = volumes;
+        self.resource_selected = self
+            .resource_selected
+            .min(self.resource_count().saturating_sub(1));
+    }
+
+    fn update_networks(&mut self, networks: Vec<NetworkSummary>) {
+        self.networks = networks;
+        self.resource_selected = self
+            .resource_selected
+            .min(self.resource_count().saturating_sub(1));
+    }
+
+    fn add_preview_logs(&mut self, container_id: String, logs: Vec<String>) {
+        // Lines of the previous selection can still come while its task stops
+        if self.preview_container.as_ref() != Some(&container_id) {
+            return;
+        }
+        self.preview_logs.extend(logs);
+        while self.preview_logs.len() > PREVIEW_LOGS_LIMIT {
+            self.preview_logs.pop_front();
+        }
+    }
+
+    fn add_engine_event(&mut self, event: EngineEvent) {
+        self.engine_events.push_back(event);
+        self.engine_events_total += 1;
+        if self.engine_events.len() > ENGINE_EVENTS_LIMIT {
+            self.engine_events.pop_front();
+        }
+        // Keep the same event selected while new ones come on top
+        if self.state.is_events() && self.resource_selected > 0 {
+            self.resource_selected = (self.resource_selected + 1).min(self.engine_events.len() - 1);
+        }
+    }
+
+    fn connection_failed(&mut self, error: String) {
+        self.profile = self.previous_profile;
+        self.report_error(error.clone());
+        self.notify(Notification::error(error));
+    }
+
+    fn recreated(&mut self, old_id: String, new_id: String) {
+        if let Some(c) = self.containers.iter().find(|c| c.id == old_id) {
+            self.env_overrides
+                .set(&c.service_key(), EnvOverride::default());
+            if let Err(e) = self.env_overrides.save() {
+                warn!("Error saving env overrides: {}", e);
+            }
+        }
+        if self.selected_container.as_ref() == Some(&old_id) {
+            self.selected_container = Some(new_id.clone());
+        }
+        if let AppState::Inspecting { container, tab } = &self.state {
+            if *container == old_id {
+                self.state = AppState::Inspecting {
+                    container: new_id.clone(),
+                    tab: *tab,
+                };
+                self.container_details = None;
+                self.pending_inspect = Some(new_id);
+            }
+        }
+    }
+
+    fn report_error(&mut self, message: String) {
+        match self.errors.back_mut() {
+            Some(last) if last.message == message => {
+                last.count += 1;
+                last.at = Utc::now();
+            }
+            _ => {
+                self.errors.push_back(ReportedError {
+                    at: Utc::now(),
+                    message,
+                    count: 1,
+                });
+                if self.errors.len() > ERRORS_LIMIT {
+                    self.errors.pop_front();
+                }
+            }
+        }
+    }
+
+    fn probe_result(&mut self, service_key: String, result: ProbeResult) {
+        // Results arriving after the probe was stopped are dropped
+        if let Some(probe) = self.probes.get_mut(&service_key) {
+            probe.results.push_back(result);
+            if probe.results.len() > PROBE_RESULTS_LIMIT {
+                probe.results.pop_front();
+            }
+        }
+    }
+
+    fn record_crash(&mut self, container_id: String, crash: CrashReport) {
+        let key = self
+            .containers
+            .iter()
+            .find(|c| c.id == container_id)
+            .map_or(container_id.clone(), |c| c.service_key());
+        if let Some(code) = crash.exit_code {
+            self.watch(&container_id, Trigger::Exited(code));
+        }
+        self.crashes.insert(key, crash);
+    }
+
+    fn exec_finished(&mut self, result: ExecResult) {
+        debug!(
+            "{} exited with {:?} in {}",
+            result.command, result.exit_code, result.container_id
+        );
+        if result.command == CLOCK_COMMAND {
+            if let Some(requested_at) = self.clock_requested_at.remove(&result.container_id) {
+                // The clock was read somewhere between the request and now
+                let host = (requested_at + Utc::now().timestamp()) / 2;
+                let skew = result
+                    .output
+                    .first()
+                    .and_then(|line| line.trim().parse::<i64>().ok())
+                    .map(|clock| clock - host);
+                self.clock_skew.insert(result.container_id, skew);
+            }
+            return;
+        }
+        if self.database_commands.get(&result.container_id) == Some(&result.command) {
+            self.database_commands.remove(&result.container_id);
+            let engine = match self
+                .containers
+                .iter()
+                .find(|c| c.id == result.container_id)
+                .and_then(|c| self.database_for(&c.image))
+            {
+                Some(stats) => stats.engine,
+                None => return,
+            };
+            let error = match result.exit_code {
+                Some(0) => None,
+                _ => Some(result.output.join("\n")),
+            };
+            self.database_reports.insert(
+                result.container_id,
+                DatabaseReport {
+                    engine,
+                    values: databases::parse(&result.output),
+                    error,
+                },
+            );
+            return;
+        }
+        if result.command == packages::LIST_COMMAND {
+            if let Some(comparison) = self.package_comparison.as_mut() {
+                if comparison.containers.contains(&result.container_id) {
+                    comparison
+                        .results
+                        .insert(result.container_id.clone(), result);
+                }
+            }
+            return;
+        }
+        if let Some(view) = self.exec_view.as_mut() {
+            if view.containers.contains(&result.container_id) {
+                view.results
+                    .entry(result.container_id.clone())
+                    .or_insert(result);
+            }
+        }
+    }
+
+    fn set_engine_arch(&mut self, arch: String) {
+        self.engine_arch = Some(arch);
+    }
+
+    fn set_engine_summary(&mut self, summary: EngineSummary) {
+        self.engine_summary = Some(summary);
+        let until = Instant::now() + SPLASH_DURATION;
+        self.splash_until = self.splash_until.map(|t| t.min(until));
+    }
+
+    fn set_engine_host(&mut self, host: String) {
+        self.auto_profile = self.config.profiles.iter().position(|p| {
+            let pattern = match &p.host_regex {
+                Some(pattern) => pattern,
+                None => return false,
+            };
+            match Regex::new(pattern) {
+                Ok(re) => re.is_match(&host),
+                Err(e) => {
+                    warn!("Invalid host_regex {}: {}", pattern, e);
+                    false
+                }
+            }
+        });
+        debug!("Engine host: {}, profile: {:?}", host, self.auto_profile);
+    }
+
+    fn update_image_details(&mut self, details: ImageDetails) {
+        self.image_details
+            .insert(details.reference.clone(), details);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(column: SortColumn, direction: SortDirection) -> SortOrder {
+        SortOrder { column, direction }
+    }
+
+    fn names(containers: &[Container]) -> Vec<&str> {
+        containers.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    fn app(config: Config, containers: &[&str]) -> App {
+        let mut app = App::new(tokio::sync::mpsc::channel(1).0, config, None);
+        app.containers = containers
+            .iter()
+            .map(|name| Container::named(name))
+            .collect();
+        app
+    }
+
+    fn filter(selector: &str) -> ContainerFilter {
+        ContainerFilter::parse(selector).unwrap()
+    }
+
+    #[test]
+    fn uptime_sort_puts_the_stopped_containers_first() {
+        let now = Utc::now();
+        let mut old = Container::named("old");
+        old.started_at = Some(now - chrono::Duration::hours(2));
+        let mut new = Container::named("new");
+        new.started_at = Some(now - chrono::Duration::minutes(5));
+        let mut stopped = Container::named("stopped");
+        stopped.status = ContainerStatus::Exited;
+        stopped.started_at = Some(now - chrono::Duration::days(1));
+        let mut containers = vec![old, stopped, new];
+        let order = sort(SortColumn::Uptime, SortDirection::Asc);
+        containers.sort_by(|a, b| compare_containers(a, b, order, now));
+        assert_eq!(names(&containers), ["stopped", "new", "old"]);
+        let order = sort(SortColumn::Uptime, SortDirection::Desc);
+        containers.sort_by(|a, b| compare_containers(a, b, order, now));
+        assert_eq!(names(&containers), ["old", "new", "stopped"]);
+    }
+
+    #[test]
+    fn status_sort_follows_the_rank() {
+        let mut exited = Container::named("a");
+        exited.status = ContainerStatus::Exited;
+        let mut paused = Container::named("b");
+        paused.status = ContainerStatus::Paused;
+        let running = Container::named("c");
+        let mut containers = vec![exited, paused, running];
+        let order = sort(SortColumn::Status, SortDirection::Asc);
+        containers.sort_by(|a, b| compare_containers(a, b, order, Utc::now()));
+        assert_eq!(names(&containers), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn ties_keep_the_name_order_in_both_directions() {
+        let mut containers = vec![
+            Container::named("web"),
+            Container::named("api"),
+            Container::named("db"),
+        ];
+        for direction in [SortDirection::Asc, SortDirection::Desc] {
+            let order = sort(SortColumn::Cpu, direction);
+            containers.sort_by(|a, b| compare_containers(a, b, order, Utc::now()));
+            assert_eq!(names(&containers), ["api", "db", "web"]);
+        }
+    }
+
+    #[test]
+    fn cpu_and_memory_sorts() {
+        let mut busy = Container::named("busy");
+        busy.cpu_usage = 80.0;
+        busy.memory_usage_bytes = 10.0;
+        let mut idle = Container::named("idle");
+        idle.cpu_usage = 1.0;
+        idle.memory_usage_bytes = 500.0;
+        let mut containers = vec![idle, busy];
+        let order = sort(SortColumn::Cpu, SortDirection::Desc);
+        containers.sort_by(|a, b| compare_containers(a, b, order, Utc::now()));
+        assert_eq!(names(&containers), ["busy", "idle"]);
+        let order = sort(SortColumn::Memory, SortDirection::Desc);
+        containers.sort_by(|a, b| compare_containers(a, b, order, Utc::now()));
+        assert_eq!(names(&containers), ["idle", "busy"]);
+        let order = sort(SortColumn::Name, SortDirection::Desc);
+        containers.sort_by(|a, b| compare_containers(a, b, order, Utc::now()));
+        assert_eq!(names(&containers), ["idle", "busy"]);
+    }
+
+    #[test]
+    fn filter_moves_the_selection_to_a_visible_row() {
+        let mut config = Config::default();
+        config.filters.push(filter("name:^api"));
+        let mut app = app(config, &["api", "web"]);
+        app.selected_container = Some("web-id".to_string());
+        app.cycle_filter();
+        assert_eq!(app.filter_error(), None);
+        assert_eq!(app.selected_container.as_deref(), Some("api-id"));
+        assert!(!app.is_visible(&app.containers[1]));
+        app.cycle_filter();
+        assert!(app.active_filter().is_none());
+        assert!(app.containers.iter().all(|c| app.is_visible(c)));
+    }
+
+    #[test]
+    fn invalid_regex_is_reported() {
+        let mut config = Config::default();
+        config.filters.push(filter("name:("));
+        let mut app = app(config, &["api"]);
+        app.cycle_filter();
+        assert!(app
+            .filter_error()
+            .unwrap()
+            .starts_with("invalid name_regex"));
+        assert!(!app.is_visible(&app.containers[0]));
+        app.cycle_filter();
+        assert_eq!(app.filter_error(), None);
+    }
+
+    fn row_labels(app: &App) -> Vec<String> {
+        app.table_rows()
+            .iter()
+            .map(|row| match row {
+                TableRow::Group {
+                    stack, containers, ..
+                } => format!("{} ({})", stack, containers.len()),
+                TableRow::Container(c) => c.name.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn projects_follow_the_sort_and_collapse() {
+        let mut app = app(
+            Config::default(),
+            &["blog-db", "shop-api", "shop-web", "lone"],
+        );
+        for (c, (project, cpu)) in app.containers.iter_mut().zip([
+            (Some("blog"), 5.0),
+            (Some("shop"), 60.0),
+            (Some("shop"), 1.0),
+            (None, 30.0),
+        ]) {
+            c.compose_project = project.map(str::to_string);
+            c.cpu_usage = cpu;
+        }
+        app.sort = sort(SortColumn::Cpu, SortDirection::Desc);
+        app.sort_containers();
+        assert_eq!(
+            row_labels(&app),
+            ["shop (2)", "shop-api", "shop-web", "blog (1)", "blog-db", "lone"]
+        );
+        app.collapsed_groups.insert("shop".to_string());
+        assert_eq!(
+            row_labels(&app),
+            ["shop (2)", "blog (1)", "blog-db", "lone"]
+        );
+        app.container_search = Some("web".to_string());
+        assert_eq!(row_labels(&app), ["shop (1)", "shop-web"]);
+    }
+
+    #[test]
+    fn batch_skips_the_hidden_marks() {
+        let mut app = app(Config::default(), &["api", "db", "web"]);
+        app.selected_container = Some("db-id".to_string());
+        assert_eq!(app.batch_targets(), ["db-id"]);
+        app.marked
+            .extend(["web-id".to_string(), "api-id".to_string()]);
+        assert_eq!(app.batch_targets(), ["api-id", "web-id"]);
+        app.container_search = Some("we".to_string());
+        assert_eq!(app.batch_targets(), ["web-id"]);
+        app.container_search = Some("db".to_string());
+        assert!(app.batch_targets().is_empty());
+    }
+
+    fn with_logs(app: &mut App, lines: &[&str]) {
+        app.add_logs(
+            lines
+                .iter()
+                .map(|text| LogLine {
+                    text: text.to_string(),
+                    stderr: false,
+                    at: None,
+                })
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn scrolling_stays_in_the_logs_and_holds_new_lines() {
+        let mut app = app(Config::default(), &[]);
+        with_logs(&mut app, &["a", "b", "c", "d", "e"]);
+        app.scroll_logs(2);
+        assert_eq!(app.log_position, 2);
+        app.scroll_logs(isize::MAX);
+        assert_eq!(app.log_position, 4);
+        with_logs(&mut app, &["f"]);
+        assert_eq!(app.shown_logs().len(), 5);
+        app.scroll_logs(-1);
+        assert_eq!(app.log_position, 3);
+        app.scroll_logs(isize::MIN);
+        assert_eq!(app.log_position, 0);
+        assert_eq!(app.shown_logs(), ["a", "b", "c", "d", "e", "f"]);
+    }
+
+    #[test]
+    fn matches_are_found_both_ways() {
+        let mut app = app(Config::default(), &[]);
+        with_logs(&mut app, &["a ERROR", "b", "c error", "d", "e"]);
+        app.search = Some("error".to_string());
+        app.jump_to_match(true);
+        assert_eq!(app.log_position, 2);
+        app.jump_to_match(true);
+        assert_eq!(app.log_position, 4);
+        // Nothing older, the viewer stays on the last match
+        app.jump_to_match(true);
+        assert_eq!(app.log_position, 4);
+        app.jump_to_match(false);
+        assert_eq!(app.log_position, 2);
+        app.jump_to_match(false);
+        assert_eq!(app.log_position, 2);
+        app.search = Some(String::new());
+        app.jump_to_match(true);
+        assert_eq!(app.log_position, 2);
+    }
+
+    #[test]
+    fn env_search_ignores_the_case() {
+        let mut app = app(Config::default(), &[]);
+        app.container_details = Some(ContainerDetails {
+            id: "api-id".to_string(),
+            env: vec![
+                "DATABASE_URL=postgres://db".to_string(),
+                "PORT=80".to_string(),
+            ],
+            ..ContainerDetails::default()
+        });
+        assert!(app.env_vars().is_empty());
+        app.state = AppState::Inspecting {
+            container: "api-id".to_string(),
+            tab: InspectTab::Env,
+        };
+        assert_eq!(app.env_vars().len(), 2);
+        app.env_search = Some("postgres".to_string());
+        assert_eq!(app.env_vars(), ["DATABASE_URL=postgres://db"]);
+        app.env_search = Some("port".to_string());
+        assert_eq!(app.env_vars(), ["PORT=80"]);
+        app.state = AppState::Inspecting {
+            container: "web-id".to_string(),
+            tab: InspectTab::Env,
+        };
+        assert!(app.env_vars().is_empty());
+    }
+
+    #[test]
+    fn project_availability_weighs_the_observed_time() {
+        let mut app = app(Config::default(), &["shop-api", "shop-web", "lone"]);
+        for c in &mut app.containers[..2] {
+            c.compose_project = Some("shop".to_string());
+        }
+        let seconds = |up, total| Availability {
+            up: Duration::from_secs(up),
+            total: Duration::from_secs(total),
+        };
+        app.availability
+            .insert("shop-api".to_string(), seconds(30, 60));
+        app.availability
+            .insert("shop-web".to_string(), seconds(180, 180));
+        assert_eq!(app.service_availability(&app.containers[0]), Some(0.5));
+        assert_eq!(app.project_availability("shop"), Some(0.875));
+        assert_eq!(app.service_availability(&app.containers[2]), None);
+        assert_eq!(app.project_availability("blog"), None);
+    }
+
+    #[test]
+    fn passthrough_keys_skip_the_invalid_ones() {
+        let mut config = Config::default();
+        config.session.passthrough = vec!["ctrl+b".into(), "ctrl+bb".into(), "esc".into()];
+        let app = app(config, &[]);
+        assert_eq!(app.passthrough_keys(), [Key::Ctrl('b'), Key::Esc]);
+        assert_eq!(app.detach_key(), Key::Ctrl(']'));
     }
 }
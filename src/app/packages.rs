@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+/// Lists the installed packages as `name version` lines with whichever
+/// package managers the image has, python packages prefixed with `pip:`
+pub const LIST_COMMAND: &str = "\
+if command -v dpkg-query >/dev/null 2>&1; then dpkg-query -W -f='${Package} ${Version}\\n'; \
+elif command -v apk >/dev/null 2>&1; then apk info -v 2>/dev/null | sed -E 's/-([0-9][^-]*-r[0-9]+)$/ \\1/'; \
+elif command -v rpm >/dev/null 2>&1; then rpm -qa --qf '%{NAME} %{VERSION}-%{RELEASE}\\n'; fi; \
+if command -v pip >/dev/null 2>&1; then pip freeze 2>/dev/null | sed -e 's/==/ /' -e 's/^/pip:/'; fi";
+
+/// Difference of a package between two containers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageChange {
+    /// Only in the second container
+    Added { name: String, version: String },
+    /// Only in the first container
+    Removed { name: String, version: String },
+    Changed {
+        name: String,
+        from: String,
+        to: String,
+    },
+}
+
+/// Parse the output of `LIST_COMMAND`, by package name
+pub fn parse(output: &[String]) -> BTreeMap<String, String> {
+    output
+        .iter()
+        .filter_map(|line| line.trim().split_once(' '))
+        .map(|(name, version)| (name.to_string(), version.trim().to_string()))
+        .collect()
+}
+
+/// Packages that differ between two listings, sorted by name
+pub fn diff(
+    left: &BTreeMap<String, String>,
+    right: &BTreeMap<String, String>,
+) -> Vec<PackageChange> {
+    let mut changes = Vec::new();
+    for (name, from) in left {
+        match right.get(name) {
+            Some(to) if to != from => changes.push(PackageChange::Changed {
+                name: name.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            Some(_) => {}
+            None => changes.push(PackageChange::Removed {
+                name: name.clone(),
+                version: from.clone(),
+            }),
+        }
+    }
+    for (name, version) in right {
+        if !left.contains_key(name) {
+            changes.push(PackageChange::Added {
+                name: name.clone(),
+                version: version.clone(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.name().cmp(b.name()));
+    changes
+}
+
+impl PackageChange {
+    pub fn name(&self) -> &str {
+        match self {
+            PackageChange::Added { name, .. }
+            | PackageChange::Removed { name, .. }
+            | PackageChange::Changed { name, .. } => name,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::ops::Range;
+
+use super::ansi;
+
+/// Positions, in chars of the text without escape sequences, of the
+/// occurrences of the search in a log line. Case is ignored unless
+/// `case_sensitive`.
+pub fn find(line: &str, search: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let text: Vec<char> = ansi::strip(line).chars().map(fold).collect();
+    let search: Vec<char> = search.chars().map(fold).collect();
+    let mut found = Vec::new();
+    if search.is_empty() {
+        return found;
+    }
+    let mut i = 0;
+    while i + search.len() <= text.len() {
+        if text[i..i + search.len()] == search[..] {
+            found.push(i..i + search.len());
+            i += search.len();
+        } else {
+            i += 1;
+        }
+    }
+    found
+}
+
+pub fn is_match(line: &str, search: &str, case_sensitive: bool) -> bool {
+    !find(line, search, case_sensitive).is_empty()
+}
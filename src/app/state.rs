@@ -1,15 +1,182 @@
+use std::fmt::{self, Display};
+
 use super::actions::{Action, Actions};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum AppState {
+    #[default]
     Monitoring,
-    Logging { container: String },
-    Inspecting { container: String },
+    Logging {
+        container: String,
+        file: Option<String>,
+    },
+    /// Logs of every container of a compose project or swarm stack
+    StackLogs {
+        stack: String,
+    },
+    Inspecting {
+        container: String,
+        tab: InspectTab,
+    },
+    Kiosk {
+        panel: KioskPanel,
+    },
+    StartOrder {
+        project: String,
+    },
+    /// CPU and memory history of a container
+    Graph {
+        container: String,
+    },
+    /// Volumes and host paths mounted by several containers
+    SharedVolumes,
+    Images,
+    Volumes,
+    Networks,
+    /// Events of the engine, the latest first
+    Events,
+}
+
+/// Resource types listed in the tab bar, switched with the number keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Containers,
+    Images,
+    Volumes,
+    Networks,
+    Events,
+}
+
+impl View {
+    pub const ALL: [View; 5] = [
+        View::Containers,
+        View::Images,
+        View::Volumes,
+        View::Networks,
+        View::Events,
+    ];
+
+    /// View of a number key, `1` being the first
+    pub fn from_key(c: char) -> Option<Self> {
+        let n = c.to_digit(10)? as usize;
+        Self::ALL.get(n.checked_sub(1)?).copied()
+    }
+
+    /// State showing the view, the containers starting in the table
+    pub fn state(&self) -> AppState {
+        match self {
+            View::Containers => AppState::Monitoring,
+            View::Images => AppState::Images,
+            View::Volumes => AppState::Volumes,
+            View::Networks => AppState::Networks,
+            View::Events => AppState::Events,
+        }
+    }
+}
+
+impl Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            View::Containers => "Containers",
+            View::Images => "Images",
+            View::Volumes => "Volumes",
+            View::Networks => "Networks",
+            View::Events => "Events",
+        };
+        write!(f, "{}", str)
+    }
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::Monitoring
+/// Panels shown in turn by the kiosk mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KioskPanel {
+    Leaderboard,
+    ProjectSummary,
+    Alerts,
+}
+
+impl KioskPanel {
+    /// Panel displayed after this one
+    pub fn next(&self) -> Self {
+        match self {
+            KioskPanel::Leaderboard => KioskPanel::ProjectSummary,
+            KioskPanel::ProjectSummary => KioskPanel::Alerts,
+            KioskPanel::Alerts => KioskPanel::Leaderboard,
+        }
+    }
+}
+
+impl Display for KioskPanel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            KioskPanel::Leaderboard => "Top Containers",
+            KioskPanel::ProjectSummary => "Projects",
+            KioskPanel::Alerts => "Alerts",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Sections of the container detail view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectTab {
+    Overview,
+    Config,
+    Env,
+    State,
+    Processes,
+    Cores,
+    Ports,
+    Mounts,
+    Networks,
+    Labels,
+    Image,
+    Database,
+    Crash,
+}
+
+impl InspectTab {
+    pub const ALL: [InspectTab; 13] = [
+        InspectTab::Overview,
+        InspectTab::Config,
+        InspectTab::Env,
+        InspectTab::State,
+        InspectTab::Processes,
+        InspectTab::Cores,
+        InspectTab::Ports,
+        InspectTab::Mounts,
+        InspectTab::Networks,
+        InspectTab::Labels,
+        InspectTab::Image,
+        InspectTab::Database,
+        InspectTab::Crash,
+    ];
+
+    /// Tab displayed after this one
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|t| t == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl Display for InspectTab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            InspectTab::Overview => "Overview",
+            InspectTab::Config => "Config",
+            InspectTab::Env => "Env",
+            InspectTab::State => "State",
+            InspectTab::Processes => "Processes",
+            InspectTab::Cores => "Cores",
+            InspectTab::Ports => "Ports",
+            InspectTab::Mounts => "Mounts",
+            InspectTab::Networks => "Networks",
+            InspectTab::Labels => "Labels",
+            InspectTab::Image => "Image",
+            InspectTab::Database => "Database",
+            InspectTab::Crash => "Last Crash",
+        };
+        write!(f, "{}", str)
     }
 }
 
@@ -19,22 +186,110 @@ impl AppState {
             vec![
                 Action::Quit,
                 Action::ShowLogs,
-                //Action::ExecCommands,
+                Action::ShowStackLogs,
+                Action::ExecCommands,
                 Action::Next,
                 Action::Previous,
+                Action::ScrollColumns,
+                Action::FilterContainers,
+                Action::Remove,
+                Action::ToggleMark,
+                Action::MarkMatching,
                 Action::StopContainer,
                 Action::PauseContainer,
+                Action::RestartContainer,
+                Action::RecreateContainer,
+                Action::RemoveContainer,
+                Action::Prune,
+                Action::Snippets,
+                Action::BroadcastExec,
+                Action::TailFile,
+                Action::PortForward,
+                Action::ShowForwards,
+                Action::ComparePackages,
+                Action::SortBy,
+                Action::ReverseSort,
+                Action::Inspect,
+                Action::ShowGraph,
+                Action::ShowStartOrder,
+                Action::ShowSharedVolumes,
+                Action::CycleFilter,
+                Action::ToggleShowAll,
+                Action::ToggleFullNames,
+                Action::ToggleDryRun,
+                Action::ToggleSplit,
+                Action::ResizeSplit,
+                Action::SwitchProfile,
+                Action::SwitchRecent,
+                Action::SwitchView,
+                Action::ShowErrors,
+                Action::ToggleDebugConsole,
             ]
             .into()
+        } else if self.is_resource_view() {
+            let mut actions = vec![
+                Action::Quit,
+                Action::Next,
+                Action::Previous,
+                Action::SwitchView,
+                Action::ShowErrors,
+                Action::ToggleDebugConsole,
+            ];
+            if matches!(self, Self::Images | Self::Volumes) {
+                actions.push(Action::Prune);
+            }
+            actions.into()
         } else if self.is_logging() {
             vec![
                 Action::Quit,
                 Action::ScrollDown,
                 Action::ScrollUp,
+                Action::PageUp,
+                Action::PageDown,
+                Action::ScrollTop,
+                Action::ScrollBottom,
+                Action::Search,
+                Action::NextMatch,
+                Action::PreviousMatch,
+                Action::Remove,
+                Action::ToggleMatchesOnly,
+                Action::StackFilter,
+                Action::LogTimeRange,
+                Action::LoadMoreLogs,
+                Action::ExportLogs,
+                Action::OpenPager,
+                Action::CycleJsonLogs,
+                Action::ToggleCaseSensitive,
+                Action::ToggleWrap,
+                Action::CycleLogStreams,
+                Action::ScrollColumns,
+                Action::SwitchRecent,
+                Action::EditLogFilters,
+                Action::ShowErrors,
+                Action::ToggleDebugConsole,
+            ]
+            .into()
+        } else if self.is_inspecting() {
+            vec![
+                Action::Quit,
+                Action::NextTab,
+                Action::ScrollDown,
+                Action::ScrollUp,
                 Action::Search,
                 Action::Remove,
+                Action::EditNotes,
+                Action::EditEnv,
+                Action::RecreateContainer,
+                Action::Copy,
+                Action::OpenSource,
+                Action::ToggleMemoryCalculation,
+                Action::Probe,
+                Action::ShowErrors,
+                Action::ToggleDebugConsole,
             ]
             .into()
+        } else if self.is_start_order() || self.is_graph() || self.is_shared_volumes() {
+            vec![Action::Quit, Action::ShowErrors, Action::ToggleDebugConsole].into()
         } else {
             vec![Action::Quit].into()
         }
@@ -45,9 +300,43 @@ impl AppState {
     }
 
     pub fn is_logging(&self) -> bool {
-        matches!(self, &Self::Logging { .. })
+        matches!(self, &Self::Logging { .. } | &Self::StackLogs { .. })
     }
     pub fn is_inspecting(&self) -> bool {
         matches!(self, &Self::Inspecting { .. })
     }
+    pub fn is_start_order(&self) -> bool {
+        matches!(self, &Self::StartOrder { .. })
+    }
+    pub fn is_graph(&self) -> bool {
+        matches!(self, &Self::Graph { .. })
+    }
+    pub fn is_shared_volumes(&self) -> bool {
+        matches!(self, &Self::SharedVolumes)
+    }
+    pub fn is_events(&self) -> bool {
+        matches!(self, &Self::Events)
+    }
+    /// Images, volumes, networks or events view
+    pub fn is_resource_view(&self) -> bool {
+        matches!(
+            self,
+            &Self::Images | &Self::Volumes | &Self::Networks | &Self::Events
+        )
+    }
+
+    /// Tab of the tab bar the state belongs to, `None` in kiosk mode
+    pub fn view(&self) -> Option<View> {
+        match self {
+            Self::Kiosk { .. } => None,
+            Self::Images => Some(View::Images),
+            Self::Volumes => Some(View::Volumes),
+            Self::Networks => Some(View::Networks),
+            Self::Events => Some(View::Events),
+            _ => Some(View::Containers),
+        }
+    }
+    pub fn is_kiosk(&self) -> bool {
+        matches!(self, &Self::Kiosk { .. })
+    }
 }
@@ -0,0 +1,48 @@
+use crate::container_management::Container;
+
+/// Resource usage summed over the containers listed in the table
+#[derive(Debug, Clone, Default)]
+pub struct Totals {
+    /// Containers counted, the running or paused ones
+    pub count: usize,
+    pub cpu: f32,
+    pub memory: f32,
+    /// Name and value of the container using the most CPU
+    pub max_cpu: Option<(String, f32)>,
+    pub max_memory: Option<(String, f32)>,
+}
+
+impl Totals {
+    pub fn average_cpu(&self) -> f32 {
+        if self.count > 0 {
+            self.cpu / self.count as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn average_memory(&self) -> f32 {
+        if self.count > 0 {
+            self.memory / self.count as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Totals of the containers that report stats, stopped ones using nothing
+pub fn totals(containers: &[&Container]) -> Totals {
+    let mut totals = Totals::default();
+    for c in containers.iter().filter(|c| c.status.is_active()) {
+        totals.count += 1;
+        totals.cpu += c.cpu_usage;
+        totals.memory += c.memory_usage_bytes;
+        if c.cpu_usage > totals.max_cpu.as_ref().map_or(f32::MIN, |(_, v)| *v) {
+            totals.max_cpu = Some((c.name.clone(), c.cpu_usage));
+        }
+        if c.memory_usage_bytes > totals.max_memory.as_ref().map_or(f32::MIN, |(_, v)| *v) {
+            totals.max_memory = Some((c.name.clone(), c.memory_usage_bytes));
+        }
+    }
+    totals
+}
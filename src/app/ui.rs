@@ -2,26 +2,471 @@ use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols,
     text::{Span, Spans, Text},
-    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState},
+    widgets::{
+        Axis, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, List,
+        ListItem, ListState, Paragraph, Row, Table, TableState, Tabs, Wrap,
+    },
     Frame,
 };
 
-use super::App;
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::Local;
+use log::Level;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::affinity;
+use super::ansi;
+use super::databases;
+use super::dry_run::DryRunCall;
+use super::history::StatsSample;
+use super::json_logs;
+use super::packages::{self, PackageChange};
+use super::search;
+use super::state::{AppState, InspectTab, KioskPanel, View};
+use super::totals;
+use super::{
+    App, Confirmation, ExecView, HealthTransition, LogStreams, PackageComparison, Prompt,
+    PromptKind, Resource, SnippetPicker, TableRow,
+};
+use crate::config::{ByteUnits, Column, JsonLogs, SortColumn, Thresholds};
+use crate::container_management::{
+    Container, ContainerDetails, ContainerStatus, HealthStatus, ImageDefaults, ImageDetails,
+    Notification, NotificationLevel, PortMapping,
+};
+use crate::inputs::key::Key;
+use crate::logging;
+use crate::telemetry;
+
+/// Time span covered by the health timeline of the detail view
+const HEALTH_TIMELINE_MINUTES: i64 = 30;
+/// Number of children above which a process is highlighted in the process tree
+const PROCESS_CHILDREN_WARNING: usize = 20;
+/// CPU samples drawn next to the CPU usage of a container
+const CPU_SPARKLINE_WIDTH: usize = 10;
+/// Probe latencies drawn next to the last probe result
+const PROBE_SPARKLINE_WIDTH: usize = 30;
+/// Smallest terminal the layout fits in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 8;
+/// Width given to a column sized in percent when deciding which ones fit
+const MIN_PERCENTAGE_COLUMN_WIDTH: u16 = 12;
+
+/// Rows left to the table when the logs are shown under it
+pub fn split_table_height(height: u16, ratio: u16) -> u16 {
+    (height as u32 * ratio as u32 / 100) as u16
+}
 
 pub fn draw<B>(rect: &mut Frame<B>, app: &App)
 where
     B: Backend,
 {
     let size = rect.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        draw_too_small(rect);
+        return;
+    }
+
+    // Tab bar on the first row, except in kiosk mode
+    let mut body = size;
+    if let Some(view) = app.state().view() {
+        draw_tab_bar(rect, Rect::new(size.x, size.y, size.width, 1), view);
+        body = Rect::new(size.x, size.y + 1, size.width, size.height - 1);
+    }
 
     // Vertical layout
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(size.height - 2), Constraint::Length(2)].as_ref())
-        .split(size);
+        .constraints([Constraint::Length(body.height - 2), Constraint::Length(2)].as_ref())
+        .split(body);
 
+    let status_bar = chunks[1];
+    let main = chunks[0];
     draw_body(rect, chunks, app);
+    if app.splash_visible() {
+        draw_splash(rect, main, app);
+    }
+    if let Some(notification) = app.notification() {
+        draw_notification(rect, status_bar, notification);
+    } else if !app.errors().is_empty() {
+        let count = app.errors().len();
+        let error = Notification::error(format!("{} error(s), E to show", count));
+        draw_notification(rect, status_bar, &error);
+    }
+    if let Some(scroll) = app.errors_view() {
+        draw_errors(rect, app, scroll);
+    }
+    if !app.dry_run_calls().is_empty() {
+        draw_dry_run(rect, app.dry_run_calls());
+    }
+    if app.debug_console() {
+        draw_debug_console(rect, app);
+    }
+}
+
+/// Summary of the engine and of the containers shown at startup
+fn draw_splash<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let key_style = Style::default().fg(Color::LightCyan);
+    let field = |key: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{:<12}", key), key_style),
+            Span::raw(value),
+        ])
+    };
+    let mut lines = vec![Spans::from(""), Spans::from("")];
+    match app.engine_summary() {
+        Some(s) => {
+            lines.push(field("Engine", format!("{} on {}", s.version, s.os)));
+            lines.push(field(
+                "Host",
+                format!(
+                    "{} CPUs, {} memory",
+                    s.cpus,
+                    label_for_size(s.memory as f32, app.byte_units())
+                ),
+            ));
+            lines.push(Spans::from(vec![
+                Span::styled(format!("{:<12}", "Containers"), key_style),
+                Span::styled(
+                    format!("{} running", s.running),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::raw(format!(", {} paused, ", s.paused)),
+                Span::styled(
+                    format!("{} stopped", s.stopped),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+        None => lines.push(Spans::from(Span::styled(
+            "Connecting to the engine…",
+            Style::default().fg(Color::DarkGray),
+        ))),
+    }
+    let unhealthy: Vec<&str> = app
+        .containers()
+        .iter()
+        .filter(|c| matches!(c.health, Some(HealthStatus::Unhealthy)))
+        .map(|c| c.name.as_str())
+        .collect();
+    if !unhealthy.is_empty() {
+        lines.push(Spans::from(vec![
+            Span::styled(format!("{:<12}", "Unhealthy"), key_style),
+            Span::styled(unhealthy.join(", "), Style::default().fg(Color::Red)),
+        ]));
+    }
+    let p = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("bctop (any key to continue)"),
+        );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Resource types, the one shown highlighted
+fn draw_tab_bar<B>(frame: &mut Frame<B>, area: Rect, view: View)
+where
+    B: Backend,
+{
+    let titles = View::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, v)| Spans::from(format!("{} {}", i + 1, v)))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(View::ALL.iter().position(|v| *v == view).unwrap_or(0))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    frame.render_widget(tabs, area);
+}
+
+/// Table of a resource view with the selected row highlighted
+fn draw_resource_table<B>(
+    frame: &mut Frame<B>,
+    area: Rect,
+    app: &App,
+    title: String,
+    header: Vec<&'static str>,
+    rows: Vec<Row>,
+    widths: &[Constraint],
+) where
+    B: Backend,
+{
+    let empty = rows.is_empty();
+    let header = Row::new(
+        header
+            .into_iter()
+            .map(|h| Cell::from(h).style(Style::default().fg(Color::LightCyan))),
+    )
+    .bottom_margin(1);
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default().borders(Borders::TOP).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(widths)
+        .column_spacing(2);
+    let mut state = TableState::default();
+    if !empty {
+        state.select(Some(app.resource_selected()));
+    }
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_images<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let rows = app
+        .images()
+        .iter()
+        .map(|i| {
+            let tags = if i.tags.is_empty() {
+                Cell::from("<none>").style(Style::default().fg(Color::DarkGray))
+            } else {
+                Cell::from(i.tags.join(", "))
+            };
+            let id = i.id.trim_start_matches("sha256:");
+            Row::new(vec![
+                tags,
+                Cell::from(id[..id.len().min(12)].to_string()),
+                Cell::from(label_for_size(i.size as f32, app.byte_units())),
+                Cell::from(label_for_uptime(i.created.map(|t| chrono::Utc::now() - t))),
+                Cell::from(i.containers.map_or("-".to_string(), |c| c.to_string())),
+            ])
+        })
+        .collect();
+    draw_resource_table(
+        frame,
+        area,
+        app,
+        format!("Images ({})", app.images().len()),
+        vec!["Tags", "ID", "Size", "Age", "Containers"],
+        rows,
+        &[
+            Constraint::Percentage(50),
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(10),
+        ],
+    );
+}
+
+fn draw_volumes<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let rows = app
+        .volumes()
+        .iter()
+        .map(|v| {
+            let users = app
+                .containers()
+                .iter()
+                .filter(|c| c.mounts.iter().any(|m| m.name.as_ref() == Some(&v.name)))
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>();
+            let users = if users.is_empty() {
+                Cell::from("unused").style(Style::default().fg(Color::DarkGray))
+            } else {
+                Cell::from(users.join(", "))
+            };
+            Row::new(vec![
+                Cell::from(v.name.clone()),
+                Cell::from(v.driver.clone()),
+                users,
+                Cell::from(v.mountpoint.clone()),
+            ])
+        })
+        .collect();
+    draw_resource_table(
+        frame,
+        area,
+        app,
+        format!("Volumes ({})", app.volumes().len()),
+        vec!["Name", "Driver", "Used by", "Mountpoint"],
+        rows,
+        &[
+            Constraint::Percentage(30),
+            Constraint::Length(8),
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+        ],
+    );
+}
+
+fn draw_networks<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let rows = app
+        .networks()
+        .iter()
+        .map(|n| {
+            let name = if n.internal {
+                Cell::from(Spans::from(vec![
+                    Span::raw(format!("{} ", n.name)),
+                    Span::styled("[internal]", Style::default().fg(Color::DarkGray)),
+                ]))
+            } else {
+                Cell::from(n.name.clone())
+            };
+            Row::new(vec![
+                name,
+                Cell::from(n.id[..n.id.len().min(12)].to_string()),
+                Cell::from(n.driver.clone()),
+                Cell::from(n.scope.clone()),
+                Cell::from(n.subnets.join(", ")),
+            ])
+        })
+        .collect();
+    draw_resource_table(
+        frame,
+        area,
+        app,
+        format!("Networks ({})", app.networks().len()),
+        vec!["Name", "ID", "Driver", "Scope", "Subnets"],
+        rows,
+        &[
+            Constraint::Percentage(30),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Percentage(40),
+        ],
+    );
+}
+
+/// Events of the engine since the view was first opened, the latest first
+fn draw_engine_events<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let rows = app
+        .engine_events()
+        .iter()
+        .rev()
+        .map(|e| {
+            let color = match e.action.as_str() {
+                "die" | "kill" | "oom" | "destroy" | "delete" => Color::Red,
+                "start" | "create" | "pull" => Color::Green,
+                _ => Color::White,
+            };
+            Row::new(vec![
+                Cell::from(e.at.with_timezone(&Local).format("%H:%M:%S").to_string())
+                    .style(Style::default().fg(Color::DarkGray)),
+                Cell::from(e.kind.clone()),
+                Cell::from(e.action.clone()).style(Style::default().fg(color)),
+                Cell::from(e.actor.clone()),
+            ])
+        })
+        .collect();
+    draw_resource_table(
+        frame,
+        area,
+        app,
+        format!("Events ({})", app.engine_events().len()),
+        vec!["Time", "Type", "Action", "Object"],
+        rows,
+        &[
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(24),
+            Constraint::Percentage(60),
+        ],
+    );
+}
+
+fn draw_too_small<B>(frame: &mut Frame<B>)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let text = vec![
+        Spans::from(Span::styled(
+            "Terminal too small",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(format!("{}x{}", size.width, size.height)),
+        Spans::from(format!("needs {}x{}", MIN_WIDTH, MIN_HEIGHT)),
+    ];
+    let top = size.height.saturating_sub(text.len() as u16) / 2;
+    let area = Rect::new(0, size.y + top, size.width, size.height.saturating_sub(top));
+    let p = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(p, area);
+}
+
+/// Overlay with the most recent records logged by bctop itself
+fn draw_debug_console<B>(frame: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 10,
+        size.height / 5,
+        size.width * 8 / 10,
+        size.height * 3 / 5,
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Debug Console")
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+    let height = block.inner(area).height as usize;
+
+    // Tells whether slowness comes from a backlog of IO events or from the engine
+    let latency = match telemetry::engine_latency() {
+        Some(p) => format!(
+            "engine p50 {}ms p95 {}ms p99 {}ms (last {} requests)",
+            p.p50.as_millis(),
+            p.p95.as_millis(),
+            p.p99.as_millis(),
+            p.count
+        ),
+        None => "no engine request yet".to_string(),
+    };
+    let status = Spans::from(Span::styled(
+        format!("IO queue: {} pending | {}", app.io_queue_depth(), latency),
+        Style::default().fg(Color::LightCyan),
+    ));
+
+    let records = logging::recent_records();
+    let mut lines = vec![status];
+    lines.extend(
+        records
+            .iter()
+            .skip(records.len().saturating_sub(height.saturating_sub(1)))
+            .map(|(level, message)| {
+                let color = match level {
+                    Level::Error => Color::Red,
+                    Level::Warn => Color::Yellow,
+                    Level::Info => Color::White,
+                    Level::Debug | Level::Trace => Color::DarkGray,
+                };
+                Spans::from(vec![
+                    Span::styled(format!("{:<5} ", level), Style::default().fg(color)),
+                    Span::raw(message.clone()),
+                ])
+            }),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
 }
 
 fn draw_body<B>(frame: &mut Frame<B>, chunks: Vec<Rect>, app: &App)
@@ -31,15 +476,38 @@ where
     if app.state().is_monitoring() {
         let available_width = chunks[0].width as usize;
 
-        let containers = app.containers();
-
         let selected_style = Style::default().add_modifier(Modifier::REVERSED);
 
-        let header_cells = ["", "ID", "SERVICE", "CPU%", "MEM", "STACK"]
-            .iter()
-            .map(|h| Cell::from(*h).style(Style::default().fg(Color::LightCyan)));
+        let sort = app.sort();
+        let (columns, left, hidden) =
+            scrolled_columns(app.columns(), chunks[0].width, app.column_offset());
+        let columns = &columns;
+        let header_cells = [("", None), ("", None)]
+            .into_iter()
+            .chain(columns.iter().map(|c| match c {
+                Column::Service if app.config().full_names => ("NAME", None),
+                _ => column_header(*c),
+            }))
+            .map(|(h, column)| {
+                let label = if column == Some(sort.column) {
+                    format!("{} {}", h, sort.direction.arrow())
+                } else {
+                    h.to_string()
+                };
+                Cell::from(label).style(Style::default().fg(Color::LightCyan))
+            });
         let header = Row::new(header_cells).height(1).bottom_margin(1);
-        let rows = containers.iter().map(|c| {
+        let thresholds = app.thresholds();
+        let table_rows = app.table_rows();
+        let rows = table_rows.iter().map(|row| {
+            let c = match row {
+                TableRow::Group {
+                    stack,
+                    containers,
+                    collapsed,
+                } => return group_row(app, columns, stack, containers, *collapsed),
+                TableRow::Container(c) => c,
+            };
             let status = &c.status;
             let status_label = match status {
                 crate::app::container_management::ContainerStatus::Created => {
@@ -68,130 +536,338 @@ where
             let cpu = c.cpu_usage;
             let mem_usage = c.memory_usage_bytes;
             let mem_total = c.memory_limit_bytes;
-            let stack = c
-                .swarm_stack
-                .clone()
-                .unwrap_or(c.compose_project.clone().unwrap_or_default());
-            let service = c
-                .swarm_service
-                .clone()
-                .unwrap_or(c.compose_service.clone().unwrap_or_default())
-                .replace(format!("{}_", stack).as_str(), "");
-
-            let mem = label_for_memory(mem_usage, mem_total);
+
+            let mem = label_for_memory(mem_usage, mem_total, app.byte_units());
             let mem_width: usize = (available_width as f32 * 0.2) as usize;
-            let num_green_chars = (mem_usage / mem_total * mem_width as f32) as usize;
-            let mut mem_label = vec![' ' as u8; mem_width];
+            let num_green_chars =
+                ((mem_usage / mem_total * mem_width as f32) as usize).min(mem_width);
+            let mut mem_label = vec![b' '; mem_width];
             // let start = mem_width - mem.chars().count() / 2;
             for (i, c) in mem.chars().enumerate() {
-                if i >= 30 {
+                if i >= 30 || i >= mem_width {
                     break;
                 }
                 mem_label[i] = c as u8;
             }
             let green_label = String::from_utf8(mem_label[0..num_green_chars].to_vec()).unwrap();
             let normal_label = String::from_utf8(mem_label[num_green_chars..].to_vec()).unwrap();
+            let mem_color = if memory_ratio(c) >= thresholds.memory {
+                Color::Red
+            } else {
+                Color::Green
+            };
             let mem_label = Spans::from(vec![
-                Span::styled(green_label, Style::default().bg(Color::Green)),
+                Span::styled(green_label, Style::default().bg(mem_color)),
                 Span::styled(normal_label, Style::default().bg(Color::DarkGray)),
             ]);
+            let cpu_style = if cpu >= thresholds.cpu {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
 
-            Row::new(vec![
+            let mark = if app.is_marked(&c.id) { "●" } else { "" };
+            let mut cells = vec![
+                Cell::from(mark).style(Style::default().fg(Color::Yellow)),
                 Cell::from(status_label),
-                Cell::from(c.id.clone()),
-                // Cell::from(c.name.clone()),
-                Cell::from(service),
-                Cell::from(label_for_cpu(cpu)),
-                Cell::from(mem_label),
-                Cell::from(stack),
-            ])
-            .height(1)
-            .bottom_margin(0)
+            ];
+            for column in columns {
+                cells.push(match column {
+                    Column::Id => Cell::from(c.id.clone()),
+                    Column::Service => {
+                        let name = if app.config().full_names {
+                            format!("{} ({})", c.name, c.image)
+                        } else {
+                            app.short_name(c)
+                        };
+                        let mut spans = vec![Span::raw(name)];
+                        if app.is_emulated(c) {
+                            spans.push(Span::styled(
+                                " [emulated]",
+                                Style::default().fg(Color::Magenta),
+                            ));
+                        }
+                        if app.pending_env(c).is_some() {
+                            spans.push(Span::styled(
+                                " [env pending]",
+                                Style::default().fg(Color::Yellow),
+                            ));
+                        }
+                        Cell::from(Spans::from(spans))
+                    }
+                    Column::Cpu => Cell::from(Spans::from(vec![
+                        Span::styled(label_for_cpu(cpu), cpu_style),
+                        Span::styled(
+                            cpu_sparkline(app.stats_history(&c.id), CPU_SPARKLINE_WIDTH),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ])),
+                    Column::Memory => Cell::from(mem_label.clone()),
+                    Column::Availability => {
+                        Cell::from(label_for_availability(app.service_availability(c)))
+                    }
+                    Column::Network => Cell::from(label_for_network(c, app.byte_units())),
+                    Column::BlockIo => Cell::from(label_for_block_io(c, app.byte_units())),
+                    Column::Uptime => Cell::from(label_for_uptime(c.uptime())),
+                    Column::Stack => Cell::from(c.stack()),
+                    Column::Ports => Cell::from(label_for_ports(&c.ports)),
+                    Column::Restarts => Cell::from(c.restart_count.to_string()),
+                    Column::Disk => Cell::from(label_for_disk(c, app.byte_units())),
+                    Column::Runtime => Cell::from(Spans::from(runtime_label(c))),
+                    Column::Pids => {
+                        let style = if pids_near_limit(c, thresholds) {
+                            Style::default().fg(Color::Red)
+                        } else {
+                            Style::default()
+                        };
+                        Cell::from(label_for_pids(c)).style(style)
+                    }
+                });
+            }
+            let row = Row::new(cells).height(1).bottom_margin(0);
+            if app.is_crash_looping(c) {
+                row.style(Style::default().fg(Color::Red))
+            } else {
+                row
+            }
         });
 
+        let widths = [
+            Constraint::Length(1), // Mark
+            Constraint::Length(1), // Status
+        ]
+        .into_iter()
+        .chain(columns.iter().map(|c| column_width(*c)))
+        .collect::<Vec<_>>();
         let t = Table::new(rows)
             .header(header)
             .block(
                 Block::default()
                     .borders(Borders::TOP)
-                    .title("Container Monitoring"),
+                    .title(monitoring_title(app) + &columns_indicator(left, hidden)),
             )
             .highlight_style(selected_style)
-            .widths(&[
-                Constraint::Length(1),  // Status
-                Constraint::Length(12), // ID
-                // Constraint::Percentage(15), // Name
-                Constraint::Percentage(15), // SERVICE
-                Constraint::Length(7),      // CPU
-                Constraint::Percentage(20), // MEM
-                Constraint::Percentage(15), // STACK
-            ])
+            .widths(&widths)
             .column_spacing(2);
 
         let mut table_state = TableState::default();
-        table_state.select(app.selected_container_index());
+        table_state.select(app.selected_row_in(&table_rows));
 
-        frame.render_stateful_widget(t, chunks[0], &mut table_state);
+        let mut table_area = chunks[0];
+        if app.config().split_logs {
+            table_area.height = split_table_height(chunks[0].height, app.config().split_ratio());
+            let logs_area = Rect {
+                y: chunks[0].y + table_area.height,
+                height: chunks[0].height - table_area.height,
+                ..chunks[0]
+            };
+            draw_preview_logs(frame, logs_area, app);
+        }
+        // Totals on the last row of the table
+        table_area.height -= 1;
+        frame.render_stateful_widget(t, table_area, &mut table_state);
+        let totals_area = Rect {
+            y: table_area.y + table_area.height,
+            height: 1,
+            ..table_area
+        };
+        draw_totals(frame, totals_area, app);
 
-        draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
+        if let Some(search) = app.container_search() {
+            draw_search(frame, search, "Search");
+        } else if matches!(app.prompt(), Some(p) if p.kind == PromptKind::Exec) {
+            draw_help(frame, chunks[1], &session_help(app));
+        } else {
+            draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
+        }
+        if let Some(picker) = app.snippet_picker() {
+            draw_snippet_picker(frame, picker);
+        }
+        if let Some(prompt) = app.prompt() {
+            draw_prompt(frame, app, prompt);
+        }
+        if let Some(confirmation) = app.confirmation() {
+            draw_confirmation(frame, app, confirmation);
+        }
+        if let Some(selected) = app.forwards_view() {
+            draw_forwards(frame, app, selected);
+        }
+        if let Some(view) = app.exec_view() {
+            draw_exec_view(frame, app, view);
+        }
+        if let Some(comparison) = app.package_comparison() {
+            draw_package_comparison(frame, app, comparison);
+        }
     } else if app.state().is_logging() {
-        let logs = app.logs();
+        let logs = app.shown_log_lines();
         let available_height = chunks[0].height as usize - 1; // -1 for the TOP border
         let available_width = chunks[0].width as usize;
         let pos = app.log_position();
 
-        let logs_iter = logs.iter().rev().take(available_height + pos).rev();
+        // Only the lines in view, the newest at the bottom
+        let logs_iter = logs.iter().rev().skip(pos).take(available_height).rev();
         let mut logs = Text::raw("");
-        for l in logs_iter {
-            let mut i = available_width;
-            let mut line = String::new();
-            loop {
-                line.extend(l.chars().skip(i - available_width).take(available_width));
-                if i > l.chars().count() {
-                    break;
-                }
-                i += available_width;
-                line.push('\n');
-            }
-
-            let t = if let Some(s) = app.search() {
-                if line.contains(s) {
-                    let mut content = vec![];
-                    if line.starts_with(s) {
-                        content.push(Span::styled(s, Style::default().fg(Color::Yellow)));
+        let viewer = &app.config().log_viewer;
+        let offset = (!viewer.wrap).then(|| app.log_offset());
+        for (l, stderr) in logs_iter {
+            match json_logs::render(l, viewer.json, &viewer.json_fields) {
+                Some(lines) => {
+                    for line in lines {
+                        logs.extend(log_rows(
+                            &line,
+                            *stderr,
+                            app.search().as_deref(),
+                            viewer.case_sensitive,
+                            available_width,
+                            offset,
+                        ));
                     }
-                    let lv: Vec<String> = line.split(s).map(|e| e.to_owned()).collect();
-                    for segment in lv.iter() {
-                        content.push(Span::raw(segment.to_owned()));
-                        if lv.last() != Some(&segment) {
-                            content.push(Span::styled(s, Style::default().fg(Color::Yellow)));
-                        }
-                    }
-                    if line.ends_with(s) {
-                        content.push(Span::styled(s, Style::default().fg(Color::Yellow)));
-                    }
-                    let mut txt = Text::raw("");
-                    txt.lines = vec![Spans::from(content)];
-                    txt
-                } else {
-                    Text::raw(line)
                 }
-            } else {
-                Text::raw(line)
-            };
-            logs.extend(t);
+                None => logs.extend(log_rows(
+                    l,
+                    *stderr,
+                    app.search().as_deref(),
+                    viewer.case_sensitive,
+                    available_width,
+                    offset,
+                )),
+            }
         }
 
-        let p = Paragraph::new(logs).block(Block::default().borders(Borders::TOP).title(format!(
-            "Logs for {}",
-            app.selected_container().as_ref().unwrap()
-        )));
+        let mut title = vec![Span::raw(match app.state() {
+            AppState::Logging {
+                container,
+                file: Some(path),
+            } => format!("{} in {}", path, container),
+            AppState::StackLogs { stack } => format!("Logs for {}", stack),
+            _ => format!("Logs for {}", app.selected_container().as_ref().unwrap()),
+        })];
+        if let Some(previous) = app.recent_containers().get(1) {
+            let name = app
+                .containers()
+                .iter()
+                .find(|c| c.id == *previous)
+                .map_or(previous.clone(), |c| c.name.clone());
+            title.push(Span::styled(
+                format!(" [Tab: {}]", name),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if let Some(tail) = app.log_tail() {
+            title.push(Span::styled(
+                format!(" [last {} lines, + for more]", tail),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if viewer.json != JsonLogs::Raw {
+            title.push(Span::styled(
+                format!(" [JSON {}]", viewer.json),
+                Style::default().fg(Color::LightCyan),
+            ));
+        }
+        if let Some(offset) = offset {
+            title.push(Span::styled(
+                format!(" [no wrap, column {}]", offset + 1),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        match app.log_streams() {
+            LogStreams::Both => {}
+            LogStreams::Stdout => title.push(Span::styled(
+                " [stdout only]",
+                Style::default().fg(Color::LightCyan),
+            )),
+            LogStreams::Stderr => title.push(Span::styled(
+                " [stderr only]",
+                Style::default().fg(Color::LightRed),
+            )),
+        }
+        if let Some(range) = app.log_range() {
+            title.push(Span::styled(
+                format!(" [{}]", range),
+                Style::default().fg(Color::LightCyan),
+            ));
+        }
+        if !app.log_grep().is_empty() {
+            title.push(Span::styled(
+                format!(" [filter: {}]", app.log_grep().join(" > ")),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if app.matches_only() {
+            title.push(Span::styled(
+                format!(
+                    " [matches only, {} lines of context]",
+                    app.config().log_viewer.context_lines
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if app.pending_logs_count() > 0 {
+            title.push(Span::styled(
+                format!(" (+{} new lines)", app.pending_logs_count()),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        // Wrapped lines take more than one row, keep the bottom in view
+        let overflow = logs.height().saturating_sub(available_height) as u16;
+        let p = Paragraph::new(logs)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .title(Spans::from(title)),
+            )
+            .scroll((overflow, 0));
         frame.render_widget(p, chunks[0]);
-        if app.search().is_some() {
-            draw_search(frame, app.search().as_ref().unwrap());
+        if let Some(search) = app.search() {
+            let mut title = match app.search_matches() {
+                Some((Some(current), total)) => format!("Search (match {}/{})", current, total),
+                Some((None, total)) => format!("Search ({} matches)", total),
+                None => "Search".to_string(),
+            };
+            if app.config().log_viewer.case_sensitive {
+                title.push_str(" [Aa]");
+            }
+            draw_search(frame, search, &title);
         } else {
             draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
         }
+        if let Some(text) = app.log_filter_editor() {
+            draw_log_filter_editor(frame, text);
+        }
+        if let Some(prompt) = app.prompt() {
+            draw_prompt(frame, app, prompt);
+        }
+    } else if let AppState::Inspecting { container, tab } = app.state() {
+        draw_inspect(frame, chunks[0], app, container, *tab);
+        match app.env_search() {
+            Some(search) if app.env_search_typing() => draw_search(frame, search, "Search"),
+            _ => draw_help(frame, chunks[1], format!("{}", app.actions()).as_str()),
+        }
+    } else if let AppState::Graph { container } = app.state() {
+        draw_graph(frame, chunks[0], app, container);
+        draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
+    } else if let AppState::StartOrder { project } = app.state() {
+        draw_start_order(frame, chunks[0], app, project);
+        draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
+    } else if app.state().is_shared_volumes() {
+        draw_shared_volumes(frame, chunks[0], app);
+        draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
+    } else if app.state().is_resource_view() {
+        match app.state() {
+            AppState::Images => draw_images(frame, chunks[0], app),
+            AppState::Volumes => draw_volumes(frame, chunks[0], app),
+            AppState::Networks => draw_networks(frame, chunks[0], app),
+            _ => draw_engine_events(frame, chunks[0], app),
+        }
+        draw_help(frame, chunks[1], format!("{}", app.actions()).as_str());
+    } else if let AppState::Kiosk { panel } = app.state() {
+        draw_kiosk(frame, chunks[0], app, *panel);
+        draw_help(
+            frame,
+            chunks[1],
+            format!("Kiosk | {}", app.actions()).as_str(),
+        );
     // } else if app.state().is_exec_command() {
     //     let mut logs = app.logs().clone();
     //     let available_height = chunks[0].height as usize - 1; // -1 for the TOP border
@@ -232,47 +908,2375 @@ where
     }
 }
 
-fn draw_help<B>(frame: &mut Frame<B>, chunk: Rect, help_txt: &str)
+/// Project row of the monitoring table with the aggregated usage of its containers
+fn group_row(
+    app: &App,
+    columns: &[Column],
+    stack: &str,
+    containers: &[&Container],
+    collapsed: bool,
+) -> Row<'static> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let cpu: f32 = containers.iter().map(|c| c.cpu_usage).sum();
+    let mem: f32 = containers.iter().map(|c| c.memory_usage_bytes).sum();
+    let running = containers
+        .iter()
+        .filter(|c| matches!(c.status, ContainerStatus::Running))
+        .count();
+    let marked = containers.iter().filter(|c| app.is_marked(&c.id)).count();
+    let mark = match marked {
+        0 => "",
+        n if n == containers.len() => "●",
+        _ => "◐",
+    };
+    let mut cells = vec![
+        Cell::from(mark).style(Style::default().fg(Color::Yellow)),
+        Cell::from(if collapsed { "▸" } else { "▾" }),
+    ];
+    // The project name goes in the first column, whichever it is
+    for (i, column) in columns.iter().enumerate() {
+        cells.push(match column {
+            _ if i == 0 => Cell::from(stack.to_string()).style(bold),
+            Column::Service => Cell::from(format!("{} / {} running", running, containers.len())),
+            Column::Cpu => Cell::from(label_for_cpu(cpu)).style(bold),
+            Column::Memory => Cell::from(label_for_size(mem, app.byte_units())).style(bold),
+            Column::Network => {
+                let rx: f32 = containers.iter().map(|c| c.net_rx_rate).sum();
+                let tx: f32 = containers.iter().map(|c| c.net_tx_rate).sum();
+                let units = app.byte_units();
+                Cell::from(format!(
+                    "{} ↓ {} ↑",
+                    label_for_rate(rx, units),
+                    label_for_rate(tx, units)
+                ))
+                .style(bold)
+            }
+            Column::BlockIo => {
+                let read: f32 = containers.iter().map(|c| c.block_read_rate).sum();
+                let write: f32 = containers.iter().map(|c| c.block_write_rate).sum();
+                Cell::from(format!(
+                    "r {} w {}",
+                    label_for_rate(read, app.byte_units()),
+                    label_for_rate(write, app.byte_units())
+                ))
+                .style(bold)
+            }
+            Column::Availability => {
+                Cell::from(label_for_availability(app.project_availability(stack)))
+            }
+            Column::Pids => {
+                let pids: u64 = containers.iter().map(|c| c.pids).sum();
+                Cell::from(pids.to_string()).style(bold)
+            }
+            Column::Restarts => {
+                let restarts: i64 = containers.iter().map(|c| c.restart_count).sum();
+                Cell::from(restarts.to_string()).style(bold)
+            }
+            Column::Disk => {
+                let size: i64 = containers.iter().filter_map(|c| c.size_rw).sum();
+                Cell::from(label_for_size(size as f32, app.byte_units())).style(bold)
+            }
+            Column::Id | Column::Uptime | Column::Stack | Column::Ports | Column::Runtime => {
+                Cell::from("")
+            }
+        });
+    }
+    Row::new(cells).height(1)
+}
+
+/// Header label of a column and the sort column it shows
+fn column_header(column: Column) -> (&'static str, Option<SortColumn>) {
+    match column {
+        Column::Id => ("ID", None),
+        Column::Service => ("SERVICE", None),
+        Column::Cpu => ("CPU%", Some(SortColumn::Cpu)),
+        Column::Memory => ("MEM", Some(SortColumn::Memory)),
+        Column::Network => ("NET I/O", None),
+        Column::BlockIo => ("BLOCK I/O", None),
+        Column::Availability => ("AVAIL", None),
+        Column::Uptime => ("UPTIME", Some(SortColumn::Uptime)),
+        Column::Stack => ("STACK", None),
+        Column::Ports => ("PORTS", None),
+        Column::Restarts => ("RESTARTS", None),
+        Column::Disk => ("DISK", Some(SortColumn::Disk)),
+        Column::Runtime => ("RUNTIME", None),
+        Column::Pids => ("PIDS", None),
+    }
+}
+
+/// Columns that fit in the width, dropping the least useful ones first
+fn fitting_columns(columns: &[Column], width: u16) -> Vec<Column> {
+    let mut columns = columns.to_vec();
+    // Mark and status, then two spaces before each column
+    let needed = |columns: &[Column]| {
+        columns.iter().fold(1 + 2 + 1, |sum, c| {
+            sum + 2
+                + match column_width(*c) {
+                    Constraint::Length(n) => n,
+                    _ => MIN_PERCENTAGE_COLUMN_WIDTH,
+                }
+        })
+    };
+    while needed(&columns) > width {
+        let dropped = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c != Column::Service)
+            .min_by_key(|(_, c)| column_priority(**c))
+            .map(|(i, _)| i);
+        match dropped {
+            Some(i) => {
+                columns.remove(i);
+            }
+            None => break,
+        }
+    }
+    columns
+}
+
+/// Columns shown once `offset` of them are scrolled out on the left, with the
+/// number scrolled out and the number still hidden for lack of room. The
+/// service column never scrolls.
+pub fn scrolled_columns(
+    columns: &[Column],
+    width: u16,
+    offset: usize,
+) -> (Vec<Column>, usize, usize) {
+    let mut left = 0;
+    let kept: Vec<Column> = columns
+        .iter()
+        .filter(|c| {
+            if **c == Column::Service || left >= offset {
+                return true;
+            }
+            left += 1;
+            false
+        })
+        .copied()
+        .collect();
+    let shown = fitting_columns(&kept, width);
+    let hidden = kept.len() - shown.len();
+    (shown, left, hidden)
+}
+
+/// Tells the columns out of the screen, and on which side
+fn columns_indicator(left: usize, hidden: usize) -> String {
+    if left == 0 && hidden == 0 {
+        return String::new();
+    }
+    format!(
+        " [{}{} more columns{}]",
+        if left > 0 { "◀ " } else { "" },
+        left + hidden,
+        if hidden > 0 { " ▶" } else { "" }
+    )
+}
+
+/// Columns with the lowest priority are the first dropped on narrow terminals
+fn column_priority(column: Column) -> u8 {
+    match column {
+        Column::Service => 13,
+        Column::Cpu => 12,
+        Column::Memory => 11,
+        Column::Uptime => 10,
+        Column::Availability => 9,
+        Column::Network => 8,
+        Column::Stack => 7,
+        Column::Id => 6,
+        Column::BlockIo => 5,
+        Column::Ports => 4,
+        Column::Restarts => 3,
+        Column::Pids => 2,
+        Column::Disk => 1,
+        Column::Runtime => 0,
+    }
+}
+
+fn column_width(column: Column) -> Constraint {
+    match column {
+        Column::Id => Constraint::Length(12),
+        Column::Service => Constraint::Percentage(15),
+        Column::Cpu => Constraint::Length(8 + CPU_SPARKLINE_WIDTH as u16),
+        Column::Memory => Constraint::Percentage(20),
+        Column::Network => Constraint::Length(22),
+        Column::BlockIo => Constraint::Length(22),
+        Column::Availability => Constraint::Length(7),
+        Column::Uptime => Constraint::Length(8),
+        Column::Stack => Constraint::Percentage(15),
+        Column::Ports => Constraint::Percentage(20),
+        Column::Restarts => Constraint::Length(8),
+        Column::Disk => Constraint::Length(18),
+        Column::Runtime => Constraint::Length(18),
+        Column::Pids => Constraint::Length(12),
+    }
+}
+
+/// CPU and memory of the listed containers: total, average and the top user
+fn draw_totals<B>(frame: &mut Frame<B>, area: Rect, app: &App)
 where
     B: Backend,
 {
-    let p = Paragraph::new(vec![Spans::from(Span::raw(help_txt))])
-        .style(Style::default().fg(Color::LightCyan))
-        .alignment(Alignment::Left)
-        .block(
-            Block::default()
-                .borders(Borders::TOP)
-                .style(Style::default().fg(Color::White))
-                .title("Help")
-                .border_type(BorderType::Plain),
-        );
-    frame.render_widget(p, chunk);
+    let totals = totals::totals(&app.visible_containers());
+    let units = app.byte_units();
+    let max = |top: &Option<(String, f32)>, label: &dyn Fn(f32) -> String| {
+        top.as_ref().map_or("-".to_string(), |(name, v)| {
+            format!("{} ({})", label(*v), name)
+        })
+    };
+    let cpu = |v: f32| format!("{:.2}%", v);
+    let memory = |v: f32| label_for_size(v, units);
+    let text = format!(
+        "{} active | CPU total {} avg {} max {} | MEM total {} avg {} max {}",
+        totals.count,
+        cpu(totals.cpu),
+        cpu(totals.average_cpu()),
+        max(&totals.max_cpu, &cpu),
+        memory(totals.memory),
+        memory(totals.average_memory()),
+        max(&totals.max_memory, &memory),
+    );
+    let p = Paragraph::new(Span::styled(text, Style::default().fg(Color::DarkGray)));
+    frame.render_widget(p, area);
 }
 
-fn draw_search<B>(frame: &mut Frame<B>, search: &str)
+/// Tail of the logs of the selected container, under the table
+fn draw_preview_logs<B>(frame: &mut Frame<B>, area: Rect, app: &App)
 where
     B: Backend,
 {
-    let p = Paragraph::new(vec![Spans::from(Span::raw(search))])
-        .style(Style::default().fg(Color::LightCyan))
-        .alignment(Alignment::Left)
-        .block(
-            Block::default()
-                .borders(Borders::TOP)
-                .title("Search")
-                .style(Style::default().fg(Color::White).bg(Color::Black))
-                .border_type(BorderType::Plain),
-        );
-    frame.render_widget(
-        p,
-        Rect::new(0, frame.size().height - 2, frame.size().width, 2),
+    let title = match app.selected_container() {
+        Some(id) => {
+            let name = app
+                .containers()
+                .iter()
+                .find(|c| c.id == *id)
+                .map_or(id.as_str(), |c| c.name.as_str());
+            format!("Logs of {}", name)
+        }
+        None => "Select a container to see its logs".to_string(),
+    };
+    let logs = app.preview_logs();
+    let height = area.height.saturating_sub(1) as usize; // -1 for the TOP border
+    let lines: Vec<Spans> = logs
+        .iter()
+        .skip(logs.len().saturating_sub(height))
+        .map(|l| {
+            let spans = ansi::parse(l).into_iter();
+            Spans::from(
+                spans
+                    .map(|(text, style)| Span::styled(text, style))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .title(Span::styled(title, Style::default().fg(Color::LightCyan))),
     );
+    frame.render_widget(paragraph, area);
+}
+
+/// Rows of a log line styled by its escape sequences, red when written to
+/// stderr, and with the searched text highlighted: wrapped to the width, or a
+/// single row starting at the `offset` column when given
+fn log_rows(
+    line: &str,
+    stderr: bool,
+    search: Option<&str>,
+    case_sensitive: bool,
+    width: usize,
+    offset: Option<usize>,
+) -> Vec<Spans<'static>> {
+    let highlight = Style::default().fg(Color::Yellow);
+    // Matches may span several styled segments
+    let found = search.map_or_else(Vec::new, |s| search::find(line, s, case_sensitive));
+    let mut segments: Vec<(String, Style)> = Vec::new();
+    let base = if stderr {
+        Style::default().fg(Color::LightRed)
+    } else {
+        Style::default()
+    };
+    let mut position = 0;
+    for (text, style) in ansi::parse(line) {
+        for c in text.chars() {
+            let style = if found.iter().any(|r| r.contains(&position)) {
+                highlight
+            } else {
+                base.patch(style)
+            };
+            match segments.last_mut() {
+                Some((text, last)) if *last == style => text.push(c),
+                _ => segments.push((c.to_string(), style)),
+            }
+            position += 1;
+        }
+    }
+    let width = width.max(1);
+    let mut rows = vec![Vec::new()];
+    // Columns taken in the last row, or since the start of the line when not
+    // wrapping
+    let mut used = 0;
+    for (text, style) in segments {
+        let mut chunk = String::new();
+        for g in text.graphemes(true) {
+            let start = used;
+            used += g.width();
+            match offset {
+                None if used > width && start > 0 => {
+                    if !chunk.is_empty() {
+                        let row = rows.last_mut().unwrap();
+                        row.push(Span::styled(std::mem::take(&mut chunk), style));
+                    }
+                    rows.push(Vec::new());
+                    used -= start;
+                }
+                // Wide characters cut by the edges are left out
+                Some(offset) if start < offset || used > offset + width => continue,
+                _ => {}
+            }
+            chunk.push_str(g);
+        }
+        if !chunk.is_empty() {
+            rows.last_mut().unwrap().push(Span::styled(chunk, style));
+        }
+    }
+    rows.into_iter().map(Spans::from).collect()
+}
+
+fn monitoring_title(app: &App) -> String {
+    let mut title = "Container Monitoring".to_string();
+    if app.dry_run() {
+        title.push_str(" [DRY RUN]");
+    }
+    if let Some(p) = app.active_profile() {
+        title.push_str(&format!(" @{}", p.name));
+    }
+    match (app.active_filter(), app.filter_error()) {
+        (Some(f), Some(error)) => {
+            title.push_str(&format!(" [{}, hides everything: {}]", f.name, error))
+        }
+        (Some(f), None) => title.push_str(&format!(" [{}]", f.name)),
+        _ => {}
+    }
+    if !app.config().show_all {
+        title.push_str(" [running]");
+    }
+    if app.marked_count() > 0 {
+        title.push_str(&format!(" [{} marked]", app.marked_count()));
+    }
+    let sort = app.sort();
+    title.push_str(&format!(" (by {} {})", sort.column, sort.direction.arrow()));
+    title
 }
 
-fn label_for_memory(mem_usage: f32, mem_total: f32) -> String {
-    let mem_usage = mem_usage / 1024.0 / 1024.0 / 1024.0;
-    let mem_total = mem_total / 1024.0 / 1024.0 / 1024.0;
-    format!("{:.2} / {:.2} GB", mem_usage, mem_total)
+fn draw_inspect<B>(frame: &mut Frame<B>, chunk: Rect, app: &App, id: &str, tab: InspectTab)
+where
+    B: Backend,
+{
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .title("Container Details");
+    let area = block.inner(chunk);
+    frame.render_widget(block, chunk);
+
+    let c = match app.containers().iter().find(|c| c.id == id) {
+        Some(c) => c,
+        None => {
+            let p = Paragraph::new(format!("Container {} not found", id))
+                .style(Style::default().fg(Color::Red));
+            frame.render_widget(p, area);
+            return;
+        }
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(1)].as_ref())
+        .split(area);
+    let titles = InspectTab::ALL
+        .iter()
+        .map(|t| Spans::from(t.to_string()))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(InspectTab::ALL.iter().position(|t| *t == tab).unwrap_or(0))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    frame.render_widget(tabs, chunks[0]);
+
+    if tab == InspectTab::Overview {
+        draw_inspect_overview(frame, chunks[1], app, c);
+    } else if tab == InspectTab::Env {
+        draw_inspect_env(frame, chunks[1], app);
+    } else if tab == InspectTab::Mounts {
+        draw_inspect_mounts(frame, chunks[1], app);
+    } else if tab == InspectTab::Processes {
+        draw_inspect_processes(frame, chunks[1], app);
+    } else if tab == InspectTab::Crash {
+        draw_inspect_crash(frame, chunks[1], app, c);
+    } else if tab == InspectTab::Cores {
+        draw_inspect_cores(frame, chunks[1], app, c);
+    } else if tab == InspectTab::Image {
+        draw_inspect_image(frame, chunks[1], app, c);
+    } else if tab == InspectTab::Database {
+        draw_inspect_database(frame, chunks[1], app, c);
+    } else {
+        let lines = match app.container_details() {
+            Some(details) => {
+                let defaults = app.image_details(&c.image).map(|i| &i.defaults);
+                inspect_section(tab, c, details, defaults)
+            }
+            None => vec![Spans::from("loading...")],
+        };
+        let p = Paragraph::new(lines).scroll((app.inspect_scroll(), 0));
+        frame.render_widget(p, chunks[1]);
+    }
+
+    if let Some(text) = app.notes_editor() {
+        draw_notes_editor(frame, c, text);
+    }
+    if let Some(text) = app.env_editor() {
+        draw_env_editor(frame, c, text);
+    }
+}
+
+/// Environment variables of the inspected container, one selectable per line
+fn draw_inspect_env<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    if app.container_details().is_none() {
+        frame.render_widget(Paragraph::new("loading..."), area);
+        return;
+    }
+    let key_style = Style::default().fg(Color::LightCyan);
+    let pending_style = Style::default().fg(Color::Yellow);
+    let pending = app.inspected_container().and_then(|c| app.pending_env(c));
+    let defaults = app
+        .inspected_container()
+        .and_then(|c| app.image_details(&c.image))
+        .map(|i| &i.defaults);
+    let mut overridden = 0;
+    let items = app
+        .env_vars()
+        .into_iter()
+        .map(|v| {
+            let (key, value) = v.split_once('=').unwrap_or((v, ""));
+            let mut spans = vec![
+                Span::styled(key.to_string(), key_style),
+                Span::raw(format!("={}", value)),
+            ];
+            // Variables added or changed compared to the image
+            match defaults.map(|d| d.env_value(key)) {
+                Some(None) => {
+                    overridden += 1;
+                    spans.push(Span::styled("  + added", Style::default().fg(Color::Green)));
+                }
+                Some(Some(default)) if default != value => {
+                    overridden += 1;
+                    spans.push(Span::styled(
+                        format!("  ~ image: {}", default),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                _ => {}
+            }
+            match pending {
+                Some(p) if p.unset.iter().any(|k| k == key) => {
+                    spans.push(Span::styled("  → unset", pending_style))
+                }
+                Some(p) => {
+                    if let Some(new) = p.set.get(key) {
+                        spans.push(Span::styled(format!("  → {}", new), pending_style))
+                    }
+                }
+                None => {}
+            }
+            ListItem::new(Spans::from(spans))
+        })
+        .collect::<Vec<_>>();
+    let mut title = Vec::new();
+    if overridden > 0 {
+        title.push(Span::styled(
+            format!("{} set by the container, not the image ", overridden),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let Some(p) = pending {
+        let added = p
+            .set
+            .keys()
+            .filter(|k| {
+                !app.env_vars()
+                    .iter()
+                    .any(|v| v.starts_with(&format!("{}=", k)))
+            })
+            .count();
+        title.push(Span::styled(
+            format!(
+                "{} pending change(s), {} new, applied when recreated (R) ",
+                p.set.len() + p.unset.len(),
+                added
+            ),
+            pending_style,
+        ));
+    }
+    if let Some(search) = app.env_search() {
+        title.push(Span::styled(
+            format!("matching \"{}\"", search),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let block = Block::default().title(Spans::from(title));
+    let mut state = ListState::default();
+    if !items.is_empty() {
+        state.select(Some(app.inspect_scroll() as usize));
+    }
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// One bar per core, telling a single saturated thread from a spread load
+fn draw_inspect_cores<B>(frame: &mut Frame<B>, area: Rect, app: &App, c: &Container)
+where
+    B: Backend,
+{
+    if c.per_cpu_usage.is_empty() {
+        let p = Paragraph::new("The engine does not report per-core usage (cgroup v2)")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(p, area);
+        return;
+    }
+    let bar_width = (area.width as usize).saturating_sub(18).max(10);
+    let lines = c
+        .per_cpu_usage
+        .iter()
+        .enumerate()
+        .map(|(i, usage)| {
+            let filled = ((usage / 100.0).min(1.0) * bar_width as f32).round() as usize;
+            let color = if *usage >= app.thresholds().cpu {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            Spans::from(vec![
+                Span::styled(
+                    format!("cpu{:<4}", i),
+                    Style::default().fg(Color::LightCyan),
+                ),
+                Span::styled("█".repeat(filled), Style::default().fg(color)),
+                Span::styled(
+                    "░".repeat(bar_width - filled),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(format!(" {:>6.2}%", usage)),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let p = Paragraph::new(lines).scroll((app.inspect_scroll(), 0));
+    frame.render_widget(p, area);
+}
+
+/// OCI annotations of the image of the inspected container, then its other labels
+fn draw_inspect_image<B>(frame: &mut Frame<B>, area: Rect, app: &App, c: &Container)
+where
+    B: Backend,
+{
+    let image = match app.image_details(&c.image) {
+        Some(i) => i,
+        None => {
+            frame.render_widget(Paragraph::new("loading..."), area);
+            return;
+        }
+    };
+    let key_style = Style::default().fg(Color::LightCyan);
+    let dim = Style::default().fg(Color::DarkGray);
+    let (oci, others): (BTreeMap<_, _>, BTreeMap<_, _>) = image
+        .labels
+        .iter()
+        .partition(|(k, _)| k.starts_with("org.opencontainers.image."));
+    let mut lines = vec![Spans::from(Span::styled("OCI annotations", dim))];
+    if oci.is_empty() {
+        lines.push(Spans::from(Span::styled("none", dim)));
+    }
+    lines.extend(oci.iter().map(|(k, v)| {
+        Spans::from(vec![
+            Span::styled(
+                format!("{:<12}", k.trim_start_matches("org.opencontainers.image.")),
+                key_style,
+            ),
+            Span::raw(v.to_string()),
+        ])
+    }));
+    if let Some(url) = image.source_url() {
+        lines.push(Spans::from(Span::styled(
+            format!("o opens {}", url),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled("Labels", dim)));
+    if others.is_empty() {
+        lines.push(Spans::from(Span::styled("none", dim)));
+    }
+    lines.extend(others.iter().map(|(k, v)| {
+        Spans::from(vec![
+            Span::styled(k.to_string(), key_style),
+            Span::raw(format!("={}", v)),
+        ])
+    }));
+    let p = Paragraph::new(lines).scroll((app.inspect_scroll(), 0));
+    frame.render_widget(p, area);
+}
+
+/// Exit code and last logs from the latest crash of the inspected service
+fn draw_inspect_crash<B>(frame: &mut Frame<B>, area: Rect, app: &App, c: &Container)
+where
+    B: Backend,
+{
+    let crash = match app.last_crash(c) {
+        Some(crash) => crash,
+        None => {
+            let p = Paragraph::new("No crash seen since bctop started")
+                .style(Style::default().fg(Color::DarkGray));
+            frame.render_widget(p, area);
+            return;
+        }
+    };
+    let key_style = Style::default().fg(Color::LightCyan);
+    let exit_code = match crash.exit_code {
+        Some(code) => code.to_string(),
+        None => "unknown, restarted between two refreshes".to_string(),
+    };
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled(format!("{:<10}", "At"), key_style),
+            Span::raw(crash.at.to_rfc3339()),
+        ]),
+        Spans::from(vec![
+            Span::styled(format!("{:<10}", "Exit code"), key_style),
+            Span::raw(exit_code),
+        ]),
+        Spans::from(vec![
+            Span::styled(format!("{:<10}", "OOM"), key_style),
+            Span::raw(crash.oom_killed.to_string()),
+        ]),
+        Spans::from(""),
+    ];
+    lines.extend(crash.logs.iter().map(|l| Spans::from(l.clone())));
+    let p = Paragraph::new(lines).scroll((app.inspect_scroll(), 0));
+    frame.render_widget(p, area);
+}
+
+/// Key numbers of the database running in the inspected container
+fn draw_inspect_database<B>(frame: &mut Frame<B>, area: Rect, app: &App, c: &Container)
+where
+    B: Backend,
+{
+    let dim = Style::default().fg(Color::DarkGray);
+    if app.database_for(&c.image).is_none() {
+        let p =
+            Paragraph::new("Not a known database image, see `databases` in the config").style(dim);
+        frame.render_widget(p, area);
+        return;
+    }
+    let report = match app.database_report(&c.id) {
+        Some(r) => r,
+        None => {
+            frame.render_widget(Paragraph::new("loading..."), area);
+            return;
+        }
+    };
+    let key_style = Style::default().fg(Color::LightCyan);
+    let mut lines: Vec<Spans> = databases::fields(report.engine)
+        .iter()
+        .map(|f| {
+            let value = match report.values.get(f.key) {
+                Some(v) if f.bytes => v
+                    .parse::<f32>()
+                    .map_or_else(|_| v.clone(), |b| label_for_size(b, app.byte_units())),
+                Some(v) => v.clone(),
+                None => "-".to_string(),
+            };
+            Spans::from(vec![
+                Span::styled(format!("{:<18}", f.label), key_style),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    if let Some(error) = &report.error {
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled(
+            "The status command failed:",
+            Style::default().fg(Color::Red),
+        )));
+        lines.extend(error.lines().map(|l| Spans::from(l.to_string())));
+    }
+    let p = Paragraph::new(lines).scroll((app.inspect_scroll(), 0));
+    frame.render_widget(p, area);
+}
+
+/// Processes of the inspected container as an indented tree
+fn draw_inspect_processes<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let rows = match app.process_tree() {
+        Some(rows) => rows,
+        None => {
+            frame.render_widget(Paragraph::new("loading..."), area);
+            return;
+        }
+    };
+    let mut lines = vec![Spans::from(Span::styled(
+        format!("{:>7} {:>7} {:<5} COMMAND", "PID", "PPID", "STAT"),
+        Style::default().fg(Color::LightCyan),
+    ))];
+    lines.extend(rows.iter().map(|row| {
+        let p = row.process;
+        let branch = if row.depth == 0 {
+            String::new()
+        } else {
+            format!("{}└─ ", "   ".repeat(row.depth - 1))
+        };
+        let mut spans = vec![Span::raw(format!(
+            "{:>7} {:>7} {:<5} {}",
+            p.pid,
+            p.ppid.map_or("-".to_string(), |ppid| ppid.to_string()),
+            p.state,
+            branch
+        ))];
+        if p.is_zombie() {
+            spans.push(Span::styled(
+                format!("{} <defunct>", p.command),
+                Style::default().fg(Color::Red),
+            ));
+        } else if row.children > PROCESS_CHILDREN_WARNING {
+            spans.push(Span::styled(
+                format!("{} ({} children)", p.command, row.children),
+                Style::default().fg(Color::Yellow),
+            ));
+        } else {
+            spans.push(Span::raw(p.command.clone()));
+        }
+        Spans::from(spans)
+    }));
+    let p = Paragraph::new(lines).scroll((app.inspect_scroll(), 0));
+    frame.render_widget(p, area);
+}
+
+/// Bind mounts and volumes of the inspected container
+fn draw_inspect_mounts<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let details = match app.container_details() {
+        Some(d) => d,
+        None => {
+            frame.render_widget(Paragraph::new("loading..."), area);
+            return;
+        }
+    };
+    if details.mounts.is_empty() {
+        let p = Paragraph::new("none").style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(p, area);
+        return;
+    }
+    let header = Row::new(["TYPE", "SOURCE", "DESTINATION", "MODE", "DRIVER"])
+        .style(Style::default().fg(Color::LightCyan))
+        .bottom_margin(1);
+    let rows = details
+        .mounts
+        .iter()
+        .skip(app.inspect_scroll() as usize)
+        .map(|m| {
+            // Volumes are better known by their name than by their path on the host
+            let source = match &m.name {
+                Some(name) => format!("{} ({})", name, m.source),
+                None => m.source.clone(),
+            };
+            let mode = if m.rw {
+                Cell::from("rw")
+            } else {
+                Cell::from("ro").style(Style::default().fg(Color::Yellow))
+            };
+            Row::new(vec![
+                Cell::from(m.kind.clone()),
+                Cell::from(source),
+                Cell::from(m.destination.clone()),
+                mode,
+                Cell::from(m.driver.clone().unwrap_or_else(|| "-".to_string())),
+            ])
+        });
+    let t = Table::new(rows)
+        .header(header)
+        .widths(&[
+            Constraint::Length(7),
+            Constraint::Percentage(45),
+            Constraint::Percentage(30),
+            Constraint::Length(4),
+            Constraint::Length(10),
+        ])
+        .column_spacing(2);
+    frame.render_widget(t, area);
+}
+
+/// Lines of a section of the detail view, other than the overview
+fn inspect_section(
+    tab: InspectTab,
+    c: &Container,
+    details: &ContainerDetails,
+    defaults: Option<&ImageDefaults>,
+) -> Vec<Spans<'static>> {
+    let key_style = Style::default().fg(Color::LightCyan);
+    let field = |key: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{:<16}", key), key_style),
+            Span::raw(value),
+        ])
+    };
+    // Values overriding the image are highlighted, with the image one next to them
+    let overridable = |key: &str, value: &str, default: Option<&String>| match default {
+        Some(default) if default != value => {
+            let default = if default.is_empty() { "none" } else { default };
+            Spans::from(vec![
+                Span::styled(format!("{:<16}", key), key_style),
+                Span::styled(value.to_string(), Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("  (image: {})", default),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        }
+        _ => field(key, value.to_string()),
+    };
+    let none = || {
+        vec![Spans::from(Span::styled(
+            "none",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    };
+    match tab {
+        InspectTab::Config => vec![
+            field("Image", c.image.clone()),
+            field("Command", details.command.clone()),
+            overridable(
+                "Entrypoint",
+                &details.entrypoint,
+                defaults.map(|d| &d.entrypoint),
+            ),
+            overridable("Cmd", &details.cmd, defaults.map(|d| &d.cmd)),
+            overridable(
+                "Working dir",
+                &details.working_dir,
+                defaults.map(|d| &d.working_dir),
+            ),
+            overridable("User", &details.user, defaults.map(|d| &d.user)),
+            field("Hostname", details.hostname.clone()),
+            field("Restart policy", details.restart_policy.clone()),
+            field("Created", details.created.clone()),
+        ],
+        InspectTab::State => vec![
+            field("Status", details.state.clone()),
+            field("PID", details.pid.to_string()),
+            field("Exit code", details.exit_code.to_string()),
+            field("Error", details.error.clone()),
+            field(
+                "Started",
+                c.started_at.map_or("-".to_string(), |t| t.to_rfc3339()),
+            ),
+            field("Finished", details.finished_at.clone()),
+            field("OOM killed", details.oom_killed.to_string()),
+            field("Restart count", details.restart_count.to_string()),
+        ],
+        InspectTab::Ports if details.ports.is_empty() => none(),
+        InspectTab::Ports => details
+            .ports
+            .iter()
+            .map(|p| {
+                let host = if let Some(host_port) = &p.host_port {
+                    let host_ip = p.host_ip.as_deref().unwrap_or("0.0.0.0");
+                    Span::raw(format!("-> {}:{}", host_ip, host_port))
+                } else {
+                    Span::styled(
+                        "exposed, not published",
+                        Style::default().fg(Color::DarkGray),
+                    )
+                };
+                Spans::from(vec![
+                    Span::styled(format!("{:<16}", p.container_port), key_style),
+                    host,
+                ])
+            })
+            .collect(),
+        InspectTab::Networks if details.networks.is_empty() => none(),
+        InspectTab::Networks => details
+            .networks
+            .iter()
+            .map(|n| {
+                field(
+                    &n.name,
+                    format!(
+                        "ip {}  gateway {}  mac {}",
+                        n.ip_address, n.gateway, n.mac_address
+                    ),
+                )
+            })
+            .collect(),
+        InspectTab::Labels if c.labels.is_empty() => none(),
+        InspectTab::Labels => c
+            .labels
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(k, v)| {
+                Spans::from(vec![
+                    Span::styled(k.clone(), key_style),
+                    Span::raw(format!("={}", v)),
+                ])
+            })
+            .collect(),
+        InspectTab::Overview
+        | InspectTab::Env
+        | InspectTab::Mounts
+        | InspectTab::Processes
+        | InspectTab::Cores
+        | InspectTab::Image
+        | InspectTab::Database
+        | InspectTab::Crash => Vec::new(),
+    }
+}
+
+/// Summary, notes and health of the inspected container
+fn draw_inspect_overview<B>(frame: &mut Frame<B>, area: Rect, app: &App, c: &Container)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(22),
+                Constraint::Min(3),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let key_style = Style::default().fg(Color::LightCyan);
+    let field = |key: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{:<10}", key), key_style),
+            Span::raw(value),
+        ])
+    };
+    let image = app.image_details(&c.image);
+    let loading = || "loading...".to_string();
+    let mut info = vec![
+        field("Name", c.name.clone()),
+        field("ID", c.id.clone()),
+        field("Image", c.image.clone()),
+        field("Status", format!("{:?}", c.status)),
+        field(
+            "Health",
+            c.health.map_or("-".to_string(), |h| h.to_string()),
+        ),
+        field("Stack", c.stack()),
+        field("Service", c.service()),
+        field("CPU", format!("{:.2}%", c.cpu_usage)),
+        field(
+            "Memory",
+            label_for_memory(c.memory_usage_bytes, c.memory_limit_bytes, app.byte_units()),
+        ),
+        field("Mem detail", label_for_memory_breakdown(app, c)),
+        field("Net I/O", label_for_network(c, app.byte_units())),
+        field("Block I/O", label_for_block_io(c, app.byte_units())),
+        field("PIDs", label_for_pids(c)),
+        platform_field(app, c, key_style),
+        Spans::from(
+            [Span::styled(format!("{:<10}", "Runtime"), key_style)]
+                .into_iter()
+                .chain(runtime_label(c))
+                .collect::<Vec<_>>(),
+        ),
+        clock_field(app, c, key_style),
+        probe_field(app, c, key_style),
+    ];
+    info.extend([
+        field("Image ID", image.map_or_else(loading, |i| i.id.clone())),
+        field(
+            "Digest",
+            image.map_or_else(loading, |i| {
+                i.digests
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| "- (local image)".to_string())
+            }),
+        ),
+        field("Registry", ImageDetails::registry_of(&c.image)),
+    ]);
+    frame.render_widget(Paragraph::new(info), chunks[0]);
+
+    let notes = app
+        .notes()
+        .get(&c.service_key())
+        .cloned()
+        .unwrap_or_default();
+    let notes = Paragraph::new(notes)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Notes"));
+    frame.render_widget(notes, chunks[1]);
+
+    let timeline = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Health (last {} min)", HEALTH_TIMELINE_MINUTES));
+    let strip_area = timeline.inner(chunks[2]);
+    frame.render_widget(timeline, chunks[2]);
+    let strip = health_timeline(app.health_history(&c.id), strip_area.width as usize);
+    frame.render_widget(Paragraph::new(strip), strip_area);
+}
+
+/// What the memory usage is made of and how it is computed
+fn label_for_memory_breakdown(app: &App, c: &Container) -> String {
+    let size = |bytes: Option<f32>| {
+        bytes.map_or("n/a".to_string(), |b| label_for_size(b, app.byte_units()))
+    };
+    // The stats API has no swap figure for either cgroup version
+    let cgroup = c
+        .memory
+        .cgroup
+        .map_or(String::new(), |v| format!(", {}", v));
+    format!(
+        "rss {}, cache {}, swap n/a ({}{}, m to switch)",
+        size(c.memory.rss),
+        size(c.memory.cache),
+        app.memory_calculation(),
+        cgroup
+    )
+}
+
+/// Runtime of the container, badged when it is a known sandbox
+fn runtime_label(c: &Container) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::raw(
+        c.runtime.clone().unwrap_or_else(|| "-".to_string()),
+    )];
+    if let Some(sandbox) = c.sandbox() {
+        spans.push(Span::styled(
+            format!(" [{}]", sandbox),
+            Style::default().fg(Color::LightBlue),
+        ));
+    }
+    spans
+}
+
+/// Platform of the image next to the host one, warning about emulation
+fn platform_field<'a>(app: &App, c: &Container, key_style: Style) -> Spans<'a> {
+    let platform = c.platform.clone().unwrap_or_else(|| "unknown".to_string());
+    let host = app.engine_arch().unwrap_or("unknown");
+    let mut spans = vec![
+        Span::styled(format!("{:<10}", "Platform"), key_style),
+        Span::raw(format!("{} (host {})", platform, host)),
+    ];
+    if app.is_emulated(c) {
+        spans.push(Span::styled(
+            " emulated, expect a high CPU usage",
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    Spans::from(spans)
+}
+
+/// Last status and latency of the HTTP probe of the service
+fn probe_field<'a>(app: &App, c: &Container, key_style: Style) -> Spans<'a> {
+    let mut spans = vec![Span::styled(format!("{:<10}", "Probe"), key_style)];
+    let probe = match app.probe(c) {
+        Some(p) => p,
+        None => {
+            spans.push(Span::raw("off (H to probe the HTTP port)"));
+            return Spans::from(spans);
+        }
+    };
+    match probe.results.back() {
+        None => spans.push(Span::raw("waiting...")),
+        Some(last) => {
+            let label = match (last.status, &last.error) {
+                (Some(status), _) => format!("{} {} ms", status, last.latency.as_millis()),
+                (None, Some(error)) => error.clone(),
+                (None, None) => "no response".to_string(),
+            };
+            let color = if last.is_up() {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            spans.push(Span::styled(label, Style::default().fg(color)));
+            spans.push(Span::styled(
+                sparkline(
+                    probe
+                        .results
+                        .iter()
+                        .map(|r| r.latency.as_secs_f32() * 1000.0),
+                    PROBE_SPARKLINE_WIDTH,
+                ),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+    }
+    spans.push(Span::raw(format!(" {}", probe.url)));
+    Spans::from(spans)
+}
+
+/// Drift of the container clock, highlighted above the threshold
+fn clock_field<'a>(app: &App, c: &Container, key_style: Style) -> Spans<'a> {
+    let value = match app.clock_skew(&c.id) {
+        None => Span::raw("checking..."),
+        Some(None) => Span::raw("unavailable"),
+        Some(Some(skew)) => {
+            let label = match skew {
+                0 => "in sync".to_string(),
+                s if s > 0 => format!("{}s ahead of the host", s),
+                s => format!("{}s behind the host", -s),
+            };
+            if skew.abs() > app.thresholds().clock_skew {
+                Span::styled(
+                    format!("{} (drift)", label),
+                    Style::default().fg(Color::Red),
+                )
+            } else {
+                Span::raw(label)
+            }
+        }
+    };
+    Spans::from(vec![
+        Span::styled(format!("{:<10}", "Clock"), key_style),
+        value,
+    ])
+}
+
+/// Popup listing the snippets of the selected container
+fn draw_snippet_picker<B>(frame: &mut Frame<B>, picker: &SnippetPicker)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 6,
+        size.height / 4,
+        size.width * 2 / 3,
+        size.height / 2,
+    );
+    let mut lines = picker
+        .snippets
+        .iter()
+        .enumerate()
+        .map(|(i, snippet)| {
+            let style = if i == picker.selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Spans::from(vec![
+                Span::styled(format!("{:<20}", snippet.name), style),
+                Span::styled(
+                    format!(" {}", snippet.command),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let title = if picker.confirming {
+        let snippet = &picker.snippets[picker.selected];
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled(
+            format!("Run `{}`? [y/N]", snippet.command),
+            Style::default().fg(Color::Yellow),
+        )));
+        "Snippets (y to run, n to cancel)"
+    } else {
+        "Snippets (Enter to run, Esc to close)"
+    };
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup with the output of the last command run, one section per container
+fn draw_exec_view<B>(frame: &mut Frame<B>, app: &App, view: &ExecView)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 10,
+        size.height / 10,
+        size.width * 8 / 10,
+        size.height * 8 / 10,
+    );
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+    for id in &view.containers {
+        let name = app
+            .containers()
+            .iter()
+            .find(|c| c.id == *id)
+            .map_or(id.clone(), |c| c.name.clone());
+        let result = view.results.get(id);
+        let status = match result.map(|r| r.exit_code) {
+            None => Span::styled("running...", Style::default().fg(Color::Yellow)),
+            Some(Some(0)) => Span::styled("exit 0", Style::default().fg(Color::Green)),
+            Some(Some(code)) => {
+                Span::styled(format!("exit {}", code), Style::default().fg(Color::Red))
+            }
+            Some(None) => Span::styled("failed", Style::default().fg(Color::Red)),
+        };
+        if view.containers.len() > 1 {
+            lines.push(Spans::from(vec![
+                Span::styled(format!("▸ {} ", name), bold),
+                status,
+            ]));
+        } else {
+            lines.push(Spans::from(status));
+        }
+        if let Some(result) = result {
+            lines.extend(result.output.iter().map(|l| Spans::from(l.clone())));
+        }
+        lines.push(Spans::from(""));
+    }
+
+    let failed = view
+        .results
+        .values()
+        .filter(|r| r.exit_code != Some(0))
+        .count();
+    let mut title = vec![Span::raw(view.title.clone())];
+    if view.containers.len() > 1 {
+        title.push(Span::raw(format!(
+            " {}/{} done",
+            view.results.len(),
+            view.containers.len()
+        )));
+        if failed > 0 {
+            title.push(Span::styled(
+                format!(", {} failed", failed),
+                Style::default().fg(Color::Red),
+            ));
+        }
+    }
+    let p = Paragraph::new(lines).scroll((view.scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Spans::from(title))
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup with the packages that differ between two containers
+fn draw_package_comparison<B>(frame: &mut Frame<B>, app: &App, comparison: &PackageComparison)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 10,
+        size.height / 10,
+        size.width * 8 / 10,
+        size.height * 8 / 10,
+    );
+    let names = comparison.containers.clone().map(|id| {
+        app.containers()
+            .iter()
+            .find(|c| c.id == id)
+            .map_or(id, |c| c.name.clone())
+    });
+    let title = format!("Packages {} → {}", names[0], names[1]);
+
+    let listings = comparison
+        .containers
+        .iter()
+        .map(|id| comparison.results.get(id))
+        .collect::<Vec<_>>();
+    let failed = listings
+        .iter()
+        .zip(names.iter())
+        .find_map(|(r, name)| r.filter(|r| r.exit_code.is_none()).map(|r| (name, r)));
+    let lines = match (failed, &listings[..]) {
+        (Some((name, result)), _) => {
+            let mut lines = vec![Spans::from(Span::styled(
+                format!("Could not list the packages of {}", name),
+                Style::default().fg(Color::Red),
+            ))];
+            lines.extend(result.output.iter().map(|l| Spans::from(l.clone())));
+            lines
+        }
+        (None, [Some(left), Some(right)]) => {
+            let changes = packages::diff(
+                &packages::parse(&left.output),
+                &packages::parse(&right.output),
+            );
+            if changes.is_empty() {
+                vec![Spans::from("Same packages and versions")]
+            } else {
+                changes
+                    .into_iter()
+                    .map(|change| match change {
+                        PackageChange::Added { name, version } => Spans::from(Span::styled(
+                            format!("+ {} {}", name, version),
+                            Style::default().fg(Color::Green),
+                        )),
+                        PackageChange::Removed { name, version } => Spans::from(Span::styled(
+                            format!("- {} {}", name, version),
+                            Style::default().fg(Color::Red),
+                        )),
+                        PackageChange::Changed { name, from, to } => Spans::from(Span::styled(
+                            format!("~ {} {} → {}", name, from, to),
+                            Style::default().fg(Color::Yellow),
+                        )),
+                    })
+                    .collect()
+            }
+        }
+        _ => vec![Spans::from(Span::styled(
+            "listing packages...",
+            Style::default().fg(Color::Yellow),
+        ))],
+    };
+    let p = Paragraph::new(lines).scroll((comparison.scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup listing the active port forwards
+fn draw_forwards<B>(frame: &mut Frame<B>, app: &App, selected: usize)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 6,
+        size.height / 4,
+        size.width * 2 / 3,
+        size.height / 2,
+    );
+    let mut lines = app
+        .forwards()
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let style = if i == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            let name = app
+                .containers()
+                .iter()
+                .find(|c| c.id == f.container_id)
+                .map_or(f.container_id.clone(), |c| c.name.clone());
+            Spans::from(vec![
+                Span::styled(
+                    format!("localhost:{:<6} -> {}", f.local_port, f.target),
+                    style,
+                ),
+                Span::styled(format!(" {}", name), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        lines.push(Spans::from(Span::styled(
+            "No port forward, start one with P",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Port forwards (d to stop, Esc to close)")
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup asking for the text an action needs
+fn draw_prompt<B>(frame: &mut Frame<B>, app: &App, prompt: &Prompt)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(size.width / 6, size.height / 2 - 1, size.width * 2 / 3, 3);
+    let title = match prompt.kind {
+        PromptKind::Broadcast => format!(
+            "Run in {} containers (Enter to run, Esc to cancel)",
+            app.broadcast_count()
+        ),
+        PromptKind::TailFile => "File to follow (Enter to open, Esc to cancel)".to_string(),
+        PromptKind::PortForward => {
+            "Ports to forward as local:container (Enter to start, Esc to cancel)".to_string()
+        }
+        PromptKind::LogRange => {
+            "Logs of: last 15m, 2024-05-01 10:00 to 11:00, or all (Enter to fetch, Esc to cancel)"
+                .to_string()
+        }
+        PromptKind::ExportLogs => {
+            "File to write the logs to (Enter to export, Esc to cancel)".to_string()
+        }
+        PromptKind::Exec => format!(
+            "Command to run with the terminal, {} (Enter to start, Esc to cancel)",
+            session_keys(app.detach_key(), &app.passthrough_keys())
+        ),
+        PromptKind::MarkMatching => {
+            "Mark containers matching label:key=value name:regex (Enter to mark, Esc to cancel)"
+                .to_string()
+        }
+    };
+    let p = Paragraph::new(format!("{}█", prompt.text)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup asking to confirm a stop, restart or removal, listing the containers
+fn draw_confirmation<B>(frame: &mut Frame<B>, app: &App, confirmation: &Confirmation)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let height = (confirmation.targets.len() as u16 + 5).min(size.height);
+    let area = Rect::new(
+        size.width / 4,
+        size.height.saturating_sub(height) / 2,
+        size.width / 2,
+        height,
+    );
+    let mut lines: Vec<Spans> = confirmation
+        .targets
+        .iter()
+        .map(|id| {
+            let name = match confirmation.resource {
+                Resource::Container => app
+                    .containers()
+                    .iter()
+                    .find(|c| c.id == *id)
+                    .map_or(id.as_str(), |c| c.name.as_str()),
+                Resource::Image => {
+                    let id = id.trim_start_matches("sha256:");
+                    &id[..id.len().min(12)]
+                }
+                Resource::Volume => id.as_str(),
+            };
+            let mut spans = vec![Span::raw(format!("  {}", name))];
+            if app.is_protected(id) {
+                spans.push(Span::styled(
+                    " [protected]",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            Spans::from(spans)
+        })
+        .collect();
+    lines.push(Spans::from(""));
+    let question = match &confirmation.phrase {
+        Some(phrase) => format!(
+            "Type \"{}\" and Enter to {} {} {}(s): {}█",
+            phrase,
+            confirmation.verb.to_lowercase(),
+            confirmation.targets.len(),
+            confirmation.resource.noun(),
+            confirmation.typed
+        ),
+        None => format!(
+            "{} {} {}(s)? [y/N]",
+            confirmation.verb,
+            confirmation.targets.len(),
+            confirmation.resource.noun()
+        ),
+    };
+    lines.push(Spans::from(Span::styled(
+        question,
+        Style::default().fg(Color::Yellow),
+    )));
+    let title = match (app.dry_run(), &confirmation.phrase) {
+        (true, _) => "Confirm dry run (y to list the API calls, n to cancel)",
+        (false, Some(_)) => "Confirm protected containers (Esc to cancel)",
+        (false, None) => "Confirm (y to proceed, n to cancel)",
+    };
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup to edit the env overrides applied when recreating a container
+fn draw_env_editor<B>(frame: &mut Frame<B>, c: &Container, text: &str)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 6,
+        size.height / 6,
+        size.width * 2 / 3,
+        size.height * 2 / 3,
+    );
+    let help = Spans::from(Span::styled(
+        "KEY=value sets a variable, -KEY removes it, on the next recreation",
+        Style::default().fg(Color::DarkGray),
+    ));
+    let mut lines = vec![help, Spans::from("")];
+    lines.extend(
+        format!("{}█", text)
+            .lines()
+            .map(|l| Spans::from(l.to_string())),
+    );
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Env of {} (Esc to save)", c.display_name()))
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup to edit the notes of a container
+fn draw_notes_editor<B>(frame: &mut Frame<B>, c: &Container, text: &str)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 6,
+        size.height / 6,
+        size.width * 2 / 3,
+        size.height * 2 / 3,
+    );
+    let p = Paragraph::new(format!("{}█", text))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Notes for {} (Esc to save)", c.service_key()))
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Popup to edit the log filters of the service whose logs are shown
+fn draw_log_filter_editor<B>(frame: &mut Frame<B>, text: &str)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 6,
+        size.height / 6,
+        size.width * 2 / 3,
+        size.height * 2 / 3,
+    );
+    let help = Spans::from(Span::styled(
+        "+regex keeps only matching lines, -regex drops them",
+        Style::default().fg(Color::DarkGray),
+    ));
+    let mut lines = vec![help, Spans::from("")];
+    lines.extend(
+        format!("{}█", text)
+            .lines()
+            .map(|l| Spans::from(l.to_string())),
+    );
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Log filters (Ctrl+s to save and apply, Esc to cancel)")
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// One cell per time slot, colored by the health state at that moment
+fn health_timeline(history: &[HealthTransition], width: usize) -> Spans<'static> {
+    let now = chrono::Utc::now();
+    let window = chrono::Duration::minutes(HEALTH_TIMELINE_MINUTES);
+    let start = now - window;
+    let spans = (0..width)
+        .map(|i| {
+            let at = start + window * (2 * i as i32 + 1) / (2 * width as i32);
+            let color = match history.iter().rev().find(|t| t.at <= at) {
+                Some(HealthTransition {
+                    health: Some(HealthStatus::Healthy),
+                    ..
+                }) => Color::Green,
+                Some(HealthTransition {
+                    health: Some(HealthStatus::Unhealthy),
+                    ..
+                }) => Color::Red,
+                Some(HealthTransition {
+                    health: Some(HealthStatus::Starting),
+                    ..
+                }) => Color::Yellow,
+                _ => Color::DarkGray,
+            };
+            Span::styled("█", Style::default().fg(color))
+        })
+        .collect::<Vec<_>>();
+    Spans::from(spans)
+}
+
+/// CPU and memory of a container over the kept history
+fn draw_graph<B>(frame: &mut Frame<B>, chunk: Rect, app: &App, id: &str)
+where
+    B: Backend,
+{
+    let name = app
+        .containers()
+        .iter()
+        .find(|c| c.id == id)
+        .map_or(id.to_string(), |c| c.name.clone());
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .title(format!("Resources of {}", name));
+    let area = block.inner(chunk);
+    frame.render_widget(block, chunk);
+
+    let samples = match app.stats_history(id) {
+        Some(s) if !s.is_empty() => s,
+        _ => {
+            frame.render_widget(Paragraph::new("No samples yet"), area);
+            return;
+        }
+    };
+    // Seconds relative to now, the oldest sample on the left
+    let now = std::time::Instant::now();
+    let points = |value: fn(&StatsSample) -> f64| {
+        samples
+            .iter()
+            .map(|s| (-(now - s.at).as_secs_f64(), value(s)))
+            .collect::<Vec<_>>()
+    };
+    let cpu = points(|s| s.cpu as f64);
+    let units = app.byte_units();
+    let mebi = (units.base() * units.base()) as f64;
+    let memory: Vec<_> = samples
+        .iter()
+        .map(|s| (-(now - s.at).as_secs_f64(), s.memory as f64 / mebi))
+        .collect();
+    let since = cpu.first().map_or(0.0, |p| p.0).min(-1.0);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    frame.render_widget(
+        resource_chart("CPU", "%", &cpu, since, Color::LightCyan),
+        chunks[0],
+    );
+    frame.render_widget(
+        resource_chart(
+            "Memory",
+            units.suffixes()[2],
+            &memory,
+            since,
+            Color::LightGreen,
+        ),
+        chunks[1],
+    );
+}
+
+/// Line chart of one resource, scaled to its highest value
+fn resource_chart<'a>(
+    title: &'a str,
+    unit: &str,
+    points: &'a [(f64, f64)],
+    since: f64,
+    color: Color,
+) -> Chart<'a> {
+    let max = points.iter().map(|p| p.1).fold(1.0, f64::max) * 1.1;
+    let last = points.last().map_or(0.0, |p| p.1);
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(points);
+    let label = Style::default().fg(Color::DarkGray);
+    Chart::new(vec![dataset])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("{} ({:.2} {})", title, last, unit)),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([since, 0.0])
+                .labels(vec![
+                    Span::styled(format!("{:.0}s", since), label),
+                    Span::styled("now", label),
+                ])
+                .style(label),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max])
+                .labels(vec![
+                    Span::styled("0", label),
+                    Span::styled(format!("{:.0}", max), label),
+                ])
+                .style(label),
+        )
+}
+
+/// Gantt-like chart of when the containers of a project started and, when
+/// bctop saw it happen, became healthy
+/// Containers sharing a named volume or a host path, grouped by what they share
+fn draw_shared_volumes<B>(frame: &mut Frame<B>, chunk: Rect, app: &App)
+where
+    B: Backend,
+{
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .title("Shared volumes");
+    let area = block.inner(chunk);
+    frame.render_widget(block, chunk);
+
+    let shared = affinity::shared_mounts(app.containers());
+    if shared.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No volume or host path mounted by several containers"),
+            area,
+        );
+        return;
+    }
+    let mut lines = Vec::new();
+    for mount in &shared {
+        let kind = if mount.volume { "volume" } else { "bind" };
+        let mut header = vec![
+            Span::styled(format!("{} ", kind), Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                mount.source.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ];
+        if mount.conflicting() {
+            header.push(Span::styled(
+                format!("  {} writers, possible write conflict", mount.writers()),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        lines.push(Spans::from(header));
+        for user in &mount.users {
+            let (mode, color) = if user.rw {
+                ("rw", Color::Yellow)
+            } else {
+                ("ro", Color::Green)
+            };
+            lines.push(Spans::from(vec![
+                Span::raw(format!("  {:<25.25} {} ", user.container, user.destination)),
+                Span::styled(mode, Style::default().fg(color)),
+            ]));
+        }
+        lines.push(Spans::from(""));
+    }
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+fn draw_start_order<B>(frame: &mut Frame<B>, chunk: Rect, app: &App, project: &str)
+where
+    B: Backend,
+{
+    let block = Block::default().borders(Borders::TOP).title(format!(
+        "Start order of {}",
+        if project.is_empty() { "-" } else { project }
+    ));
+    let area = block.inner(chunk);
+    frame.render_widget(block, chunk);
+
+    let mut containers: Vec<&Container> = app
+        .containers()
+        .iter()
+        .filter(|c| c.stack() == project)
+        .collect();
+    containers.sort_by_key(|c| (c.started_at.is_none(), c.started_at));
+
+    let first = match containers.first().and_then(|c| c.started_at) {
+        Some(t) => t,
+        None => {
+            frame.render_widget(Paragraph::new("No started containers"), area);
+            return;
+        }
+    };
+    let last = containers
+        .iter()
+        .filter_map(|c| app.ready_at(c).or(c.started_at))
+        .max()
+        .unwrap_or(first);
+    let span = (last - first).num_milliseconds().max(1) as f64;
+
+    let name_width = 25;
+    let label_width = 24;
+    let bar_width = (area.width as usize).saturating_sub(name_width + label_width + 2);
+    let column = |t: chrono::DateTime<chrono::Utc>| {
+        ((t - first).num_milliseconds() as f64 / span * bar_width.saturating_sub(1) as f64) as usize
+    };
+
+    let lines = containers
+        .iter()
+        .map(|c| {
+            let name = Span::raw(format!("{:<width$.width$} ", c.name, width = name_width));
+            let started_at = match c.started_at {
+                Some(t) => t,
+                None => {
+                    return Spans::from(vec![
+                        name,
+                        Span::styled("never started", Style::default().fg(Color::DarkGray)),
+                    ])
+                }
+            };
+            let ready_at = app.ready_at(c);
+            let start = column(started_at);
+            let end = ready_at.map_or(start, column).max(start);
+            let label = match ready_at {
+                Some(r) => format!(
+                    " +{:.1}s ready +{:.1}s",
+                    (started_at - first).num_milliseconds() as f32 / 1000.0,
+                    (r - started_at).num_milliseconds() as f32 / 1000.0
+                ),
+                None => format!(
+                    " +{:.1}s",
+                    (started_at - first).num_milliseconds() as f32 / 1000.0
+                ),
+            };
+            Spans::from(vec![
+                name,
+                Span::raw(" ".repeat(start)),
+                Span::styled("▌", Style::default().fg(Color::LightCyan)),
+                Span::styled("█".repeat(end - start), Style::default().fg(Color::Green)),
+                Span::raw(" ".repeat(bar_width.saturating_sub(end + 1))),
+                Span::styled(label, Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect::<Vec<_>>();
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+fn draw_kiosk<B>(frame: &mut Frame<B>, chunk: Rect, app: &App, panel: KioskPanel)
+where
+    B: Backend,
+{
+    let block = Block::default().borders(Borders::TOP).title(Span::styled(
+        format!(" {} ", panel),
+        Style::default()
+            .fg(Color::LightCyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let area = block.inner(chunk);
+    frame.render_widget(block, chunk);
+
+    match panel {
+        KioskPanel::Leaderboard => draw_kiosk_leaderboard(frame, area, app),
+        KioskPanel::ProjectSummary => draw_kiosk_projects(frame, area, app),
+        KioskPanel::Alerts => draw_kiosk_alerts(frame, area, app),
+    }
+}
+
+/// Busiest containers by CPU, each one with a pair of big gauges
+fn draw_kiosk_leaderboard<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let units = app.byte_units();
+    let gauge_height = 3;
+    let count = (area.height / gauge_height) as usize;
+    let mut top: Vec<&Container> = app.containers().iter().collect();
+    top.sort_by(|a, b| {
+        b.cpu_usage
+            .partial_cmp(&a.cpu_usage)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    top.truncate(count);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            top.iter()
+                .map(|_| Constraint::Length(gauge_height))
+                .chain(std::iter::once(Constraint::Min(0)))
+                .collect::<Vec<_>>(),
+        )
+        .split(area);
+
+    for (c, row) in top.iter().zip(rows.iter()) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(*row);
+        let name = app.short_name(c);
+
+        let cpu = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title(name))
+            .gauge_style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+            .ratio((c.cpu_usage / 100.0).clamp(0.0, 1.0) as f64)
+            .label(format!("CPU {:.2}%", c.cpu_usage));
+        frame.render_widget(cpu, columns[0]);
+
+        let mem = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title(c.stack()))
+            .gauge_style(Style::default().fg(Color::Blue).bg(Color::DarkGray))
+            .ratio(memory_ratio(c) as f64)
+            .label(label_for_memory(
+                c.memory_usage_bytes,
+                c.memory_limit_bytes,
+                units,
+            ));
+        frame.render_widget(mem, columns[1]);
+    }
+}
+
+/// Aggregated usage per swarm stack / compose project
+fn draw_kiosk_projects<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let mut projects: BTreeMap<String, (usize, usize, f32, f32)> = BTreeMap::new();
+    for c in app.containers() {
+        let entry = projects.entry(c.stack()).or_insert((0, 0, 0.0, 0.0));
+        entry.0 += 1;
+        if matches!(c.status, ContainerStatus::Running) {
+            entry.1 += 1;
+        }
+        entry.2 += c.cpu_usage;
+        entry.3 += c.memory_usage_bytes;
+    }
+
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let header = Row::new(
+        ["PROJECT", "RUNNING", "CPU%", "MEM", "AVAIL"]
+            .iter()
+            .map(|h| {
+                Cell::from(*h).style(
+                    Style::default()
+                        .fg(Color::LightCyan)
+                        .add_modifier(Modifier::BOLD),
+                )
+            }),
+    )
+    .height(1)
+    .bottom_margin(1);
+    let rows = projects
+        .iter()
+        .map(|(name, (total, running, cpu, mem))| {
+            let running_style = if running < total {
+                bold.fg(Color::Red)
+            } else {
+                bold.fg(Color::Green)
+            };
+            let label = if name.is_empty() { "-" } else { name.as_str() };
+            Row::new(vec![
+                Cell::from(label.to_string()).style(bold),
+                Cell::from(format!("{} / {}", running, total)).style(running_style),
+                Cell::from(format!("{:.2}%", cpu)).style(bold),
+                Cell::from(label_for_size(*mem, app.byte_units())).style(bold),
+                Cell::from(label_for_availability(app.project_availability(name))),
+            ])
+            .height(2)
+        })
+        .collect::<Vec<_>>();
+
+    let t = Table::new(rows)
+        .header(header)
+        .widths(&[
+            Constraint::Percentage(40),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+        ])
+        .column_spacing(2);
+    frame.render_widget(t, area);
+}
+
+/// Containers that are down or close to their resource limits
+fn draw_kiosk_alerts<B>(frame: &mut Frame<B>, area: Rect, app: &App)
+where
+    B: Backend,
+{
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let alerts = app
+        .containers()
+        .iter()
+        .filter_map(|c| {
+            let reason = app.alert(c)?;
+            Some(Spans::from(vec![
+                Span::styled(format!("{:<12}", reason), bold.fg(Color::Red)),
+                Span::styled(c.name.clone(), bold),
+            ]))
+        })
+        .collect::<Vec<_>>();
+
+    let p = if alerts.is_empty() {
+        Paragraph::new(Span::styled(
+            "All containers healthy",
+            bold.fg(Color::Green),
+        ))
+        .alignment(Alignment::Center)
+    } else {
+        Paragraph::new(alerts)
+    };
+    frame.render_widget(p, area);
+}
+
+pub fn memory_ratio(c: &Container) -> f32 {
+    if c.memory_limit_bytes > 0.0 {
+        (c.memory_usage_bytes / c.memory_limit_bytes).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// What an embedded session does with the keys, e.g. `every key goes to it
+/// but ctrl+], which detaches`
+pub fn session_keys(detach: Key, passthrough: &[Key]) -> String {
+    if passthrough.contains(&detach) {
+        format!("every key goes to it, {} twice detaches", detach)
+    } else {
+        format!("every key goes to it but {}, which detaches", detach)
+    }
+}
+
+/// Help bar while the command of a session is typed
+fn session_help(app: &App) -> String {
+    let passthrough = app.passthrough_keys();
+    let mut help = format!("Session | detach: {}", app.detach_key());
+    if passthrough.contains(&app.detach_key()) {
+        help.push_str(" twice");
+    }
+    if !passthrough.is_empty() {
+        let keys: Vec<String> = passthrough.iter().map(Key::to_string).collect();
+        help.push_str(&format!(" | passed through: {}", keys.join(", ")));
+    }
+    help
+}
+
+fn draw_help<B>(frame: &mut Frame<B>, chunk: Rect, help_txt: &str)
+where
+    B: Backend,
+{
+    let p = Paragraph::new(vec![Spans::from(Span::raw(help_txt))])
+        .style(Style::default().fg(Color::LightCyan))
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .style(Style::default().fg(Color::White))
+                .title("Help")
+                .border_type(BorderType::Plain),
+        );
+    frame.render_widget(p, chunk);
+}
+
+/// Popup listing the operations skipped by the dry run and their API calls
+fn draw_dry_run<B>(frame: &mut Frame<B>, skipped: &[DryRunCall])
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 10,
+        size.height / 5,
+        size.width * 8 / 10,
+        size.height * 3 / 5,
+    );
+    let mut lines = Vec::new();
+    for skipped in skipped {
+        lines.push(Spans::from(Span::styled(
+            skipped.target.clone(),
+            Style::default().fg(Color::LightCyan),
+        )));
+        lines.extend(
+            skipped
+                .calls
+                .iter()
+                .map(|call| Spans::from(format!("  {}", call))),
+        );
+    }
+    let p = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Dry run, nothing was changed (Esc to close)")
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Errors not dismissed yet, the latest first
+fn draw_errors<B>(frame: &mut Frame<B>, app: &App, scroll: u16)
+where
+    B: Backend,
+{
+    let size = frame.size();
+    let area = Rect::new(
+        size.width / 10,
+        size.height / 5,
+        size.width * 8 / 10,
+        size.height * 3 / 5,
+    );
+    let lines: Vec<Spans> = if app.errors().is_empty() {
+        vec![Spans::from(Span::styled(
+            "No error",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        app.errors()
+            .iter()
+            .rev()
+            .map(|e| {
+                let repeated = if e.count > 1 {
+                    format!(" (x{})", e.count)
+                } else {
+                    String::new()
+                };
+                Spans::from(vec![
+                    Span::styled(
+                        format!("{} ", e.at.with_timezone(&Local).format("%H:%M:%S")),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(e.message.clone(), Style::default().fg(Color::Red)),
+                    Span::raw(repeated),
+                ])
+            })
+            .collect()
+    };
+    let p = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Errors (c to dismiss all, Esc to close)")
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+/// Progress or outcome of the last operation, on the right of the status bar border
+fn draw_notification<B>(frame: &mut Frame<B>, status_bar: Rect, notification: &Notification)
+where
+    B: Backend,
+{
+    let color = match notification.level {
+        NotificationLevel::Progress => Color::Yellow,
+        NotificationLevel::Success => Color::Green,
+        NotificationLevel::Error => Color::Red,
+    };
+    let text = format!(" {} ", notification.message);
+    let width = (text.chars().count() as u16).min(status_bar.width);
+    let area = Rect::new(
+        status_bar.x + status_bar.width - width,
+        status_bar.y,
+        width,
+        1,
+    );
+    let p = Paragraph::new(Span::styled(
+        text,
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    ));
+    frame.render_widget(Clear, area);
+    frame.render_widget(p, area);
+}
+
+fn draw_search<B>(frame: &mut Frame<B>, search: &str, title: &str)
+where
+    B: Backend,
+{
+    let p = Paragraph::new(vec![Spans::from(Span::raw(search))])
+        .style(Style::default().fg(Color::LightCyan))
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .title(title)
+                .style(Style::default().fg(Color::White).bg(Color::Black))
+                .border_type(BorderType::Plain),
+        );
+    frame.render_widget(
+        p,
+        Rect::new(0, frame.size().height - 2, frame.size().width, 2),
+    );
+}
+
+/// Receive and send rates, e.g. `1.2MB/s ↓ 300KB/s ↑`
+fn label_for_network(c: &Container, units: ByteUnits) -> String {
+    format!(
+        "{} ↓ {} ↑",
+        label_for_rate(c.net_rx_rate, units),
+        label_for_rate(c.net_tx_rate, units)
+    )
+}
+
+/// Disk read and write rates, e.g. `r 1.2MB/s w 300KB/s`
+fn label_for_block_io(c: &Container, units: ByteUnits) -> String {
+    format!(
+        "r {} w {}",
+        label_for_rate(c.block_read_rate, units),
+        label_for_rate(c.block_write_rate, units)
+    )
+}
+
+/// Last values as block characters scaled to the highest one, e.g. `▁▂▅█▃`
+fn sparkline(values: impl ExactSizeIterator<Item = f32> + Clone, width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    if values.len() == 0 {
+        return String::new();
+    }
+    let recent = values.clone().skip(values.len().saturating_sub(width));
+    let max = recent.clone().fold(1.0f32, |max, s| max.max(s));
+    let line: String = recent
+        .map(|s| BARS[((s / max) * (BARS.len() - 1) as f32).round() as usize])
+        .collect();
+    format!(" {}", line)
+}
+
+/// CPU percentages of a container as a sparkline
+fn cpu_sparkline(samples: Option<&VecDeque<StatsSample>>, width: usize) -> String {
+    match samples {
+        Some(s) => sparkline(s.iter().map(|s| s.cpu), width),
+        None => String::new(),
+    }
+}
+
+/// Two most significant units of the uptime, e.g. `3d 4h`
+fn label_for_uptime(uptime: Option<chrono::Duration>) -> String {
+    let seconds = match uptime {
+        Some(u) => u.num_seconds().max(0),
+        None => return "-".to_string(),
+    };
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Running pids over their limit, e.g. `42 / 100`
+pub fn label_for_pids(c: &Container) -> String {
+    match c.pids_limit {
+        Some(limit) => format!("{} / {}", c.pids, limit),
+        None => c.pids.to_string(),
+    }
+}
+
+pub fn pids_near_limit(c: &Container, thresholds: Thresholds) -> bool {
+    c.pids_limit
+        .is_some_and(|limit| c.pids as f32 >= limit as f32 * thresholds.pids)
+}
+
+/// Writable layer size and the total with the image, e.g. `12.0MB (1.2GB)`
+fn label_for_disk(c: &Container, units: ByteUnits) -> String {
+    match (c.size_rw, c.size_root_fs) {
+        (Some(rw), Some(root_fs)) => format!(
+            "{} ({})",
+            label_for_size(rw as f32, units),
+            label_for_size(root_fs as f32, units)
+        ),
+        (Some(rw), None) => label_for_size(rw as f32, units),
+        _ => "-".to_string(),
+    }
+}
+
+/// Bytes per second with a human readable unit
+fn label_for_rate(bytes_per_second: f32, units: ByteUnits) -> String {
+    format!("{}/s", label_for_size(bytes_per_second, units))
+}
+
+/// Bytes with a human readable unit
+pub fn label_for_size(bytes: f32, units: ByteUnits) -> String {
+    let suffixes = units.suffixes();
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= units.base() && unit < suffixes.len() - 1 {
+        value /= units.base();
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0}{}", value, suffixes[unit])
+    } else {
+        format!("{:.1}{}", value, suffixes[unit])
+    }
+}
+
+/// `hostPort->containerPort` for each published port, once per port
+/// even when published on both IPv4 and IPv6
+fn label_for_ports(ports: &[PortMapping]) -> String {
+    let mut labels: Vec<String> = ports
+        .iter()
+        .filter_map(|p| {
+            p.host_port
+                .as_ref()
+                .map(|host_port| format!("{}->{}", host_port, p.container_port))
+        })
+        .collect();
+    labels.dedup();
+    labels.join(", ")
+}
+
+/// Memory used over the limit, each with its own unit, e.g. `50.0MiB / 2.0GiB`
+fn label_for_memory(mem_usage: f32, mem_total: f32, units: ByteUnits) -> String {
+    // Stopped containers report no limit
+    let total = if mem_total > 0.0 {
+        label_for_size(mem_total, units)
+    } else {
+        "-".to_string()
+    };
+    format!("{} / {}", label_for_size(mem_usage, units), total)
+}
+
+/// Session availability, colored against common SLO targets
+fn label_for_availability(availability: Option<f32>) -> Span<'static> {
+    match availability {
+        Some(a) => {
+            let color = if a >= 0.999 {
+                Color::Green
+            } else if a >= 0.99 {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            Span::styled(format!("{:.1}%", a * 100.0), Style::default().fg(color))
+        }
+        None => Span::raw("-"),
+    }
 }
 
 fn label_for_cpu(cpu_usage: f32) -> String {
@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use regex::Regex;
+
+use crate::config::{WatchdogConfig, WatchdogRule};
+use crate::container_management::Container;
+
+/// Why the watchdog restarts a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Exited on its own, with its exit code
+    Exited(i64),
+    /// Failed its health checks that many times in a row
+    Unhealthy(i64),
+}
+
+/// Restarts decided for the containers matching a watchdog rule
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    /// Containers stopped through bctop, whose next exit is not a crash
+    stopped_by_user: HashSet<String>,
+    /// Last automatic restart of each service
+    restarted_at: HashMap<String, Instant>,
+    /// Containers to restart on the next tick
+    pending: Vec<String>,
+}
+
+impl Watchdog {
+    /// The user stopped or removed the container, it is expected to go down
+    pub fn stopped_by_user(&mut self, id: &str) {
+        self.stopped_by_user.insert(id.to_string());
+    }
+
+    /// The container runs again, so a later exit would be unexpected
+    pub fn running(&mut self, id: &str) {
+        self.stopped_by_user.remove(id);
+    }
+
+    /// Decide whether the container is restarted, returning true when it is
+    pub fn check(&mut self, config: &WatchdogConfig, c: &Container, trigger: Trigger) -> bool {
+        if !config.enabled {
+            return false;
+        }
+        let rule = match config.rules.iter().find(|r| matches(r, c)) {
+            Some(r) => r,
+            None => return false,
+        };
+        let applies = match trigger {
+            Trigger::Exited(_) => rule.on_exit && !self.stopped_by_user.contains(&c.id),
+            Trigger::Unhealthy(streak) => rule.health_failures.is_some_and(|n| streak >= n),
+        };
+        if !applies {
+            return false;
+        }
+        let cooldown = Duration::from_secs(config.cooldown_seconds);
+        if let Some(at) = self.restarted_at.get(&c.service_key()) {
+            if at.elapsed() < cooldown {
+                debug!("Watchdog cooling down for {}, not restarting", c.name);
+                return false;
+            }
+        }
+        self.restarted_at.insert(c.service_key(), Instant::now());
+        self.pending.push(c.id.clone());
+        true
+    }
+
+    /// Containers to restart now
+    pub fn take_pending(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+fn matches(rule: &WatchdogRule, c: &Container) -> bool {
+    if let Some(label) = &rule.label {
+        let matches = match label.split_once('=') {
+            Some((key, value)) => c.labels.get(key).map(String::as_str) == Some(value),
+            None => c.labels.contains_key(label),
+        };
+        if !matches {
+            return false;
+        }
+    }
+    if let Some(pattern) = &rule.name_regex {
+        match Regex::new(pattern) {
+            Ok(re) if re.is_match(&c.name) => {}
+            Ok(_) => return false,
+            Err(e) => {
+                warn!("Invalid watchdog name_regex {}: {}", pattern, e);
+                return false;
+            }
+        }
+    }
+    // A rule without criteria would restart everything
+    rule.label.is_some() || rule.name_regex.is_some()
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::Exited(code) => write!(f, "exited with {}", code),
+            Trigger::Unhealthy(streak) => write!(f, "{} failed health checks", streak),
+        }
+    }
+}
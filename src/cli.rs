@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+
+/// A simple tool to monitor containers and their resources
+#[derive(Debug, Default, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Args {
+    /// Only list the containers whose name, image, service or project contains it
+    pub pattern: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Connection profile from the config file to use instead of the local engine
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// File to write the application logs to
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Log level: off, error, warn, info, debug or trace
+    #[arg(long)]
+    pub log_level: Option<LevelFilter>,
+    /// Print changes as plain lines for screen readers instead of drawing
+    #[arg(long)]
+    pub accessible: bool,
+    /// Show the API calls of stops, restarts and removals instead of making them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run without the interface, reporting alerts and events to the
+    /// destinations of the `daemon` config until interrupted
+    Daemon,
+    /// Run without the interface, serving the containers and their logs to
+    /// the bctop instances connecting through a `bctop://` profile
+    Agent {
+        /// Address to listen on, tunnel it through SSH to reach it remotely
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        listen: String,
+        /// Token the clients must send, required to listen on other addresses
+        /// than the loopback one. Defaults to $BCTOP_AGENT_TOKEN.
+        #[arg(long)]
+        token: Option<String>,
+    },
+    /// Open the logs of a container right away
+    Logs {
+        /// Name of the container, or the start of its id
+        name: String,
+    },
+}
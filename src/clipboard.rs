@@ -0,0 +1,9 @@
+use std::io::{self, Write};
+
+/// Copy text to the clipboard of the terminal with an OSC 52 sequence, which
+/// also works over SSH as long as the terminal supports it.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64::encode(text))?;
+    stdout.flush()
+}
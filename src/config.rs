@@ -0,0 +1,854 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+use eyre::{bail, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Bounds of the share of the split view given to the table, in percent
+pub const SPLIT_RATIO_MIN: u16 = 20;
+pub const SPLIT_RATIO_MAX: u16 = 80;
+
+/// User configuration, read from `<config dir>/bctop/config.json`.
+///
+/// Every field has a default so a partial (or missing) file is valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub sort: SortOrder,
+    /// List stopped containers too, not only the running ones
+    pub show_all: bool,
+    /// Show the logs of the selected container under the table
+    pub split_logs: bool,
+    /// Show the container name and image instead of the service name
+    pub full_names: bool,
+    /// Extra keys added to the default ones
+    pub keys: KeyPreset,
+    /// Percentage of the height of the split view taken by the table
+    pub split_ratio: u16,
+    /// Print changes as plain lines for screen readers instead of drawing
+    pub accessible: bool,
+    /// Ask before stopping, restarting or removing containers
+    pub confirm_destructive: bool,
+    /// Only show the API calls stops, restarts and removals would make
+    pub dry_run: bool,
+    /// `key=value` or `key` label of the containers that can only be stopped,
+    /// restarted or removed after typing a confirmation phrase
+    pub protection_label: String,
+    pub memory_calculation: MemoryCalculation,
+    pub units: ByteUnits,
+    pub kiosk: KioskConfig,
+    pub thresholds: Thresholds,
+    /// Columns of the monitoring table, in order
+    pub columns: Vec<Column>,
+    pub filters: Vec<ContainerFilter>,
+    pub snippets: Vec<Snippet>,
+    pub name_rules: Vec<NameRule>,
+    pub databases: Vec<DatabaseStats>,
+    pub profiles: Vec<EngineProfile>,
+    pub log: LogSettings,
+    pub log_viewer: LogViewerConfig,
+    pub telemetry: TelemetryConfig,
+    pub session: SessionConfig,
+    pub probe: ProbeConfig,
+    pub watchdog: WatchdogConfig,
+    pub daemon: DaemonConfig,
+    /// The file could not be parsed, saving would replace it by the defaults
+    #[serde(skip)]
+    unreadable: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sort: SortOrder::default(),
+            show_all: true,
+            split_logs: false,
+            full_names: false,
+            keys: KeyPreset::default(),
+            split_ratio: 60,
+            accessible: false,
+            confirm_destructive: true,
+            dry_run: false,
+            protection_label: "bctop.protect=true".to_string(),
+            memory_calculation: MemoryCalculation::default(),
+            units: ByteUnits::default(),
+            kiosk: KioskConfig::default(),
+            thresholds: Thresholds::default(),
+            columns: Column::DEFAULT.to_vec(),
+            filters: Vec::new(),
+            snippets: Vec::new(),
+            name_rules: Vec::new(),
+            databases: DatabaseStats::defaults(),
+            profiles: Vec::new(),
+            log: LogSettings::default(),
+            log_viewer: LogViewerConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            session: SessionConfig::default(),
+            probe: ProbeConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            daemon: DaemonConfig::default(),
+            unreadable: false,
+        }
+    }
+}
+
+impl Config {
+    /// Location of the configuration file
+    pub fn path() -> Option<PathBuf> {
+        BaseDirs::new().map(|dirs| dirs.config_dir().join("bctop").join("config.json"))
+    }
+
+    /// Load the configuration, falling back to the defaults when the file
+    /// does not exist or can not be parsed.
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(p) => p,
+            None => return Self::default(),
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid config file {}: {}", path.display(), e);
+                Self {
+                    unreadable: true,
+                    ..Self::default()
+                }
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Share of the split view taken by the table, kept within the bounds
+    pub fn split_ratio(&self) -> u16 {
+        self.split_ratio.clamp(SPLIT_RATIO_MIN, SPLIT_RATIO_MAX)
+    }
+
+    /// Position of the connection profile with the given name
+    pub fn profile_index(&self, name: &str) -> Option<usize> {
+        self.profiles.iter().position(|p| p.name == name)
+    }
+
+    /// Persist the configuration so runtime changes survive restarts. A file
+    /// that could not be parsed is left as it is rather than replaced by the
+    /// defaults.
+    pub fn save(&self) -> Result<()> {
+        if self.unreadable {
+            bail!("the config file could not be parsed, fix it to keep runtime changes");
+        }
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(self)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortColumn {
+    Name,
+    Cpu,
+    Memory,
+    Disk,
+    Uptime,
+    Status,
+}
+
+impl SortColumn {
+    /// Column that comes after this one when cycling with the sort action
+    pub fn next(&self) -> Self {
+        match self {
+            SortColumn::Name => SortColumn::Cpu,
+            SortColumn::Cpu => SortColumn::Memory,
+            SortColumn::Memory => SortColumn::Disk,
+            SortColumn::Disk => SortColumn::Uptime,
+            SortColumn::Uptime => SortColumn::Status,
+            SortColumn::Status => SortColumn::Name,
+        }
+    }
+
+    /// Direction used when switching to this column
+    pub fn default_direction(&self) -> SortDirection {
+        match self {
+            SortColumn::Name | SortColumn::Status => SortDirection::Asc,
+            SortColumn::Cpu | SortColumn::Memory | SortColumn::Disk | SortColumn::Uptime => {
+                SortDirection::Desc
+            }
+        }
+    }
+}
+
+impl Display for SortColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            SortColumn::Name => "Name",
+            SortColumn::Cpu => "CPU",
+            SortColumn::Memory => "Memory",
+            SortColumn::Disk => "Disk",
+            SortColumn::Uptime => "Uptime",
+            SortColumn::Status => "Status",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// What is left out of the memory usage reported by the engine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryCalculation {
+    /// The page cache, leaving what the processes really use
+    #[default]
+    Cache,
+    /// The inactive files only, like `docker stats`
+    DockerStats,
+}
+
+impl MemoryCalculation {
+    pub fn toggle(&self) -> Self {
+        match self {
+            MemoryCalculation::Cache => MemoryCalculation::DockerStats,
+            MemoryCalculation::DockerStats => MemoryCalculation::Cache,
+        }
+    }
+}
+
+impl Display for MemoryCalculation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            MemoryCalculation::Cache => "usage - cache",
+            MemoryCalculation::DockerStats => "usage - inactive files (docker stats)",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Set of keys bound on top of the ones listed in the help bar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPreset {
+    #[default]
+    Default,
+    /// j and k to move, and in the logs gg, G, ctrl+d, ctrl+u, n and N
+    Vim,
+}
+
+/// Multiples used to show byte sizes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteUnits {
+    /// Powers of 1024: KiB, MiB, GiB, like `docker stats`
+    #[default]
+    Binary,
+    /// Powers of 1000: KB, MB, GB
+    Decimal,
+}
+
+impl ByteUnits {
+    pub fn base(&self) -> f32 {
+        match self {
+            ByteUnits::Binary => 1024.0,
+            ByteUnits::Decimal => 1000.0,
+        }
+    }
+
+    /// Unit names from bytes up
+    pub fn suffixes(&self) -> [&'static str; 5] {
+        match self {
+            ByteUnits::Binary => ["B", "KiB", "MiB", "GiB", "TiB"],
+            ByteUnits::Decimal => ["B", "KB", "MB", "GB", "TB"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn reverse(&self) -> Self {
+        match self {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        }
+    }
+
+    /// Arrow shown next to the sorted column
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "▲",
+            SortDirection::Desc => "▼",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOrder {
+    pub column: SortColumn,
+    pub direction: SortDirection,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            column: SortColumn::Name,
+            direction: SortDirection::Asc,
+        }
+    }
+}
+
+/// Optional columns of the monitoring table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Id,
+    Service,
+    Cpu,
+    Memory,
+    Network,
+    /// Disk read and write rates, not shown unless configured
+    BlockIo,
+    Availability,
+    /// Time since the container started
+    Uptime,
+    Stack,
+    /// Published ports, not shown unless configured
+    Ports,
+    /// Restarts done by the engine, not shown unless configured
+    Restarts,
+    /// Size of the writable layer, not shown unless configured
+    Disk,
+    /// OCI runtime, not shown unless configured
+    Runtime,
+    /// Running pids and their limit, not shown unless configured
+    Pids,
+}
+
+impl Column {
+    /// Columns shown when none are configured
+    pub const DEFAULT: [Column; 8] = [
+        Column::Id,
+        Column::Service,
+        Column::Cpu,
+        Column::Memory,
+        Column::Network,
+        Column::Availability,
+        Column::Uptime,
+        Column::Stack,
+    ];
+}
+
+/// Usage above which a container is highlighted and listed in the alerts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    /// CPU percentage
+    pub cpu: f32,
+    /// Share of the memory limit, between 0 and 1
+    pub memory: f32,
+    /// Seconds between the clocks of a container and of the host
+    pub clock_skew: i64,
+    /// Share of the pids limit, between 0 and 1
+    pub pids: f32,
+    /// Restarts within `restart_window_minutes` making a crash loop
+    pub restarts: usize,
+    pub restart_window_minutes: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            cpu: 90.0,
+            memory: 0.9,
+            clock_skew: 5,
+            pids: 0.9,
+            restarts: 3,
+            restart_window_minutes: 5,
+        }
+    }
+}
+
+/// Named filter for the monitoring table, switched at runtime with the
+/// filter action. A container must match every criteria that is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerFilter {
+    pub name: String,
+    /// `key=value` to match a label value, or `key` to only require the label
+    pub label: Option<String>,
+    /// Regular expression matched against the container name
+    pub name_regex: Option<String>,
+}
+
+impl ContainerFilter {
+    /// Parse a selector typed at runtime, made of one `label:key=value` or
+    /// `label:key` term and one `name:regex` term separated by spaces
+    pub fn parse(selector: &str) -> Result<Self, String> {
+        let mut filter = Self {
+            name: selector.trim().to_string(),
+            ..Self::default()
+        };
+        for term in selector.split_whitespace() {
+            let (criteria, value) = match term.split_once(':') {
+                Some(("label", label)) if !label.is_empty() => (&mut filter.label, label),
+                Some(("name", regex)) if !regex.is_empty() => (&mut filter.name_regex, regex),
+                _ => return Err(format!("unknown selector term {}", term)),
+            };
+            if criteria.is_some() {
+                return Err(format!(
+                    "{} is extra, only one label and one name term are matched",
+                    term
+                ));
+            }
+            *criteria = Some(value.to_string());
+        }
+        Ok(filter)
+    }
+
+    /// Whether the labels match the `label` criteria, true when it is unset
+    pub fn matches_labels(&self, labels: &HashMap<String, String>) -> bool {
+        match &self.label {
+            Some(label) => has_label(labels, label),
+            None => true,
+        }
+    }
+}
+
+/// Whether the labels contain `key=value`, or just `key` whatever its value
+pub fn has_label(labels: &HashMap<String, String>, label: &str) -> bool {
+    match label.split_once('=') {
+        Some((key, value)) => labels.get(key).map(String::as_str) == Some(value),
+        None => labels.contains_key(label),
+    }
+}
+
+/// One-liner offered in the snippets popup of the containers running a
+/// matching image, executed with `sh -c`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snippet {
+    pub name: String,
+    pub command: String,
+    /// Regular expression matched against the image, every container when unset
+    pub image_regex: Option<String>,
+}
+
+/// Rewrite of the names shown in the table, e.g. to strip a project prefix.
+/// The rules apply in order, each one to the result of the previous one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NameRule {
+    /// Regular expression matched against the name
+    pub pattern: String,
+    /// Replacement of the matches, `$1` standing for the first group
+    pub replace: String,
+}
+
+/// Database engines whose status bctop knows how to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseEngine {
+    Postgres,
+    Mysql,
+    Redis,
+}
+
+/// Quick stats shown in the database tab of the detail view for the
+/// containers whose image matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseStats {
+    /// Regular expression matched against the image
+    pub image_regex: String,
+    pub engine: DatabaseEngine,
+    /// Replaces the status command of the engine, e.g. to pass credentials.
+    /// Its output must keep the format of the default one.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+impl DatabaseStats {
+    /// Official images of the supported engines and their usual forks
+    pub fn defaults() -> Vec<Self> {
+        [
+            ("postgres|postgis|timescale", DatabaseEngine::Postgres),
+            ("mysql|mariadb|percona", DatabaseEngine::Mysql),
+            ("redis|valkey|keydb", DatabaseEngine::Redis),
+        ]
+        .into_iter()
+        .map(|(image_regex, engine)| Self {
+            image_regex: image_regex.to_string(),
+            engine,
+            command: None,
+        })
+        .collect()
+    }
+}
+
+/// Docker engine bctop can connect to and the settings used with it,
+/// selected with `--profile <name>` or by matching the engine host name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineProfile {
+    pub name: String,
+    /// `unix:///var/run/docker.sock`, `tcp://host:2376` or `http://host:2375`,
+    /// `bctop://host:7878` for an agent, empty for the local defaults
+    pub endpoint: String,
+    pub tls: Option<TlsConfig>,
+    /// Regular expression matched against the host name of the connected
+    /// engine, to use the profile without selecting it
+    pub host_regex: Option<String>,
+    /// Replace the global thresholds
+    pub thresholds: Option<Thresholds>,
+    /// Offered after the global filters
+    pub filters: Vec<ContainerFilter>,
+    /// Replace the global columns
+    pub columns: Option<Vec<Column>>,
+    /// Token the agent of a `bctop://` profile was started with
+    pub agent_token: Option<String>,
+}
+
+impl EngineProfile {
+    /// Address of the `bctop agent` the profile goes through instead of the
+    /// docker API
+    pub fn agent(&self) -> Option<&str> {
+        self.endpoint.strip_prefix("bctop://")
+    }
+
+    /// Host name of a remote engine, where its published ports are reachable
+    pub fn host(&self) -> Option<&str> {
+        let (_, rest) = self.endpoint.split_once("://")?;
+        if self.endpoint.starts_with("unix://") {
+            return None;
+        }
+        let authority = rest.split('/').next()?;
+        let host = match authority.rsplit_once(':') {
+            Some((host, _)) => host,
+            None => authority,
+        };
+        Some(host).filter(|h| !h.is_empty())
+    }
+}
+
+/// Client certificates used to reach an engine over TLS
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub ca: PathBuf,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// Display of the logs of the containers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogViewerConfig {
+    /// Lines shown before and after each match when only the matches are listed
+    pub context_lines: usize,
+    /// Last lines fetched when opening the logs, and added each time more are
+    /// asked for. 0 fetches them all.
+    pub tail: usize,
+    /// How the lines that are JSON objects are shown
+    pub json: JsonLogs,
+    /// Fields of the JSON lines shown in the `fields` mode, in order
+    pub json_fields: Vec<String>,
+    /// Tell upper and lower case apart when searching the logs
+    pub case_sensitive: bool,
+    /// Wrap the long lines, else cut them at the edge of the screen
+    pub wrap: bool,
+}
+
+/// Display of the log lines that are JSON objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonLogs {
+    /// As they are written
+    Raw,
+    /// Only the values of some fields, on one line
+    Fields,
+    /// Indented, one field per line
+    Pretty,
+}
+
+impl JsonLogs {
+    pub fn next(&self) -> Self {
+        match self {
+            JsonLogs::Raw => JsonLogs::Fields,
+            JsonLogs::Fields => JsonLogs::Pretty,
+            JsonLogs::Pretty => JsonLogs::Raw,
+        }
+    }
+}
+
+impl Display for JsonLogs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            JsonLogs::Raw => "raw",
+            JsonLogs::Fields => "fields",
+            JsonLogs::Pretty => "pretty",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl Default for LogViewerConfig {
+    fn default() -> Self {
+        Self {
+            context_lines: 2,
+            tail: 1000,
+            json: JsonLogs::Raw,
+            json_fields: ["time", "timestamp", "level", "msg", "message"]
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+            case_sensitive: false,
+            wrap: true,
+        }
+    }
+}
+
+/// Where and how much bctop logs about itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// Log file, defaults to `<data dir>/bctop/bctop.log`
+    pub path: Option<PathBuf>,
+    pub level: String,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            level: "info".to_string(),
+        }
+    }
+}
+
+/// Keyboard handling of the sessions embedded in bctop (exec, attach)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Keys declared as going to the session, e.g. a tmux prefix like
+    /// `ctrl+b`. When the detach key is one of them, it only detaches when
+    /// pressed twice in a row.
+    pub passthrough: Vec<String>,
+    /// Key leaving the session
+    pub detach: String,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            passthrough: Vec::new(),
+            detach: "ctrl+]".to_string(),
+        }
+    }
+}
+
+/// Destinations of the alerts and events of `bctop daemon`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// URL each event is posted to as JSON
+    pub webhook: Option<String>,
+    /// File each event is appended to, one JSON object per line
+    pub file: Option<PathBuf>,
+}
+
+/// OTLP export of bctop's own operations, disabled without an endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Base URL of an OTLP/HTTP collector, e.g. `http://localhost:4318`
+    pub endpoint: Option<String>,
+    /// Extra headers sent with every export, e.g. for authentication
+    pub headers: HashMap<String, String>,
+    pub service_name: String,
+    /// Seconds between two exports
+    pub interval_seconds: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            headers: HashMap::new(),
+            service_name: "bctop".to_string(),
+            interval_seconds: 10,
+        }
+    }
+}
+
+/// Read-only wall display settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KioskConfig {
+    /// Start in kiosk mode instead of the interactive monitoring table
+    pub enabled: bool,
+    /// Seconds each panel stays on screen before cycling to the next one
+    pub cycle_seconds: u64,
+}
+
+impl Default for KioskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cycle_seconds: 10,
+        }
+    }
+}
+
+/// HTTP probe of the published port of a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeConfig {
+    /// Path requested, e.g. `/healthz`
+    pub path: String,
+    pub interval_seconds: u64,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            path: "/".to_string(),
+            interval_seconds: 5,
+        }
+    }
+}
+
+/// Restarts the matching containers when they crash or stay unhealthy, for
+/// hosts without an orchestrator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub enabled: bool,
+    /// Containers watched, the first matching rule applies
+    pub rules: Vec<WatchdogRule>,
+    /// Minimum seconds between two restarts of a service
+    pub cooldown_seconds: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            cooldown_seconds: 60,
+        }
+    }
+}
+
+/// Containers of the watchdog, matched like a filter: every criteria set must match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogRule {
+    /// `key=value` to match a label value, or `key` to only require the label
+    pub label: Option<String>,
+    /// Regular expression matched against the container name
+    pub name_regex: Option<String>,
+    /// Restart when the container exits with an error or is killed
+    pub on_exit: bool,
+    /// Restart after this many failed health checks in a row, never when unset
+    pub health_failures: Option<i64>,
+}
+
+impl Default for WatchdogRule {
+    fn default() -> Self {
+        Self {
+            label: None,
+            name_regex: None,
+            on_exit: true,
+            health_failures: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_order_from_the_file() {
+        let config: Config =
+            serde_json::from_str(r#"{"sort": {"column": "cpu", "direction": "desc"}}"#).unwrap();
+        assert_eq!(
+            config.sort,
+            SortOrder {
+                column: SortColumn::Cpu,
+                direction: SortDirection::Desc,
+            }
+        );
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.sort, SortOrder::default());
+    }
+
+    #[test]
+    fn unreadable_file_is_not_overwritten() {
+        let config = Config {
+            unreadable: true,
+            ..Config::default()
+        };
+        assert!(config.save().is_err());
+    }
+
+    #[test]
+    fn filters_from_the_file() {
+        let config: Config = serde_json::from_str(
+            r#"{"filters": [{"name": "web", "label": "tier=front", "name_regex": "^web-"}]}"#,
+        )
+        .unwrap();
+        let filter = &config.filters[0];
+        assert_eq!(filter.name, "web");
+        assert_eq!(filter.label.as_deref(), Some("tier=front"));
+        assert_eq!(filter.name_regex.as_deref(), Some("^web-"));
+    }
+
+    #[test]
+    fn label_criteria() {
+        let labels = HashMap::from([("tier".to_string(), "front".to_string())]);
+        assert!(has_label(&labels, "tier"));
+        assert!(has_label(&labels, "tier=front"));
+        assert!(!has_label(&labels, "tier=back"));
+        assert!(!has_label(&labels, "team"));
+        assert!(ContainerFilter::default().matches_labels(&labels));
+        let filter = ContainerFilter {
+            label: Some("tier=back".to_string()),
+            ..ContainerFilter::default()
+        };
+        assert!(!filter.matches_labels(&labels));
+    }
+
+    #[test]
+    fn selector_terms() {
+        let filter = ContainerFilter::parse("label:tier=front name:^web-").unwrap();
+        assert_eq!(filter.name, "label:tier=front name:^web-");
+        assert_eq!(filter.label.as_deref(), Some("tier=front"));
+        assert_eq!(filter.name_regex.as_deref(), Some("^web-"));
+        let filter = ContainerFilter::parse("  label:tier ").unwrap();
+        assert_eq!(filter.name, "label:tier");
+        assert_eq!(filter.label.as_deref(), Some("tier"));
+        assert_eq!(filter.name_regex, None);
+    }
+
+    #[test]
+    fn selector_errors() {
+        assert!(ContainerFilter::parse("label:a label:b")
+            .unwrap_err()
+            .contains("label:b is extra"));
+        assert!(ContainerFilter::parse("name:a name:b")
+            .unwrap_err()
+            .contains("name:b is extra"));
+        for selector in ["web", "image:nginx", "label:", "name:"] {
+            assert!(ContainerFilter::parse(selector)
+                .unwrap_err()
+                .starts_with("unknown selector term"));
+        }
+    }
+}
@@ -1,36 +1,243 @@
 use std::collections::{HashMap, HashSet};
 use std::panic;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use bollard::container::{
-    ListContainersOptions, LogsOptions, RemoveContainerOptions, StatsOptions, StopContainerOptions,
+    Config, CreateContainerOptions, ListContainersOptions, LogOutput, LogsOptions, MemoryStats,
+    MemoryStatsStats, NetworkingConfig, RemoveContainerOptions, RenameContainerOptions,
+    RestartContainerOptions, Stats, StatsOptions, StopContainerOptions, TopOptions,
 };
-use bollard::Docker;
+use bollard::exec::{CreateExecOptions, ResizeExecOptions, StartExecResults};
+use bollard::image::ListImagesOptions;
+use bollard::network::{ConnectNetworkOptions, ListNetworksOptions};
+use bollard::system::EventsOptions;
+use bollard::volume::ListVolumesOptions;
+use bollard::{Docker, API_DEFAULT_VERSION};
 
-use bollard::service::{ContainerStateStatusEnum, ContainerSummary};
+use bollard::service::{
+    ContainerInspectResponse, ContainerStateStatusEnum, ContainerSummary, EndpointSettings,
+    EventMessage, MountPoint,
+};
 use chrono::TimeZone;
-use chrono::Utc;
-use futures::stream::StreamExt;
+use chrono::{DateTime, Utc};
+use futures::stream::{Stream, StreamExt, TryStreamExt};
+use futures::FutureExt;
 use log::{debug, error, info, warn};
-use tokio::sync::Mutex;
+use tokio::io::AsyncWrite;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::config::{EngineProfile, MemoryCalculation};
+use crate::env_overrides::EnvOverride;
+use crate::telemetry;
+
+use super::{
+    CgroupVersion, Container, ContainerDetails, ContainerManagement, ContainerStatus, CrashReport,
+    EngineEvent, EngineSummary, ExecResult, HealthStatus, ImageDefaults, ImageDetails,
+    ImageSummary, LogLine, LogRange, MemoryBreakdown, MountInfo, NetworkInfo, NetworkSummary,
+    Notification, PortForward, PortMapping, ProcessInfo, VolumeSummary,
+};
+
+/// Seconds before a request to the engine times out
+const TIMEOUT: u64 = 120;
+/// Computing sizes is slow for the engine, they are only asked this often
+const SIZE_REFRESH: Duration = Duration::from_secs(30);
+/// Log lines kept when a container crashes
+const CRASH_LOG_LINES: &str = "50";
+/// Log lines first shown in the split view
+const PREVIEW_LOG_LINES: &str = "100";
+/// Most log lines handed to the app at once, when many are already written
+const LOG_BATCH_SIZE: usize = 500;
+/// SGR foreground colors of the services in the logs of a stack, red left out
+/// for the errors
+const STACK_LOG_COLORS: [u8; 10] = [36, 33, 32, 35, 34, 96, 93, 92, 95, 94];
+
+/// Log an error and show it in the error panel
+async fn report(manager: &Arc<Mutex<impl ContainerManagement>>, message: String) {
+    error!("{}", message);
+    manager.lock().await.report_error(message);
+}
+
+/// The container vanished, e.g. removed between the listing and its refresh
+fn is_not_found(e: &bollard::errors::Error) -> bool {
+    matches!(
+        e,
+        bollard::errors::Error::DockerResponseServerError {
+            status_code: 404,
+            ..
+        }
+    )
+}
+
+/// What is known of the engine for the whole connection
+#[derive(Debug, Clone, Copy, Default)]
+struct EngineFacts {
+    /// Total memory of the host, in bytes
+    host_memory: Option<f32>,
+    /// Windows containers report their CPU and memory differently
+    windows: bool,
+}
+
+/// Cumulative counters of a container, kept from one refresh to the next
+/// to turn them into rates and to notice restarts
+#[derive(Debug, Clone, Copy)]
+struct CounterSample {
+    at: Instant,
+    net_rx: u64,
+    net_tx: u64,
+    blk_read: u64,
+    blk_write: u64,
+    running: bool,
+    started_at: Option<DateTime<Utc>>,
+}
 
-use super::{Container, ContainerManagement, ContainerStatus};
+impl CounterSample {
+    /// Bytes per second between two samples of a counter
+    fn rate(&self, previous: Option<&CounterSample>, counter: fn(&Self) -> u64) -> f32 {
+        let previous = match previous {
+            Some(p) => p,
+            None => return 0.0,
+        };
+        let elapsed = (self.at - previous.at).as_secs_f32();
+        if elapsed > 0.0 {
+            // Counters restart from zero along with the container
+            counter(self).saturating_sub(counter(previous)) as f32 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Connect to the engine described by the profile, or to the local defaults
+/// (`DOCKER_HOST` or the local socket) when there is none.
+pub fn connect(profile: Option<&EngineProfile>) -> Result<Docker, bollard::errors::Error> {
+    let profile = match profile {
+        Some(p) => p,
+        None => return Docker::connect_with_local_defaults(),
+    };
+    let endpoint = profile.endpoint.as_str();
+    if endpoint.is_empty() {
+        Docker::connect_with_local_defaults()
+    } else if let Some(path) = endpoint.strip_prefix("unix://") {
+        Docker::connect_with_unix(path, TIMEOUT, API_DEFAULT_VERSION)
+    } else if profile.agent().is_some() {
+        // The handler goes through the agent, this client stays unused
+        Docker::connect_with_local_defaults()
+    } else if let Some(tls) = &profile.tls {
+        Docker::connect_with_ssl(
+            endpoint,
+            &tls.key,
+            &tls.cert,
+            &tls.ca,
+            TIMEOUT,
+            API_DEFAULT_VERSION,
+        )
+    } else {
+        Docker::connect_with_http(endpoint, TIMEOUT, API_DEFAULT_VERSION)
+    }
+}
 
 pub async fn start_management_process(
+    docker: Docker,
     manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
 ) {
-    let docker = Docker::connect_with_local_defaults().unwrap();
+    // Identify the engine so a profile can be picked by host name
+    let mut engine = EngineFacts::default();
+    match docker.info().await {
+        Ok(info) => {
+            engine = EngineFacts {
+                host_memory: info.mem_total.map(|m| m as f32),
+                windows: info.os_type.as_deref() == Some("windows"),
+            };
+            let mut manager = manager.lock().await;
+            manager.set_engine_summary(EngineSummary {
+                version: info.server_version.unwrap_or_default(),
+                os: info.operating_system.unwrap_or_default(),
+                cpus: info.ncpu.unwrap_or_default(),
+                memory: info.mem_total.unwrap_or_default(),
+                running: info.containers_running.unwrap_or_default(),
+                paused: info.containers_paused.unwrap_or_default(),
+                stopped: info.containers_stopped.unwrap_or_default(),
+            });
+            if let Some(name) = info.name {
+                manager.set_engine_host(name);
+            }
+            if let Some(arch) = info.architecture {
+                manager.set_engine_arch(image_arch(&arch).to_string());
+            }
+        }
+        Err(e) => warn!("Error getting engine info: {}", e),
+    }
+
     let mut alive_container_ids = HashSet::new();
+    let mut samples: HashMap<String, CounterSample> = HashMap::new();
+    // Images do not change, their platform is only asked once
+    let mut platforms: HashMap<String, Option<String>> = HashMap::new();
+    let mut sizes: HashMap<String, (Option<i64>, Option<i64>)> = HashMap::new();
+    let mut sizes_at: Option<Instant> = None;
+    // Inspecting every container on each refresh is costly on large hosts,
+    // the details are kept until an event of the container comes
+    let mut details: HashMap<String, ContainerInspectResponse> = HashMap::new();
+    let mut events = container_events(&docker);
     loop {
         let mut tasks = Vec::new();
 
+        while let Some(event) = events.next().now_or_never() {
+            match event {
+                Some(Ok(event)) => {
+                    if let Some(id) = event.actor.and_then(|a| a.id) {
+                        details.remove(&id);
+                    }
+                }
+                // Events may have been missed, everything is inspected again
+                _ => {
+                    details.clear();
+                    events = container_events(&docker);
+                    break;
+                }
+            }
+        }
+
+        let with_sizes = sizes_at.is_none_or(|at| at.elapsed() >= SIZE_REFRESH);
+        let started = SystemTime::now();
         let containers_summary = docker
             .list_containers(Some(ListContainersOptions::<String> {
                 all: true,
+                size: with_sizes,
                 ..Default::default()
             }))
-            .await
-            .unwrap();
+            .await;
+        telemetry::record(
+            "engine_request",
+            started,
+            containers_summary.as_ref().err().map(|e| e.to_string()),
+            &[("request", "list_containers")],
+        );
+        let mut containers_summary = match containers_summary {
+            Ok(c) => c,
+            Err(e) => {
+                // The engine may be restarting, keep trying
+                report(&manager, format!("Error listing containers: {}", e)).await;
+                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        if with_sizes {
+            sizes_at = Some(Instant::now());
+            sizes = containers_summary
+                .iter()
+                .filter_map(|c| Some((c.id.clone()?, (c.size_rw, c.size_root_fs))))
+                .collect();
+        } else {
+            for c in containers_summary.iter_mut() {
+                if let Some((rw, root_fs)) = c.id.as_ref().and_then(|id| sizes.get(id)) {
+                    c.size_rw = *rw;
+                    c.size_root_fs = *root_fs;
+                }
+            }
+        }
         let container_ids: HashSet<String> = containers_summary
             .clone()
             .iter()
@@ -42,20 +249,35 @@ pub async fn start_management_process(
             manager.lock().await.remove_container(&container_id);
         }
 
+        details.retain(|id, _| container_ids.contains(id));
         alive_container_ids = container_ids;
 
         for container_summary in containers_summary {
+            let d = docker.clone();
             let m = manager.clone();
             let cs = container_summary.clone();
+            let previous = cs.id.as_ref().and_then(|id| samples.get(id)).copied();
+            let image_id = cs.image_id.clone().unwrap_or_default();
+            if !platforms.contains_key(&image_id) {
+                let platform = image_platform(&docker, &image_id).await;
+                platforms.insert(image_id.clone(), platform);
+            }
+            let platform = platforms[&image_id].clone();
+            let cached = cs.id.as_ref().and_then(|id| details.get(id)).cloned();
             let t = tokio::spawn(async move {
-                update_container(cs, m).await;
+                update_container(d, cs, m, previous, cached, platform, engine).await
             });
             tasks.push(t);
         }
 
+        samples.clear();
         for t in tasks {
             match t.await {
-                Ok(_) => {}
+                Ok(Some((id, sample, inspected))) => {
+                    samples.insert(id.clone(), sample);
+                    details.insert(id, inspected);
+                }
+                Ok(None) => {}
                 Err(e) => {
                     error!("Error updating container: {}", e);
                     if e.is_panic() {
@@ -68,16 +290,34 @@ pub async fn start_management_process(
     }
 }
 
+/// Refresh a container, returning its counters for the next refresh
+/// Events of the containers, to know when their details change
+fn container_events(
+    docker: &Docker,
+) -> Pin<Box<dyn Stream<Item = Result<EventMessage, bollard::errors::Error>> + Send>> {
+    Box::pin(docker.events(Some(EventsOptions::<String> {
+        filters: HashMap::from([("type".to_string(), vec!["container".to_string()])]),
+        ..Default::default()
+    })))
+}
+
+/// Refresh a container, inspecting it again unless `cached` details are
+/// given. Returns its counters and details for the next refresh.
 async fn update_container(
+    docker: Docker,
     container_summary: ContainerSummary,
     manager: Arc<Mutex<impl ContainerManagement>>,
-) {
-    let docker = Docker::connect_with_local_defaults().unwrap();
+    previous: Option<CounterSample>,
+    cached: Option<ContainerInspectResponse>,
+    platform: Option<String>,
+    engine: EngineFacts,
+) -> Option<(String, CounterSample, ContainerInspectResponse)> {
     let container_id = container_summary.id.unwrap();
-    let labels = container_summary.labels.unwrap_or(HashMap::new());
+    let labels = container_summary.labels.unwrap_or_default();
 
     debug!("Updating container: {}", container_id);
 
+    let started = SystemTime::now();
     let stream = &mut docker
         .stats(
             &container_id,
@@ -87,24 +327,62 @@ async fn update_container(
             }),
         )
         .take(1);
-    let stats = match stream.next().await {
+    let stats = stream.next().await;
+    telemetry::record(
+        "engine_request",
+        started,
+        match &stats {
+            Some(Err(e)) => Some(e.to_string()),
+            _ => None,
+        },
+        &[("request", "stats")],
+    );
+    let stats = match stats {
         Some(Ok(s)) => s,
-        _ => {
-            error!("Error getting stats for container: {}", container_id);
-            return;
+        Some(Err(e)) if is_not_found(&e) => return None,
+        Some(Err(e)) => {
+            let message = format!("Error getting stats of {}: {}", container_id, e);
+            report(&manager, message).await;
+            return None;
         }
+        None => {
+            error!("No stats for container: {}", container_id);
+            return None;
+        }
+    };
+
+    let details = match cached {
+        Some(d) => d,
+        None => match docker.inspect_container(&container_id, None).await {
+            Ok(d) => d,
+            Err(e) if is_not_found(&e) => return None,
+            Err(e) => {
+                let message = format!("Error inspecting container {}: {}", container_id, e);
+                report(&manager, message).await;
+                return None;
+            }
+        },
     };
+    // Never started containers report `0001-01-01T00:00:00Z`
+    let started_at = details
+        .state
+        .as_ref()
+        .and_then(|s| s.started_at.as_deref())
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .filter(|t| t.timestamp() > 0);
 
     let cpu_container_usage = stats
         .cpu_stats
         .cpu_usage
         .total_usage
-        .checked_sub(stats.precpu_stats.cpu_usage.total_usage)
-        .unwrap_or(0u64);
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
     let csu = stats.cpu_stats.system_cpu_usage.unwrap_or(0);
     let psu = stats.precpu_stats.system_cpu_usage.unwrap_or(0);
-    let cpu_system_usage = csu - psu;
-    let cpu_usage = if cpu_system_usage > 0 {
+    let cpu_system_usage = csu.saturating_sub(psu);
+    let cpu_usage = if engine.windows {
+        windows_cpu_usage(&stats, cpu_container_usage)
+    } else if cpu_system_usage > 0 {
         cpu_container_usage as f32 / cpu_system_usage as f32
             * 100.0
             * stats.cpu_stats.online_cpus.unwrap_or(1) as f32
@@ -112,8 +390,100 @@ async fn update_container(
         0.0
     };
 
-    let memory_usage = stats.memory_stats.usage.unwrap_or(0) as f32;
-    let memory_limit = stats.memory_stats.limit.unwrap_or(0) as f32;
+    let per_cpu_usage = match (
+        &stats.cpu_stats.cpu_usage.percpu_usage,
+        &stats.precpu_stats.cpu_usage.percpu_usage,
+    ) {
+        (Some(current), Some(previous)) if cpu_system_usage > 0 => current
+            .iter()
+            .zip(previous)
+            .map(|(c, p)| {
+                c.saturating_sub(*p) as f32 / cpu_system_usage as f32
+                    * 100.0
+                    * stats.cpu_stats.online_cpus.unwrap_or(1) as f32
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let networks = stats.networks.unwrap_or_default();
+    // cgroup v1 reports `Read`/`Write`, v2 `read`/`write`
+    let block_io = stats
+        .blkio_stats
+        .io_service_bytes_recursive
+        .unwrap_or_default();
+    let block_bytes = |op: &str| -> u64 {
+        block_io
+            .iter()
+            .filter(|e| e.op.eq_ignore_ascii_case(op))
+            .map(|e| e.value)
+            .sum()
+    };
+    // Windows has no block I/O statistics but storage ones
+    let (blk_read, blk_write) = if engine.windows {
+        (
+            stats.storage_stats.read_size_bytes.unwrap_or(0),
+            stats.storage_stats.write_size_bytes.unwrap_or(0),
+        )
+    } else {
+        (block_bytes("read"), block_bytes("write"))
+    };
+    let sample = CounterSample {
+        at: Instant::now(),
+        net_rx: networks.values().map(|n| n.rx_bytes).sum(),
+        net_tx: networks.values().map(|n| n.tx_bytes).sum(),
+        blk_read,
+        blk_write,
+        running: details
+            .state
+            .as_ref()
+            .and_then(|s| s.running)
+            .unwrap_or(false),
+        started_at,
+    };
+    if let Some(previous) = &previous {
+        check_crash(
+            &docker,
+            &container_id,
+            &details,
+            previous,
+            &sample,
+            &manager,
+        )
+        .await;
+    }
+
+    let memory = if engine.windows {
+        // The private working set is what Task Manager and `docker stats` show
+        MemoryBreakdown {
+            usage: stats.memory_stats.privateworkingset.unwrap_or(0) as f32,
+            ..Default::default()
+        }
+    } else {
+        memory_breakdown(&stats.memory_stats)
+    };
+    let memory_limit = match details.host_config.as_ref().and_then(|h| h.memory) {
+        // Windows reports no limit in the statistics, only in the configuration
+        Some(limit) if engine.windows && limit > 0 => limit as f32,
+        _ => memory_limit(&stats.memory_stats, engine.host_memory),
+    };
+
+    let mut ports: Vec<PortMapping> = container_summary
+        .ports
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.public_port.is_some())
+        .map(|p| PortMapping {
+            container_port: format!(
+                "{}/{}",
+                p.private_port,
+                p.typ.map_or("tcp".to_string(), |t| t.to_string())
+            ),
+            host_ip: p.ip,
+            host_port: p.public_port.map(|port| port.to_string()),
+        })
+        .collect();
+    ports.sort_by(|a, b| a.container_port.cmp(&b.container_port));
 
     let container = Container {
         id: container_id,
@@ -125,109 +495,1518 @@ async fn update_container(
             .to_string(),
         image: container_summary.image.unwrap(),
         status: ContainerStatus::from(container_summary.state.unwrap_or(String::from("running"))),
+        health: container_summary
+            .status
+            .as_deref()
+            .and_then(HealthStatus::from_status_text),
+        health_failing_streak: details
+            .state
+            .as_ref()
+            .and_then(|s| s.health.as_ref())
+            .and_then(|h| h.failing_streak)
+            .unwrap_or(0),
         swarm_service: labels.get("com.docker.swarm.service.name").cloned(),
         swarm_stack: labels.get("com.docker.stack.namespace").cloned(),
         compose_service: labels.get("com.docker.compose.service").cloned(),
         compose_project: labels.get("com.docker.compose.project").cloned(),
-        cpu_usage: cpu_usage,
-        memory_usage_bytes: memory_usage,
+        labels,
+        ports,
+        mounts: mount_infos(details.mounts.clone()),
+        started_at,
+        cpu_usage,
+        per_cpu_usage,
+        memory_usage_bytes: memory.used(MemoryCalculation::default()),
         memory_limit_bytes: memory_limit,
+        memory,
+        net_rx_rate: sample.rate(previous.as_ref(), |s| s.net_rx),
+        net_tx_rate: sample.rate(previous.as_ref(), |s| s.net_tx),
+        block_read_rate: sample.rate(previous.as_ref(), |s| s.blk_read),
+        block_write_rate: sample.rate(previous.as_ref(), |s| s.blk_write),
+        pids: stats.pids_stats.current.unwrap_or(0),
+        // Unlimited is reported as 0 or as the largest value depending on the cgroup version
+        pids_limit: stats.pids_stats.limit.filter(|l| *l > 0 && *l < u64::MAX),
+        restart_count: details.restart_count.unwrap_or_default(),
+        platform,
+        runtime: details.host_config.as_ref().and_then(|h| h.runtime.clone()),
+        size_rw: container_summary.size_rw,
+        size_root_fs: container_summary.size_root_fs,
     };
 
+    let id = container.id.clone();
     manager.lock().await.update_containers(container);
+    Some((id, sample, details))
+}
+
+fn mount_infos(mounts: Option<Vec<MountPoint>>) -> Vec<MountInfo> {
+    mounts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|m| MountInfo {
+            kind: m.typ.map(|t| t.to_string()).unwrap_or_default(),
+            name: m.name.filter(|n| !n.is_empty()),
+            source: m.source.unwrap_or_default(),
+            destination: m.destination.unwrap_or_default(),
+            rw: m.rw.unwrap_or(true),
+            driver: m.driver.filter(|d| !d.is_empty()),
+        })
+        .collect()
+}
+
+/// Split the memory usage with the statistics of cgroup v1 or v2
+fn memory_breakdown(stats: &MemoryStats) -> MemoryBreakdown {
+    let usage = stats.usage.unwrap_or(0) as f32;
+    // Without hierarchical accounting cgroup v1 leaves the `total_` counters at zero
+    let v1 = |total: u64, own: u64| Some(if total > 0 { total } else { own } as f32);
+    match &stats.stats {
+        Some(MemoryStatsStats::V1(s)) => MemoryBreakdown {
+            usage,
+            rss: v1(s.total_rss, s.rss),
+            cache: v1(s.total_cache, s.cache),
+            inactive_file: v1(s.total_inactive_file, s.inactive_file),
+            cgroup: Some(CgroupVersion::V1),
+        },
+        // cgroup v2 has no cache counter, the file backed memory is the page cache
+        Some(MemoryStatsStats::V2(s)) => MemoryBreakdown {
+            usage,
+            rss: Some(s.anon as f32),
+            cache: Some(s.file as f32),
+            inactive_file: Some(s.inactive_file as f32),
+            cgroup: Some(CgroupVersion::V2),
+        },
+        None => MemoryBreakdown {
+            usage,
+            ..Default::default()
+        },
+    }
+}
+
+/// CPU percentage of a Windows container: the 100ns intervals used over the
+/// intervals available to all the processors between the two reads
+fn windows_cpu_usage(stats: &Stats, used_intervals: u64) -> f32 {
+    let read = DateTime::parse_from_rfc3339(&stats.read);
+    let preread = DateTime::parse_from_rfc3339(&stats.preread);
+    let elapsed = match (read, preread) {
+        (Ok(read), Ok(preread)) => (read - preread).num_nanoseconds().unwrap_or(0),
+        _ => return 0.0,
+    };
+    let possible_intervals = elapsed.max(0) as f32 / 100.0 * stats.num_procs.max(1) as f32;
+    if possible_intervals > 0.0 {
+        used_intervals as f32 / possible_intervals * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Memory limit of a container, the host memory when it has none. Engines
+/// report no limit as zero, as the host memory or as the largest cgroup
+/// value depending on the cgroup version.
+fn memory_limit(stats: &MemoryStats, host_memory: Option<f32>) -> f32 {
+    let limit = stats.limit.unwrap_or(0) as f32;
+    match host_memory {
+        Some(host) if host > 0.0 && (limit <= 0.0 || limit > host) => host,
+        _ => limit,
+    }
+}
+
+/// `os/arch[/variant]` of an image, `None` when it can not be inspected
+async fn image_platform(docker: &Docker, image_id: &str) -> Option<String> {
+    let image = match docker.inspect_image(image_id).await {
+        Ok(i) => i,
+        Err(e) => {
+            debug!("Error inspecting image {}: {}", image_id, e);
+            return None;
+        }
+    };
+    let mut platform = format!(
+        "{}/{}",
+        image.os.unwrap_or_default(),
+        image.architecture.unwrap_or_default()
+    );
+    if let Some(variant) = image.variant.filter(|v| !v.is_empty()) {
+        platform = format!("{}/{}", platform, variant);
+    }
+    Some(platform)
+}
+
+/// Image platform name of an architecture reported by `uname -m`
+fn image_arch(machine: &str) -> &str {
+    match machine {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "i386" | "i686" => "386",
+        m if m.starts_with("armv") => "arm",
+        m => m,
+    }
+}
+
+/// Record the exit code and last logs of a container that went down on its
+/// own, or restarted between two refreshes
+async fn check_crash(
+    docker: &Docker,
+    container_id: &str,
+    details: &ContainerInspectResponse,
+    previous: &CounterSample,
+    sample: &CounterSample,
+    manager: &Arc<Mutex<impl ContainerManagement>>,
+) {
+    let state = details.state.clone().unwrap_or_default();
+    let oom_killed = state.oom_killed.unwrap_or(false);
+    let exit_code = state.exit_code.unwrap_or(0);
+    let (until, exit_code) = if previous.running && !sample.running {
+        // A stop asked by the user exits cleanly and is not a crash
+        if !state.restarting.unwrap_or(false) && exit_code == 0 && !oom_killed {
+            return;
+        }
+        (Utc::now(), Some(exit_code))
+    } else if let (true, Some(previous_start), Some(started)) =
+        (sample.running, previous.started_at, sample.started_at)
+    {
+        if started == previous_start {
+            return;
+        }
+        // Down and up again before we noticed, keep the logs of the previous run
+        (started, None)
+    } else {
+        return;
+    };
+
+    let mut logs = docker.logs(
+        container_id,
+        Some(LogsOptions {
+            until: until.timestamp(),
+            stdout: true,
+            stderr: true,
+            tail: CRASH_LOG_LINES,
+            ..Default::default()
+        }),
+    );
+    let mut lines = Vec::new();
+    while let Some(Ok(chunk)) = logs.next().await {
+        lines.push(chunk.to_string().trim_end().to_string());
+    }
+    info!("Container {} crashed with {:?}", container_id, exit_code);
+    manager.lock().await.record_crash(
+        container_id.to_string(),
+        CrashReport {
+            at: until,
+            exit_code,
+            oom_killed,
+            logs: lines,
+        },
+    );
 }
 
+/// Show the logs of a container, the last `tail` lines or all of them, or the
+/// ones of `range`. They are followed unless the range has an end.
 pub async fn start_monitoring_logs(
+    docker: Docker,
     container_id: String,
+    range: Option<LogRange>,
+    tail: Option<usize>,
     manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
 ) {
-    let docker = Docker::connect_with_local_defaults().unwrap();
-    let mut now = Utc.timestamp(0, 0);
-
-    loop {
-        let mut logs = docker.logs(
+    let since = range.map_or(0, |r| r.since);
+    let until = match range.and_then(|r| r.until) {
+        Some(until) => until,
+        None => {
+            let tail = tail.map_or("all".to_string(), |t| t.to_string());
+            follow_logs(
+                &docker,
+                &container_id,
+                &tail,
+                since,
+                &manager,
+                |m, lines| m.add_logs(lines),
+            )
+            .await;
+            return;
+        }
+    };
+    let mut logs = docker
+        .logs(
             &container_id,
             Some(LogsOptions {
-                since: now.timestamp(),
-                follow: false,
+                since,
+                until,
                 stdout: true,
                 stderr: true,
                 tail: "all",
                 ..Default::default()
             }),
+        )
+        .ready_chunks(LOG_BATCH_SIZE);
+    while let Some(chunks) = logs.next().await {
+        let lines = match chunks.into_iter().collect::<Result<Vec<_>, _>>() {
+            Ok(chunks) => chunks.iter().map(log_line).collect(),
+            Err(e) => {
+                let message = format!("Error reading the logs of {}: {}", container_id, e);
+                report(&manager, message).await;
+                return;
+            }
+        };
+        manager.lock().await.add_logs(lines);
+    }
+}
+
+/// Follow the tail of the logs of the container selected in the split view
+pub async fn start_preview_logs(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
+) {
+    follow_logs(
+        &docker,
+        &container_id,
+        PREVIEW_LOG_LINES,
+        0,
+        &manager,
+        |m, chunks| {
+            let lines = chunks
+                .iter()
+                .flat_map(|c| c.text.lines())
+                .map(str::to_string);
+            m.add_preview_logs(container_id.clone(), lines.collect())
+        },
+    )
+    .await;
+}
+
+/// Follow the logs of several containers together, each line prefixed by the
+/// service name given with the container id in a color of its own
+pub async fn start_stack_logs(
+    docker: Docker,
+    containers: Vec<(String, String)>,
+    tail: Option<usize>,
+    manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
+) {
+    let tail = tail.map_or("all".to_string(), |t| t.to_string());
+    let width = containers.iter().map(|(_, s)| s.len()).max().unwrap_or(0);
+    let follows = containers.iter().enumerate().map(|(i, (id, service))| {
+        let color = STACK_LOG_COLORS[i % STACK_LOG_COLORS.len()];
+        let prefix = format!(
+            "\x1b[{}m{:<width$} |\x1b[0m ",
+            color,
+            service,
+            width = width
         );
-        let mut logs_vec = Vec::new();
-        while let Some(Ok(chunk)) = logs.next().await {
-            logs_vec.push(format!("{}", chunk));
+        follow_logs(&docker, id, &tail, 0, &manager, move |m, chunks| {
+            let prefix = &prefix;
+            let lines = chunks.iter().flat_map(|c| {
+                c.text.lines().map(move |l| LogLine {
+                    text: format!("{}{}", prefix, l),
+                    stderr: c.stderr,
+                    at: c.at,
+                })
+            });
+            m.add_logs(lines.collect())
+        })
+    });
+    futures::future::join_all(follows).await;
+}
+
+fn log_line(output: &LogOutput) -> LogLine {
+    LogLine {
+        text: output.to_string(),
+        stderr: matches!(output, LogOutput::StdErr { .. }),
+        at: None,
+    }
+}
+
+/// Line of logs fetched with timestamps, the time taken off its text
+fn timestamped_log_line(output: &LogOutput) -> LogLine {
+    let mut line = log_line(output);
+    if let Some((time, text)) = line.text.split_once(' ') {
+        if let Ok(at) = DateTime::parse_from_rfc3339(time) {
+            line.at = Some(at.with_timezone(&Utc));
+            line.text = text.to_string();
         }
-        now = Utc::now();
-        manager.lock().await.add_logs(logs_vec);
+    }
+    line
+}
+
+/// Stream the logs of a container as they are written, starting with the last
+/// `tail` lines written after `since`. Lines already there are delivered in
+/// batches. The stream is opened again after an error or when the container
+/// stops, from that time on.
+async fn follow_logs<M: ContainerManagement>(
+    docker: &Docker,
+    container_id: &str,
+    tail: &str,
+    since: i64,
+    manager: &Arc<Mutex<M>>,
+    deliver: impl Fn(&mut M, Vec<LogLine>),
+) {
+    let mut since = since;
+    let mut tail = tail;
+
+    loop {
+        let mut logs = docker
+            .logs(
+                container_id,
+                Some(LogsOptions {
+                    since,
+                    follow: true,
+                    stdout: true,
+                    stderr: true,
+                    timestamps: true,
+                    tail,
+                    ..Default::default()
+                }),
+            )
+            .ready_chunks(LOG_BATCH_SIZE);
+        while let Some(chunks) = logs.next().await {
+            let mut lines = Vec::with_capacity(chunks.len());
+            let mut error = None;
+            for chunk in chunks {
+                match chunk {
+                    Ok(chunk) => lines.push(timestamped_log_line(&chunk)),
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+            }
+            deliver(&mut *manager.lock().await, lines);
+            if let Some(e) = error {
+                let message = format!("Error reading the logs of {}: {}", container_id, e);
+                report(manager, message).await;
+                break;
+            }
+        }
+        since = Utc::now().timestamp();
+        tail = "all";
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
     }
 }
 
-pub async fn stop_container(container_id: String) {
-    let docker = Docker::connect_with_local_defaults().unwrap();
-    match docker.inspect_container(&container_id, None).await {
-        Ok(container) => {
-            let status = container
-                .state
+/// Fetch every log line of a container, to write them to `path`
+pub async fn export_logs(
+    docker: Docker,
+    container_id: String,
+    path: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let logs = docker
+        .logs(
+            &container_id,
+            Some(LogsOptions {
+                stdout: true,
+                stderr: true,
+                tail: "all",
+                ..Default::default()
+            }),
+        )
+        .map_ok(|chunk| chunk.to_string())
+        .try_collect()
+        .await;
+    match logs {
+        Ok(logs) => manager.lock().await.logs_to_export(path, logs),
+        Err(e) => {
+            let message = format!("Error reading the logs of {}: {}", container_id, e);
+            report(&manager, message).await;
+        }
+    }
+}
+
+/// Follow the logs of a container for a client of the agent, starting with the
+/// last `tail` lines, until the stream ends or the receiver is gone
+pub async fn stream_logs(
+    docker: Docker,
+    container_id: String,
+    tail: Option<usize>,
+    lines: mpsc::Sender<Vec<LogLine>>,
+) {
+    let tail = tail.map_or("all".to_string(), |t| t.to_string());
+    let mut logs = docker
+        .logs(
+            &container_id,
+            Some(LogsOptions {
+                follow: true,
+                stdout: true,
+                stderr: true,
+                timestamps: true,
+                tail: tail.as_str(),
+                ..Default::default()
+            }),
+        )
+        .ready_chunks(LOG_BATCH_SIZE);
+    while let Some(chunks) = logs.next().await {
+        let batch = match chunks.into_iter().collect::<Result<Vec<_>, _>>() {
+            Ok(chunks) => chunks.iter().map(timestamped_log_line).collect(),
+            Err(e) => {
+                warn!("Error reading the logs of {}: {}", container_id, e);
+                return;
+            }
+        };
+        if lines.send(batch).await.is_err() {
+            return;
+        }
+    }
+}
+
+pub async fn inspect_container(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let details = match docker.inspect_container(&container_id, None).await {
+        Ok(d) => d,
+        Err(e) => {
+            let message = format!("Error inspecting container {}: {}", container_id, e);
+            report(&manager, message).await;
+            return;
+        }
+    };
+    let config = details.config.unwrap_or_default();
+    let state = details.state.unwrap_or_default();
+    let network_settings = details.network_settings.unwrap_or_default();
+
+    let command = details
+        .path
+        .into_iter()
+        .chain(details.args.unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let restart_policy = details
+        .host_config
+        .and_then(|h| h.restart_policy)
+        .and_then(|p| p.name)
+        .map(|n| n.to_string())
+        .unwrap_or_default();
+
+    let mut ports = Vec::new();
+    for (container_port, bindings) in network_settings.ports.unwrap_or_default() {
+        match bindings {
+            Some(bindings) if !bindings.is_empty() => {
+                ports.extend(bindings.into_iter().map(|b| PortMapping {
+                    container_port: container_port.clone(),
+                    host_ip: b.host_ip,
+                    host_port: b.host_port,
+                }))
+            }
+            _ => ports.push(PortMapping {
+                container_port,
+                host_ip: None,
+                host_port: None,
+            }),
+        }
+    }
+    // Exposed by the image or `--expose` but absent while the container is stopped
+    for container_port in config.exposed_ports.clone().unwrap_or_default().into_keys() {
+        if !ports.iter().any(|p| p.container_port == container_port) {
+            ports.push(PortMapping {
+                container_port,
+                host_ip: None,
+                host_port: None,
+            });
+        }
+    }
+    ports.sort_by(|a, b| a.container_port.cmp(&b.container_port));
+
+    let mounts = mount_infos(details.mounts);
+
+    let mut networks: Vec<NetworkInfo> = network_settings
+        .networks
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, n)| NetworkInfo {
+            name,
+            ip_address: n.ip_address.unwrap_or_default(),
+            gateway: n.gateway.unwrap_or_default(),
+            mac_address: n.mac_address.unwrap_or_default(),
+        })
+        .collect();
+    networks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let container_details = ContainerDetails {
+        id: container_id,
+        created: details.created.unwrap_or_default(),
+        command,
+        cmd: config.cmd.unwrap_or_default().join(" "),
+        entrypoint: config.entrypoint.unwrap_or_default().join(" "),
+        working_dir: config.working_dir.unwrap_or_default(),
+        user: config.user.unwrap_or_default(),
+        hostname: config.hostname.unwrap_or_default(),
+        restart_policy,
+        env: config.env.unwrap_or_default(),
+        state: state.status.map(|s| s.to_string()).unwrap_or_default(),
+        pid: state.pid.unwrap_or_default(),
+        exit_code: state.exit_code.unwrap_or_default(),
+        error: state.error.unwrap_or_default(),
+        finished_at: state.finished_at.unwrap_or_default(),
+        oom_killed: state.oom_killed.unwrap_or_default(),
+        restart_count: details.restart_count.unwrap_or_default(),
+        ports,
+        mounts,
+        networks,
+    };
+    manager
+        .lock()
+        .await
+        .update_container_details(container_details);
+}
+
+pub async fn inspect_image(
+    docker: Docker,
+    image: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let details = match docker.inspect_image(&image).await {
+        Ok(d) => d,
+        Err(e) => {
+            report(&manager, format!("Error inspecting image {}: {}", image, e)).await;
+            return;
+        }
+    };
+    let config = details.config.unwrap_or_default();
+    let image_details = ImageDetails {
+        registry: ImageDetails::registry_of(&image),
+        reference: image,
+        id: details.id.unwrap_or_default(),
+        digests: details.repo_digests.unwrap_or_default(),
+        labels: config.labels.unwrap_or_default(),
+        defaults: ImageDefaults {
+            cmd: config.cmd.unwrap_or_default().join(" "),
+            entrypoint: config.entrypoint.unwrap_or_default().join(" "),
+            working_dir: config.working_dir.unwrap_or_default(),
+            user: config.user.unwrap_or_default(),
+            env: config.env.unwrap_or_default(),
+        },
+    };
+    manager.lock().await.update_image_details(image_details);
+}
+
+pub async fn list_images(docker: Docker, manager: Arc<Mutex<impl ContainerManagement>>) {
+    let images = match docker
+        .list_images(Some(ListImagesOptions::<String>::default()))
+        .await
+    {
+        Ok(i) => i,
+        Err(e) => {
+            report(&manager, format!("Error listing images: {}", e)).await;
+            return;
+        }
+    };
+    let mut images: Vec<ImageSummary> = images
+        .into_iter()
+        .map(|i| ImageSummary {
+            id: i.id,
+            tags: i
+                .repo_tags
+                .into_iter()
+                .filter(|t| t != "<none>:<none>")
+                .collect(),
+            size: i.size,
+            created: Utc.timestamp_opt(i.created, 0).single(),
+            // -1 until the engine computed it
+            containers: Some(i.containers).filter(|c| *c >= 0),
+        })
+        .collect();
+    images.sort_by_key(|i| std::cmp::Reverse(i.created));
+    manager.lock().await.update_images(images);
+}
+
+pub async fn list_volumes(docker: Docker, manager: Arc<Mutex<impl ContainerManagement>>) {
+    let response = match docker
+        .list_volumes(None::<ListVolumesOptions<String>>)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            report(&manager, format!("Error listing volumes: {}", e)).await;
+            return;
+        }
+    };
+    let mut volumes: Vec<VolumeSummary> = response
+        .volumes
+        .unwrap_or_default()
+        .into_iter()
+        .map(|v| VolumeSummary {
+            name: v.name,
+            driver: v.driver,
+            mountpoint: v.mountpoint,
+            created: v.created_at,
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.name.cmp(&b.name));
+    manager.lock().await.update_volumes(volumes);
+}
+
+/// Remove an image left over by a prune, the engine refusing it when a
+/// container still uses it
+pub async fn remove_image(
+    docker: Docker,
+    image_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let id = image_id.trim_start_matches("sha256:");
+    let id = &id[..id.len().min(12)];
+    notify_progress(&manager, format!("Removing image {}…", id)).await;
+    let result = docker.remove_image(&image_id, None, None).await.map(|_| ());
+    notify_outcome(&manager, &format!("image {}", id), "Removed", result).await;
+    list_images(docker, manager).await;
+}
+
+/// Remove a volume left over by a prune, the engine refusing it when a
+/// container still mounts it
+pub async fn remove_volume(
+    docker: Docker,
+    name: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    notify_progress(&manager, format!("Removing volume {}…", name)).await;
+    let result = docker.remove_volume(&name, None).await;
+    notify_outcome(&manager, &format!("volume {}", name), "Removed", result).await;
+    list_volumes(docker, manager).await;
+}
+
+pub async fn list_networks(docker: Docker, manager: Arc<Mutex<impl ContainerManagement>>) {
+    let networks = match docker
+        .list_networks(None::<ListNetworksOptions<String>>)
+        .await
+    {
+        Ok(n) => n,
+        Err(e) => {
+            report(&manager, format!("Error listing networks: {}", e)).await;
+            return;
+        }
+    };
+    let mut networks: Vec<NetworkSummary> = networks
+        .into_iter()
+        .map(|n| NetworkSummary {
+            id: n.id.unwrap_or_default(),
+            name: n.name.unwrap_or_default(),
+            driver: n.driver.unwrap_or_default(),
+            scope: n.scope.unwrap_or_default(),
+            subnets: n
+                .ipam
+                .and_then(|ipam| ipam.config)
                 .unwrap_or_default()
-                .status
-                .unwrap_or(ContainerStateStatusEnum::EMPTY);
-            match status {
-                ContainerStateStatusEnum::RUNNING => {
-                    docker
-                        .stop_container(
-                            &container_id,
-                            Some(StopContainerOptions {
-                                t: 10,
-                                ..Default::default()
-                            }),
-                        )
-                        .await
-                        .unwrap();
-                }
-                ContainerStateStatusEnum::EXITED | ContainerStateStatusEnum::CREATED => {
-                    docker
-                        .remove_container(
-                            &container_id,
-                            Some(RemoveContainerOptions {
-                                force: true,
-                                ..Default::default()
-                            }),
-                        )
-                        .await
-                        .unwrap();
-                }
-                _ => warn!("Container in invalid status: {}", status),
+                .into_iter()
+                .filter_map(|c| c.subnet)
+                .collect(),
+            internal: n.internal.unwrap_or(false),
+        })
+        .collect();
+    networks.sort_by(|a, b| a.name.cmp(&b.name));
+    manager.lock().await.update_networks(networks);
+}
+
+/// Follow the events of the engine until the task is aborted
+pub async fn watch_events(docker: Docker, manager: Arc<Mutex<impl ContainerManagement>>) {
+    let mut events = docker.events(None::<EventsOptions<String>>);
+    while let Some(event) = events.next().await {
+        let event = match event {
+            Ok(e) => e,
+            Err(e) => {
+                report(&manager, format!("Error following engine events: {}", e)).await;
+                return;
+            }
+        };
+        let actor = event.actor.unwrap_or_default();
+        // Containers, images and volumes have a name, the rest only an id
+        let name = actor
+            .attributes
+            .and_then(|mut a| a.remove("name"))
+            .or(actor.id)
+            .unwrap_or_default();
+        manager.lock().await.add_engine_event(EngineEvent {
+            at: event
+                .time
+                .and_then(|t| Utc.timestamp_opt(t, 0).single())
+                .unwrap_or_else(Utc::now),
+            kind: event.typ.map(|t| t.to_string()).unwrap_or_default(),
+            action: event.action.unwrap_or_default(),
+            actor: name,
+        });
+    }
+}
+
+pub async fn list_processes(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let top = match docker
+        .top_processes(
+            &container_id,
+            Some(TopOptions {
+                ps_args: "-o pid,ppid,stat,args",
+            }),
+        )
+        .await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            let message = format!("Error listing processes of {}: {}", container_id, e);
+            report(&manager, message).await;
+            return;
+        }
+    };
+    let titles = top.titles.unwrap_or_default();
+    let column = |name: &str| titles.iter().position(|t| t == name);
+    let (pid, ppid, state) = (column("PID"), column("PPID"), column("STAT"));
+    // The command is the last column, whatever its title is (`CMD`, `COMMAND`...)
+    let command = titles.len().saturating_sub(1);
+    let processes = top
+        .processes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|row| {
+            let field = |i: Option<usize>| i.and_then(|i| row.get(i)).cloned();
+            Some(ProcessInfo {
+                pid: field(pid)?.parse().ok()?,
+                ppid: field(ppid).and_then(|p| p.parse().ok()),
+                state: field(state).unwrap_or_default(),
+                command: field(Some(command)).unwrap_or_default(),
+            })
+        })
+        .collect();
+    manager
+        .lock()
+        .await
+        .update_processes(container_id, processes);
+}
+
+/// Run a shell command in a container and report its output once it exits
+pub async fn exec_command(
+    docker: Docker,
+    container_id: String,
+    command: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let (output, exit_code) = match run_exec(&docker, &container_id, &command).await {
+        Ok(result) => result,
+        Err(e) => {
+            let message = format!("Error executing {} in {}: {}", command, container_id, e);
+            report(&manager, message).await;
+            (vec![e.to_string()], None)
+        }
+    };
+    manager.lock().await.exec_finished(ExecResult {
+        container_id,
+        command,
+        output,
+        exit_code,
+    });
+}
+
+async fn run_exec(
+    docker: &Docker,
+    container_id: &str,
+    command: &str,
+) -> Result<(Vec<String>, Option<i64>), bollard::errors::Error> {
+    let env = exec_env();
+    let exec = docker
+        .create_exec(
+            container_id,
+            CreateExecOptions {
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                cmd: Some(vec!["sh", "-c", command]),
+                env: Some(env.iter().map(String::as_str).collect()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let mut output = String::new();
+    if let StartExecResults::Attached {
+        output: mut stream, ..
+    } = docker.start_exec(&exec.id, None).await?
+    {
+        while let Some(chunk) = stream.next().await {
+            output.push_str(&chunk?.to_string());
+        }
+    }
+    let exit_code = docker.inspect_exec(&exec.id).await?.exit_code;
+    Ok((output.lines().map(String::from).collect(), exit_code))
+}
+
+/// Terminal type, locale and size of the host terminal, given to the exec
+/// sessions like `docker exec -it` does
+fn exec_env() -> Vec<String> {
+    let var = |names: &[&str], default: &str| {
+        names
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+            .unwrap_or_else(|| default.to_string())
+    };
+    let mut env = vec![
+        format!("TERM={}", var(&["TERM"], "xterm-256color")),
+        format!("LANG={}", var(&["LC_ALL", "LANG"], "C.UTF-8")),
+    ];
+    if let Ok((columns, lines)) = crossterm::terminal::size() {
+        env.push(format!("COLUMNS={}", columns));
+        env.push(format!("LINES={}", lines));
+    }
+    env
+}
+
+/// Command running in a container with a TTY and its stdin attached, like
+/// `docker exec -it`
+pub struct ExecSession {
+    pub id: String,
+    pub output: Pin<Box<dyn Stream<Item = Result<LogOutput, bollard::errors::Error>> + Send>>,
+    pub input: Pin<Box<dyn AsyncWrite + Send>>,
+}
+
+/// Start an interactive shell command in a container, `None` when the engine
+/// did not attach to it
+pub async fn start_session(
+    docker: &Docker,
+    container_id: &str,
+    command: &str,
+) -> Result<Option<ExecSession>, bollard::errors::Error> {
+    let env = exec_env();
+    let exec = docker
+        .create_exec(
+            container_id,
+            CreateExecOptions {
+                attach_stdin: Some(true),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                tty: Some(true),
+                cmd: Some(vec!["sh", "-c", command]),
+                env: Some(env.iter().map(String::as_str).collect()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    // The TTY comes from the creation, the engine still frames the output as
+    // stdout and stderr since starting can not ask for the raw stream
+    match docker.start_exec(&exec.id, None).await? {
+        StartExecResults::Attached { output, input } => Ok(Some(ExecSession {
+            id: exec.id,
+            output,
+            input,
+        })),
+        StartExecResults::Detached => Ok(None),
+    }
+}
+
+/// Give the TTY of a session the size of the terminal
+pub async fn resize_session(
+    docker: &Docker,
+    id: &str,
+    columns: u16,
+    lines: u16,
+) -> Result<(), bollard::errors::Error> {
+    let options = ResizeExecOptions {
+        width: columns,
+        height: lines,
+    };
+    docker.resize_exec(id, options).await
+}
+
+/// Exit code of a session whose command ended
+pub async fn session_exit_code(docker: &Docker, id: &str) -> Option<i64> {
+    docker.inspect_exec(id).await.ok()?.exit_code
+}
+
+/// Follow a file of a container, feeding its lines to the log viewer
+pub async fn start_tailing_file(
+    docker: Docker,
+    container_id: String,
+    path: String,
+    manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
+) {
+    let exec = docker
+        .create_exec(
+            &container_id,
+            CreateExecOptions {
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                cmd: Some(vec!["tail", "-n", "1000", "-F", path.as_str()]),
+                ..Default::default()
+            },
+        )
+        .await;
+    let output = match exec {
+        Ok(exec) => docker.start_exec(&exec.id, None).await,
+        Err(e) => Err(e),
+    };
+    let mut output = match output {
+        Ok(StartExecResults::Attached { output, .. }) => output,
+        Ok(StartExecResults::Detached) => return,
+        Err(e) => {
+            let message = format!("Error following {} in {}: {}", path, container_id, e);
+            report(&manager, message).await;
+            manager.lock().await.add_logs(vec![LogLine {
+                text: format!("Could not follow {}: {}", path, e),
+                stderr: true,
+                at: None,
+            }]);
+            return;
+        }
+    };
+    while let Some(chunk) = output.next().await {
+        match chunk {
+            Ok(chunk) => {
+                let line = log_line(&chunk);
+                let lines = line.text.lines().map(|text| LogLine {
+                    text: text.to_string(),
+                    stderr: line.stderr,
+                    at: None,
+                });
+                manager.lock().await.add_logs(lines.collect());
+            }
+            Err(e) => {
+                let message = format!("Error following {} in {}: {}", path, container_id, e);
+                report(&manager, message).await;
+                break;
             }
         }
+    }
+}
+
+/// Proxy connections to a local port to a port of a container, reachable
+/// even when it is not published. Runs until the task is aborted.
+pub async fn start_port_forward(
+    docker: Docker,
+    container_id: String,
+    local_port: u16,
+    container_port: u16,
+    manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
+) {
+    let ip = match docker.inspect_container(&container_id, None).await {
+        Ok(details) => details
+            .network_settings
+            .and_then(|n| n.networks)
+            .unwrap_or_default()
+            .into_values()
+            .filter_map(|n| n.ip_address)
+            .find(|ip| !ip.is_empty()),
+        Err(e) => {
+            error!("Error inspecting container {}: {}", container_id, e);
+            None
+        }
+    };
+    let target = match ip {
+        Some(ip) => format!("{}:{}", ip, container_port),
+        None => {
+            let error = "the container has no IP address".to_string();
+            manager
+                .lock()
+                .await
+                .forward_stopped(local_port, Some(error));
+            return;
+        }
+    };
+    let listener = match TcpListener::bind(("127.0.0.1", local_port)).await {
+        Ok(l) => l,
         Err(e) => {
-            error!("Error stopping container: {}", e);
+            let error = format!("can not listen on port {}: {}", local_port, e);
+            manager
+                .lock()
+                .await
+                .forward_stopped(local_port, Some(error));
+            return;
         }
+    };
+    info!("Forwarding localhost:{} to {}", local_port, target);
+    manager.lock().await.forward_started(PortForward {
+        local_port,
+        container_id,
+        target: target.clone(),
+    });
+    loop {
+        let (mut inbound, _) = match listener.accept().await {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("Error accepting a connection on port {}: {}", local_port, e);
+                continue;
+            }
+        };
+        let target = target.clone();
+        tokio::spawn(async move {
+            match TcpStream::connect(&target).await {
+                Ok(mut outbound) => {
+                    if let Err(e) = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await
+                    {
+                        debug!("Forwarded connection to {} closed: {}", target, e);
+                    }
+                }
+                Err(e) => warn!("Error connecting to {}: {}", target, e),
+            }
+        });
     }
 }
 
-pub async fn pause_container(container_id: String) {
-    let docker = Docker::connect_with_local_defaults().unwrap();
-    match docker.inspect_container(&container_id, None).await {
+/// Name and status of a container, notifying the user when it can not be inspected
+async fn name_and_status(
+    docker: &Docker,
+    container_id: &str,
+    manager: &Arc<Mutex<impl ContainerManagement>>,
+) -> Option<(String, ContainerStateStatusEnum)> {
+    match docker.inspect_container(container_id, None).await {
         Ok(container) => {
+            let name = container
+                .name
+                .map(|n| n.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| container_id.to_string());
             let status = container
                 .state
                 .unwrap_or_default()
                 .status
                 .unwrap_or(ContainerStateStatusEnum::EMPTY);
-            if status == ContainerStateStatusEnum::RUNNING {
-                docker.pause_container(&container_id).await.unwrap();
-            } else if status == ContainerStateStatusEnum::PAUSED {
-                docker.unpause_container(&container_id).await.unwrap();
-            } else {
-                debug!("Container is not running or paused");
+            Some((name, status))
+        }
+        Err(e) => {
+            error!("Error inspecting container {}: {}", container_id, e);
+            manager
+                .lock()
+                .await
+                .notify(Notification::error(format!("{}: {}", container_id, e)));
+            None
+        }
+    }
+}
+
+/// Notify the outcome of an operation, `done` being its past tense, e.g. `Stopped`
+async fn notify_outcome(
+    manager: &Arc<Mutex<impl ContainerManagement>>,
+    name: &str,
+    done: &str,
+    result: Result<(), bollard::errors::Error>,
+) {
+    let notification = match result {
+        Ok(()) => Notification::success(format!("{} {}", done, name)),
+        Err(e) => {
+            error!("Error on container {}: {}", name, e);
+            Notification::error(format!("{}: {}", name, e))
+        }
+    };
+    manager.lock().await.notify(notification);
+}
+
+async fn notify_progress(manager: &Arc<Mutex<impl ContainerManagement>>, message: String) {
+    manager.lock().await.notify(Notification::progress(message));
+}
+
+pub async fn stop_container(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let (name, status) = match name_and_status(&docker, &container_id, &manager).await {
+        Some(c) => c,
+        None => return,
+    };
+    match status {
+        ContainerStateStatusEnum::RUNNING => {
+            notify_progress(&manager, format!("Stopping {}…", name)).await;
+            let result = docker
+                .stop_container(&container_id, Some(StopContainerOptions { t: 10 }))
+                .await;
+            notify_outcome(&manager, &name, "Stopped", result).await;
+        }
+        ContainerStateStatusEnum::EXITED | ContainerStateStatusEnum::CREATED => {
+            notify_progress(&manager, format!("Removing {}…", name)).await;
+            let result = docker
+                .remove_container(
+                    &container_id,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await;
+            notify_outcome(&manager, &name, "Removed", result).await;
+        }
+        _ => {
+            warn!("Container in invalid status: {}", status);
+            manager.lock().await.notify(Notification::error(format!(
+                "Can not stop {}, it is {}",
+                name, status
+            )));
+        }
+    }
+}
+
+pub async fn restart_container(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let (name, _) = match name_and_status(&docker, &container_id, &manager).await {
+        Some(c) => c,
+        None => return,
+    };
+    notify_progress(&manager, format!("Restarting {}…", name)).await;
+    let result = docker
+        .restart_container(&container_id, Some(RestartContainerOptions { t: 10 }))
+        .await;
+    notify_outcome(&manager, &name, "Restarted", result).await;
+}
+
+pub async fn remove_container(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let (name, _) = match name_and_status(&docker, &container_id, &manager).await {
+        Some(c) => c,
+        None => return,
+    };
+    notify_progress(&manager, format!("Removing {}…", name)).await;
+    let result = docker
+        .remove_container(
+            &container_id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await;
+    notify_outcome(&manager, &name, "Removed", result).await;
+}
+
+/// Replace a container by a new one with the same configuration, its
+/// environment changed by `overrides`. The old container is kept aside until
+/// the new one is created so a failure leaves it in place.
+pub async fn recreate_container(
+    docker: Docker,
+    container_id: String,
+    overrides: EnvOverride,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let details = match docker.inspect_container(&container_id, None).await {
+        Ok(d) => d,
+        Err(e) => {
+            report(
+                &manager,
+                format!("Error inspecting container {}: {}", container_id, e),
+            )
+            .await;
+            return;
+        }
+    };
+    let name = details
+        .name
+        .clone()
+        .map(|n| n.trim_start_matches('/').to_string())
+        .unwrap_or_else(|| container_id.clone());
+    let config = details.config.clone().unwrap_or_default();
+    let host_config = details.host_config.clone().unwrap_or_default();
+    let running = details
+        .state
+        .as_ref()
+        .and_then(|s| s.running)
+        .unwrap_or(false);
+    notify_progress(&manager, format!("Recreating {}…", name)).await;
+
+    if running {
+        let stopped = docker
+            .stop_container(&container_id, Some(StopContainerOptions { t: 10 }))
+            .await;
+        if let Err(e) = stopped {
+            notify_outcome(&manager, &name, "Recreated", Err(e)).await;
+            return;
+        }
+    }
+    let aside = format!("{}_bctop_old", name);
+    let renamed = docker
+        .rename_container(
+            &container_id,
+            RenameContainerOptions {
+                name: aside.clone(),
+            },
+        )
+        .await;
+    if let Err(e) = renamed {
+        // Leave the container running as it was
+        if running {
+            if let Err(e) = docker.start_container::<String>(&container_id, None).await {
+                report(&manager, format!("Error starting {} again: {}", name, e)).await;
             }
         }
+        notify_outcome(&manager, &name, "Recreated", Err(e)).await;
+        return;
+    }
+
+    // Only the network of the network mode can be given at creation, the
+    // others are connected afterwards
+    let network_mode = host_config.network_mode.clone().unwrap_or_default();
+    let mut networks: HashMap<String, EndpointSettings> = details
+        .network_settings
+        .and_then(|n| n.networks)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(network, endpoint)| {
+            let aliases = endpoint.aliases.map(|aliases| {
+                aliases
+                    .into_iter()
+                    .filter(|a| !container_id.starts_with(a.as_str()))
+                    .collect()
+            });
+            let endpoint = EndpointSettings {
+                aliases,
+                links: endpoint.links,
+                ipam_config: endpoint.ipam_config,
+                driver_opts: endpoint.driver_opts,
+                ..Default::default()
+            };
+            (network, endpoint)
+        })
+        .collect();
+    let networking_config = networks
+        .remove_entry(&network_mode)
+        .map(|(network, endpoint)| NetworkingConfig {
+            endpoints_config: HashMap::from([(network, endpoint)]),
+        });
+
+    let env = overrides.apply(&config.env.clone().unwrap_or_default());
+    let create = Config {
+        hostname: config.hostname,
+        domainname: config.domainname,
+        user: config.user,
+        attach_stdin: config.attach_stdin,
+        attach_stdout: config.attach_stdout,
+        attach_stderr: config.attach_stderr,
+        exposed_ports: config.exposed_ports,
+        tty: config.tty,
+        open_stdin: config.open_stdin,
+        stdin_once: config.stdin_once,
+        env: Some(env),
+        cmd: config.cmd,
+        healthcheck: config.healthcheck,
+        args_escaped: config.args_escaped,
+        image: config.image,
+        volumes: config.volumes,
+        working_dir: config.working_dir,
+        entrypoint: config.entrypoint,
+        network_disabled: config.network_disabled,
+        mac_address: config.mac_address,
+        on_build: config.on_build,
+        labels: config.labels,
+        stop_signal: config.stop_signal,
+        stop_timeout: config.stop_timeout,
+        shell: config.shell,
+        host_config: Some(host_config),
+        networking_config,
+    };
+    let created = docker
+        .create_container(Some(CreateContainerOptions { name: name.clone() }), create)
+        .await;
+    let new_id = match created {
+        Ok(response) => response.id,
         Err(e) => {
-            error!("Error pausing container: {}", e);
+            // Put the old container back as it was
+            let restored = docker
+                .rename_container(&container_id, RenameContainerOptions { name: name.clone() })
+                .await;
+            if let Err(e) = restored {
+                report(
+                    &manager,
+                    format!("Error renaming {} back to {}: {}", aside, name, e),
+                )
+                .await;
+            } else if running {
+                if let Err(e) = docker.start_container::<String>(&container_id, None).await {
+                    report(&manager, format!("Error starting {} again: {}", name, e)).await;
+                }
+            }
+            notify_outcome(&manager, &name, "Recreated", Err(e)).await;
+            return;
         }
     };
+
+    for (network, endpoint) in networks {
+        let connected = docker
+            .connect_network(
+                &network,
+                ConnectNetworkOptions {
+                    container: new_id.clone(),
+                    endpoint_config: endpoint,
+                },
+            )
+            .await;
+        if let Err(e) = connected {
+            report(
+                &manager,
+                format!("Error connecting {} to {}: {}", name, network, e),
+            )
+            .await;
+        }
+    }
+    if let Err(e) = docker.remove_container(&container_id, None).await {
+        report(&manager, format!("Error removing {}: {}", aside, e)).await;
+    }
+    let result = if running {
+        docker.start_container::<String>(&new_id, None).await
+    } else {
+        Ok(())
+    };
+    manager.lock().await.recreated(container_id, new_id);
+    notify_outcome(&manager, &name, "Recreated", result).await;
+}
+
+pub async fn pause_container(
+    docker: Docker,
+    container_id: String,
+    manager: Arc<Mutex<impl ContainerManagement>>,
+) {
+    let (name, status) = match name_and_status(&docker, &container_id, &manager).await {
+        Some(c) => c,
+        None => return,
+    };
+    if status == ContainerStateStatusEnum::RUNNING {
+        notify_progress(&manager, format!("Pausing {}…", name)).await;
+        let result = docker.pause_container(&container_id).await;
+        notify_outcome(&manager, &name, "Paused", result).await;
+    } else if status == ContainerStateStatusEnum::PAUSED {
+        notify_progress(&manager, format!("Resuming {}…", name)).await;
+        let result = docker.unpause_container(&container_id).await;
+        notify_outcome(&manager, &name, "Resumed", result).await;
+    } else {
+        debug!("Container is not running or paused");
+        manager.lock().await.notify(Notification::error(format!(
+            "Can not pause {}, it is {}",
+            name, status
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map, Value};
+
+    const V1_FIELDS: [&str; 31] = [
+        "cache",
+        "dirty",
+        "mapped_file",
+        "total_inactive_file",
+        "pgpgout",
+        "rss",
+        "total_mapped_file",
+        "writeback",
+        "unevictable",
+        "pgpgin",
+        "total_unevictable",
+        "pgmajfault",
+        "total_rss",
+        "total_rss_huge",
+        "total_writeback",
+        "total_inactive_anon",
+        "rss_huge",
+        "hierarchical_memory_limit",
+        "total_pgfault",
+        "total_active_file",
+        "active_anon",
+        "total_active_anon",
+        "total_pgpgout",
+        "total_cache",
+        "total_dirty",
+        "inactive_anon",
+        "active_file",
+        "pgfault",
+        "inactive_file",
+        "total_pgmajfault",
+        "total_pgpgin",
+    ];
+
+    const V2_FIELDS: [&str; 31] = [
+        "anon",
+        "file",
+        "kernel_stack",
+        "slab",
+        "sock",
+        "shmem",
+        "file_mapped",
+        "file_dirty",
+        "file_writeback",
+        "anon_thp",
+        "inactive_anon",
+        "active_anon",
+        "inactive_file",
+        "active_file",
+        "unevictable",
+        "slab_reclaimable",
+        "slab_unreclaimable",
+        "pgfault",
+        "pgmajfault",
+        "workingset_refault",
+        "workingset_activate",
+        "workingset_nodereclaim",
+        "pgrefill",
+        "pgscan",
+        "pgsteal",
+        "pgactivate",
+        "pgdeactivate",
+        "pglazyfree",
+        "pglazyfreed",
+        "thp_fault_alloc",
+        "thp_collapse_alloc",
+    ];
+
+    /// Memory statistics as the engine sends them, the counters not given
+    /// being zero
+    fn stats(fields: &[&str], counters: &[(&str, u64)], usage: u64, limit: u64) -> MemoryStats {
+        let mut details: Map<String, Value> =
+            fields.iter().map(|f| (f.to_string(), json!(0))).collect();
+        for (name, value) in counters {
+            details.insert(name.to_string(), json!(value));
+        }
+        serde_json::from_value(json!({
+            "stats": details,
+            "usage": usage,
+            "limit": limit,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn v1_hierarchical_counters() {
+        let counters = [
+            ("total_rss", 300),
+            ("rss", 30),
+            ("total_cache", 500),
+            ("cache", 50),
+            ("total_inactive_file", 200),
+            ("inactive_file", 20),
+        ];
+        let breakdown = memory_breakdown(&stats(&V1_FIELDS, &counters, 1000, 0));
+        assert_eq!(breakdown.usage, 1000.0);
+        assert_eq!(breakdown.rss, Some(300.0));
+        assert_eq!(breakdown.cache, Some(500.0));
+        assert_eq!(breakdown.inactive_file, Some(200.0));
+        assert_eq!(breakdown.cgroup, Some(CgroupVersion::V1));
+    }
+
+    #[test]
+    fn v1_without_hierarchical_counters() {
+        let counters = [("rss", 300), ("cache", 500), ("inactive_file", 200)];
+        let breakdown = memory_breakdown(&stats(&V1_FIELDS, &counters, 1000, 0));
+        assert_eq!(breakdown.rss, Some(300.0));
+        assert_eq!(breakdown.cache, Some(500.0));
+        assert_eq!(breakdown.inactive_file, Some(200.0));
+        assert_eq!(breakdown.cgroup, Some(CgroupVersion::V1));
+    }
+
+    #[test]
+    fn v2_anon_and_file() {
+        let counters = [("anon", 300), ("file", 500), ("inactive_file", 200)];
+        let breakdown = memory_breakdown(&stats(&V2_FIELDS, &counters, 1000, 0));
+        assert_eq!(breakdown.usage, 1000.0);
+        assert_eq!(breakdown.rss, Some(300.0));
+        assert_eq!(breakdown.cache, Some(500.0));
+        assert_eq!(breakdown.inactive_file, Some(200.0));
+        assert_eq!(breakdown.cgroup, Some(CgroupVersion::V2));
+    }
+
+    #[test]
+    fn no_limit_is_the_host_memory() {
+        let unlimited = stats(&V2_FIELDS, &[], 1000, 0);
+        assert_eq!(memory_limit(&unlimited, Some(8000.0)), 8000.0);
+        assert_eq!(memory_limit(&unlimited, None), 0.0);
+    }
+
+    #[test]
+    fn limit_above_the_host_memory_is_the_host_memory() {
+        let unlimited = stats(&V2_FIELDS, &[], 1000, u64::MAX);
+        assert_eq!(memory_limit(&unlimited, Some(8000.0)), 8000.0);
+        let limited = stats(&V2_FIELDS, &[], 1000, 4000);
+        assert_eq!(memory_limit(&limited, Some(8000.0)), 4000.0);
+    }
 }
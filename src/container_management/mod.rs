@@ -1,25 +1,192 @@
 mod docker;
+mod probe;
 
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::config::MemoryCalculation;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+pub use bollard::Docker;
 pub use docker::{
-    pause_container, start_management_process, start_monitoring_logs, stop_container,
+    connect, exec_command, export_logs, inspect_container, inspect_image, list_images,
+    list_networks, list_processes, list_volumes, pause_container, recreate_container,
+    remove_container, remove_image, remove_volume, resize_session, restart_container,
+    session_exit_code, start_management_process, start_monitoring_logs, start_port_forward,
+    start_preview_logs, start_session, start_stack_logs, start_tailing_file, stop_container,
+    stream_logs, watch_events, ExecSession,
 };
+pub use probe::start_http_probe;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Container {
     pub id: String,
     pub status: ContainerStatus,
+    /// `None` when the container has no healthcheck or is not running
+    pub health: Option<HealthStatus>,
+    /// Health checks failed in a row, 0 without healthcheck
+    pub health_failing_streak: i64,
     pub name: String,
     pub image: String,
     pub cpu_usage: f32,
+    /// Usage of each core in percent of that core, empty when the engine does
+    /// not report it (cgroup v2)
+    pub per_cpu_usage: Vec<f32>,
+    /// Memory used according to the configured calculation
     pub memory_usage_bytes: f32,
     pub memory_limit_bytes: f32,
+    pub memory: MemoryBreakdown,
+    /// Bytes received per second since the previous refresh
+    pub net_rx_rate: f32,
+    /// Bytes sent per second since the previous refresh
+    pub net_tx_rate: f32,
+    /// Bytes read from block devices per second since the previous refresh
+    pub block_read_rate: f32,
+    /// Bytes written to block devices per second since the previous refresh
+    pub block_write_rate: f32,
+    /// Processes and threads running in the container
+    pub pids: u64,
+    /// Maximum number of pids, `None` when unlimited
+    pub pids_limit: Option<u64>,
+    /// Times the engine restarted the container
+    pub restart_count: i64,
+    /// Platform of the image, e.g. `linux/arm64/v8`
+    pub platform: Option<String>,
+    /// OCI runtime running the container, e.g. `runc`
+    pub runtime: Option<String>,
+    /// Bytes written in the writable layer, `None` until the engine computed it
+    pub size_rw: Option<i64>,
+    /// Bytes of the writable layer and the image together
+    pub size_root_fs: Option<i64>,
     pub swarm_service: Option<String>,
     pub swarm_stack: Option<String>,
     pub compose_service: Option<String>,
     pub compose_project: Option<String>,
+    pub labels: HashMap<String, String>,
+    /// Published ports, only while running
+    pub ports: Vec<PortMapping>,
+    /// Volumes and bind mounts
+    pub mounts: Vec<MountInfo>,
+    /// Start of the current (or last) run, `None` if it never started
+    pub started_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone)]
+impl Container {
+    /// Swarm stack or compose project the container belongs to
+    pub fn stack(&self) -> String {
+        self.swarm_stack
+            .clone()
+            .unwrap_or(self.compose_project.clone().unwrap_or_default())
+    }
+
+    /// Time since the container started, only while it is up
+    pub fn uptime(&self) -> Option<Duration> {
+        self.uptime_at(Utc::now())
+    }
+
+    /// Time from the start of the container to `now`, only while it is up
+    pub fn uptime_at(&self, now: DateTime<Utc>) -> Option<Duration> {
+        match self.status {
+            ContainerStatus::Running | ContainerStatus::Paused => self.started_at.map(|t| now - t),
+            _ => None,
+        }
+    }
+
+    /// Identifies the service of the container across recreations
+    pub fn service_key(&self) -> String {
+        let service = self.service();
+        if service.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}/{}", self.stack(), service)
+        }
+    }
+
+    /// URL of the published HTTP port of the container on `host`, preferring
+    /// the usual web ports over the first published TCP port
+    pub fn probe_url(&self, host: &str, path: &str) -> Option<String> {
+        const WEB_PORTS: [&str; 6] = [
+            "80/tcp", "443/tcp", "8080/tcp", "8443/tcp", "8000/tcp", "3000/tcp",
+        ];
+        let published = self
+            .ports
+            .iter()
+            .filter(|p| p.container_port.ends_with("/tcp") && p.host_port.is_some());
+        let port = published
+            .clone()
+            .min_by_key(|p| {
+                WEB_PORTS
+                    .iter()
+                    .position(|w| *w == p.container_port)
+                    .unwrap_or(WEB_PORTS.len())
+            })
+            .or_else(|| published.clone().next())?;
+        let scheme = if port.container_port.starts_with("443/")
+            || port.container_port.starts_with("8443/")
+        {
+            "https"
+        } else {
+            "http"
+        };
+        let path = path.trim_start_matches('/');
+        Some(format!(
+            "{}://{}:{}/{}",
+            scheme,
+            host,
+            port.host_port.as_ref()?,
+            path
+        ))
+    }
+
+    /// Architecture part of the image platform
+    pub fn arch(&self) -> Option<&str> {
+        self.platform.as_deref().and_then(|p| p.split('/').nth(1))
+    }
+
+    /// Name of the sandbox isolating the container when its runtime is a known one
+    pub fn sandbox(&self) -> Option<&'static str> {
+        let runtime = self.runtime.as_deref()?;
+        if runtime.contains("runsc") || runtime.contains("gvisor") {
+            Some("gVisor")
+        } else if runtime.contains("kata") {
+            Some("Kata")
+        } else if runtime.contains("sysbox") {
+            Some("Sysbox")
+        } else if runtime.contains("firecracker") {
+            Some("Firecracker")
+        } else {
+            None
+        }
+    }
+
+    /// Running and not reported unhealthy
+    pub fn is_up(&self) -> bool {
+        matches!(self.status, ContainerStatus::Running)
+            && self.health != Some(HealthStatus::Unhealthy)
+    }
+
+    /// Service name without the stack prefix
+    pub fn service(&self) -> String {
+        self.swarm_service
+            .clone()
+            .unwrap_or(self.compose_service.clone().unwrap_or_default())
+            .replace(format!("{}_", self.stack()).as_str(), "")
+    }
+
+    /// Service name, or the container name when not run by compose or swarm
+    pub fn display_name(&self) -> String {
+        let service = self.service();
+        if service.is_empty() {
+            self.name.clone()
+        } else {
+            service
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ContainerStatus {
     Created,
     Running,
@@ -31,9 +198,50 @@ pub enum ContainerStatus {
     Dead,
 }
 
+impl ContainerStatus {
+    /// Whether the container has processes, even if paused
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            ContainerStatus::Running | ContainerStatus::Paused | ContainerStatus::Restarting
+        )
+    }
+
+    /// Position when sorting by status, healthy states first
+    pub fn rank(&self) -> u8 {
+        match self {
+            ContainerStatus::Running => 0,
+            ContainerStatus::Restarting => 1,
+            ContainerStatus::Paused => 2,
+            ContainerStatus::Created => 3,
+            ContainerStatus::Removing => 4,
+            ContainerStatus::Stopped => 5,
+            ContainerStatus::Exited => 6,
+            ContainerStatus::Dead => 7,
+        }
+    }
+}
+
+impl Display for ContainerStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            ContainerStatus::Created => "created",
+            ContainerStatus::Running => "running",
+            ContainerStatus::Paused => "paused",
+            ContainerStatus::Stopped => "stopped",
+            ContainerStatus::Restarting => "restarting",
+            ContainerStatus::Removing => "removing",
+            ContainerStatus::Exited => "exited",
+            ContainerStatus::Dead => "dead",
+        };
+        write!(f, "{}", status)
+    }
+}
+
 impl From<String> for ContainerStatus {
     fn from(s: String) -> Self {
-        match s.as_str() {
+        // Not every engine reports the state in lowercase
+        match s.to_lowercase().as_str() {
             "created" => ContainerStatus::Created,
             "running" => ContainerStatus::Running,
             "paused" => ContainerStatus::Paused,
@@ -47,9 +255,633 @@ impl From<String> for ContainerStatus {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthStatus {
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+impl HealthStatus {
+    /// Parse the health suffix docker appends to the status text,
+    /// e.g. `Up 3 minutes (healthy)`
+    pub fn from_status_text(status: &str) -> Option<Self> {
+        if status.contains("(health: starting)") {
+            Some(HealthStatus::Starting)
+        } else if status.contains("(unhealthy)") {
+            Some(HealthStatus::Unhealthy)
+        } else if status.contains("(healthy)") {
+            Some(HealthStatus::Healthy)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            HealthStatus::Starting => "starting",
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Unhealthy => "unhealthy",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Configuration and runtime state of a container, from `inspect`
+#[derive(Debug, Clone, Default)]
+pub struct ContainerDetails {
+    pub id: String,
+    pub created: String,
+    pub command: String,
+    /// Arguments given to the entrypoint, as configured
+    pub cmd: String,
+    pub entrypoint: String,
+    pub working_dir: String,
+    pub user: String,
+    pub hostname: String,
+    pub restart_policy: String,
+    /// `KEY=value` pairs
+    pub env: Vec<String>,
+    pub state: String,
+    pub pid: i64,
+    pub exit_code: i64,
+    pub error: String,
+    pub finished_at: String,
+    pub oom_killed: bool,
+    pub restart_count: i64,
+    pub ports: Vec<PortMapping>,
+    pub mounts: Vec<MountInfo>,
+    pub networks: Vec<NetworkInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortMapping {
+    /// Port and protocol inside the container, e.g. `80/tcp`
+    pub container_port: String,
+    /// `None` when the port is exposed but not published
+    pub host_ip: Option<String>,
+    pub host_port: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountInfo {
+    /// `bind`, `volume`, `tmpfs`...
+    pub kind: String,
+    /// Volume name, `None` for bind mounts
+    pub name: Option<String>,
+    /// Path on the host
+    pub source: String,
+    pub destination: String,
+    pub rw: bool,
+    /// Volume driver, `None` for bind mounts
+    pub driver: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkInfo {
+    pub name: String,
+    pub ip_address: String,
+    pub gateway: String,
+    pub mac_address: String,
+}
+
+/// Process running in a container, from the `top` endpoint
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// `None` when the engine does not report it
+    pub ppid: Option<u32>,
+    /// `ps` state code, e.g. `S`, `R` or `Z` for zombies
+    pub state: String,
+    pub command: String,
+}
+
+impl ProcessInfo {
+    pub fn is_zombie(&self) -> bool {
+        self.state.starts_with('Z')
+    }
+}
+
+/// Outcome of a command run in a container
+#[derive(Debug, Clone)]
+pub struct ExecResult {
+    pub container_id: String,
+    pub command: String,
+    /// Lines written to stdout and stderr, or the error preventing the run
+    pub output: Vec<String>,
+    /// `None` when the command could not be run
+    pub exit_code: Option<i64>,
+}
+
+/// Memory usage reported by the engine and what it is made of, in bytes.
+/// The details are `None` when the engine does not report them.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MemoryBreakdown {
+    pub usage: f32,
+    /// Anonymous memory of the processes
+    pub rss: Option<f32>,
+    /// Page cache, reclaimable under pressure
+    pub cache: Option<f32>,
+    pub inactive_file: Option<f32>,
+    /// Layout of the statistics, `None` when the engine sent none
+    pub cgroup: Option<CgroupVersion>,
+}
+
+impl MemoryBreakdown {
+    /// Usage without the part the calculation leaves out
+    pub fn used(&self, calculation: MemoryCalculation) -> f32 {
+        let excluded = match calculation {
+            MemoryCalculation::Cache => self.cache,
+            MemoryCalculation::DockerStats => self.inactive_file,
+        };
+        match excluded {
+            // Counters are read one after the other, they may not add up
+            Some(excluded) if excluded < self.usage => self.usage - excluded,
+            _ => self.usage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CgroupVersion {
+    V1,
+    V2,
+}
+
+impl Display for CgroupVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CgroupVersion::V1 => write!(f, "cgroup v1"),
+            CgroupVersion::V2 => write!(f, "cgroup v2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    /// An operation started, replaced by its outcome
+    Progress,
+    Success,
+    Error,
+}
+
+/// Outcome of an operation, shown for a few seconds in the status bar
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub message: String,
+}
+
+impl Notification {
+    pub fn progress(message: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Progress,
+            message: message.into(),
+        }
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Success,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Error,
+            message: message.into(),
+        }
+    }
+}
+
+/// Local TCP port proxied to a port of a container
+#[derive(Debug, Clone)]
+pub struct PortForward {
+    pub local_port: u16,
+    pub container_id: String,
+    /// `ip:port` of the container the connections go to
+    pub target: String,
+}
+
+/// Outcome of one request of an HTTP probe
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub at: DateTime<Utc>,
+    /// `None` when no response came back
+    pub status: Option<u16>,
+    pub latency: std::time::Duration,
+    pub error: Option<String>,
+}
+
+impl ProbeResult {
+    /// Answered with a status below 400
+    pub fn is_up(&self) -> bool {
+        self.status.is_some_and(|s| s < 400)
+    }
+}
+
+/// Evidence kept from the last time a container went down unexpectedly
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub at: DateTime<Utc>,
+    /// `None` when the container was already running again when noticed
+    pub exit_code: Option<i64>,
+    pub oom_killed: bool,
+    /// Last lines logged before the crash
+    pub logs: Vec<String>,
+}
+
+/// Image stored by the engine, for the images view
+#[derive(Debug, Clone)]
+pub struct ImageSummary {
+    pub id: String,
+    /// `repository:tag` references, empty for dangling images
+    pub tags: Vec<String>,
+    pub size: i64,
+    pub created: Option<DateTime<Utc>>,
+    /// Containers created from it, `None` when the engine did not count them
+    pub containers: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VolumeSummary {
+    pub name: String,
+    pub driver: String,
+    /// Path on the host
+    pub mountpoint: String,
+    pub created: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkSummary {
+    pub id: String,
+    pub name: String,
+    pub driver: String,
+    /// `local`, `global` or `swarm`
+    pub scope: String,
+    pub subnets: Vec<String>,
+    /// Without access to the outside
+    pub internal: bool,
+}
+
+/// Something that happened on the engine, e.g. a container that died
+#[derive(Debug, Clone)]
+pub struct EngineEvent {
+    pub at: DateTime<Utc>,
+    /// `container`, `image`, `volume`, `network`...
+    pub kind: String,
+    /// `start`, `die`, `pull`...
+    pub action: String,
+    /// Name of the object, or its id when it has none
+    pub actor: String,
+}
+
+/// Where the image of a container comes from
+#[derive(Debug, Clone)]
+pub struct ImageDetails {
+    /// Image as referenced by the container, e.g. `nginx:latest`
+    pub reference: String,
+    pub id: String,
+    /// Repository digests of the image, empty for locally built images
+    pub digests: Vec<String>,
+    pub registry: String,
+    /// Labels of the image, OCI annotations among them
+    pub labels: HashMap<String, String>,
+    /// Configuration containers of the image start with
+    pub defaults: ImageDefaults,
+}
+
+/// Runtime configuration set by an image, which `docker run` can override
+#[derive(Debug, Clone, Default)]
+pub struct ImageDefaults {
+    pub cmd: String,
+    pub entrypoint: String,
+    pub working_dir: String,
+    pub user: String,
+    /// `KEY=value` pairs
+    pub env: Vec<String>,
+}
+
+impl ImageDefaults {
+    /// Value of a variable in the image, `None` when it does not set it
+    pub fn env_value(&self, key: &str) -> Option<&str> {
+        self.env
+            .iter()
+            .find_map(|v| v.strip_prefix(key)?.strip_prefix('='))
+    }
+}
+
+/// What the engine tells about itself and its host, shown at startup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EngineSummary {
+    pub version: String,
+    pub os: String,
+    pub cpus: i64,
+    /// Memory of the host in bytes
+    pub memory: i64,
+    pub running: i64,
+    pub paused: i64,
+    pub stopped: i64,
+}
+
+/// Line of the logs of a container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLine {
+    pub text: String,
+    /// Written to stderr rather than stdout. Containers with a TTY only have
+    /// one stream, reported as stdout.
+    pub stderr: bool,
+    /// Time the engine received it, `None` when it was not asked for
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Period of the logs to fetch, as unix timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRange {
+    pub since: i64,
+    /// Followed as they come when unset
+    pub until: Option<i64>,
+}
+
+impl LogRange {
+    /// Parse a relative range like `last 15m` or `2h`, or an absolute one in
+    /// local time like `2024-05-01 10:00 to 11:00`
+    pub fn parse(text: &str, now: DateTime<Local>) -> Result<Self, String> {
+        let text = text.trim();
+        let relative = text.strip_prefix("last").unwrap_or(text).trim();
+        if let Some(duration) = parse_duration(relative) {
+            return Ok(Self {
+                since: (now - duration).timestamp(),
+                until: None,
+            });
+        }
+        let (start, end) = match text.split_once(" to ") {
+            Some((start, end)) => (start.trim(), Some(end.trim())),
+            None => (text, None),
+        };
+        let start = parse_local_time(start, None)
+            .ok_or_else(|| format!("invalid start {}, expected YYYY-MM-DD HH:MM", start))?;
+        let until = match end {
+            Some(end) => {
+                let end = parse_local_time(end, Some(start.date_naive()))
+                    .ok_or_else(|| format!("invalid end {}, expected HH:MM", end))?;
+                if end <= start {
+                    return Err("the end is before the start".to_string());
+                }
+                Some(end.timestamp())
+            }
+            None => None,
+        };
+        Ok(Self {
+            since: start.timestamp(),
+            until,
+        })
+    }
+}
+
+impl Display for LogRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let time = |t: i64| match Local.timestamp_opt(t, 0).single() {
+            Some(t) => t.format("%Y-%m-%d %H:%M").to_string(),
+            None => t.to_string(),
+        };
+        match self.until {
+            Some(until) => write!(f, "{} to {}", time(self.since), time(until)),
+            None => write!(f, "since {}", time(self.since)),
+        }
+    }
+}
+
+/// `30s`, `15m`, `2h` or `1d`
+fn parse_duration(text: &str) -> Option<Duration> {
+    let unit = text.chars().last()?;
+    let value: i64 = text[..text.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        's' => Some(Duration::seconds(value)),
+        'm' => Some(Duration::minutes(value)),
+        'h' => Some(Duration::hours(value)),
+        'd' => Some(Duration::days(value)),
+        _ => None,
+    }
+}
+
+/// Date and time, or only a time on `day` when given
+fn parse_local_time(text: &str, day: Option<NaiveDate>) -> Option<DateTime<Local>> {
+    let parsed = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+            date.and_hms_opt(0, 0, 0)
+        })
+        .or_else(|| {
+            let time = ["%H:%M:%S", "%H:%M"]
+                .iter()
+                .find_map(|format| NaiveTime::parse_from_str(text, format).ok())?;
+            Some(day?.and_time(time))
+        })?;
+    Local.from_local_datetime(&parsed).earliest()
+}
+
+impl ImageDetails {
+    /// Registry host of an image reference, Docker Hub when there is none
+    pub fn registry_of(reference: &str) -> String {
+        match reference.split_once('/') {
+            Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+                host.to_string()
+            }
+            _ => "docker.io".to_string(),
+        }
+    }
+
+    /// Repository the image was built from, pointing at the commit when the
+    /// revision is known and the host has commit pages
+    pub fn source_url(&self) -> Option<String> {
+        let source = self
+            .labels
+            .get("org.opencontainers.image.source")
+            .or_else(|| self.labels.get("org.opencontainers.image.url"))?;
+        let source = source.trim_end_matches('/').trim_end_matches(".git");
+        match self.labels.get("org.opencontainers.image.revision") {
+            Some(revision)
+                if source.starts_with("https://github.com/")
+                    || source.starts_with("https://gitlab.com/") =>
+            {
+                Some(format!("{}/commit/{}", source, revision))
+            }
+            _ => Some(source.to_string()),
+        }
+    }
+}
+
 pub trait ContainerManagement {
     fn remove_container(&mut self, id: &str);
     fn update_containers(&mut self, new_container: Container);
-    fn add_logs(&mut self, logs: Vec<String>);
+    fn add_logs(&mut self, logs: Vec<LogLine>);
+    /// New lines of the container shown in the split view
+    fn add_preview_logs(&mut self, container_id: String, logs: Vec<String>);
+    /// Every log line of a container, fetched to be written to `path`
+    fn logs_to_export(&mut self, path: String, logs: Vec<String>);
     fn add_tty_output(&mut self, output: String);
+    fn update_image_details(&mut self, details: ImageDetails);
+    fn update_container_details(&mut self, details: ContainerDetails);
+    fn set_engine_host(&mut self, host: String);
+    /// Architecture of the engine host, named like image platforms (`amd64`, `arm64`...)
+    fn set_engine_arch(&mut self, arch: String);
+    fn set_engine_summary(&mut self, summary: EngineSummary);
+    fn exec_finished(&mut self, result: ExecResult);
+    fn update_processes(&mut self, container_id: String, processes: Vec<ProcessInfo>);
+    fn record_crash(&mut self, container_id: String, crash: CrashReport);
+    fn forward_started(&mut self, forward: PortForward);
+    /// A forward ended, `error` telling why when it was not asked for
+    fn forward_stopped(&mut self, local_port: u16, error: Option<String>);
+    /// Show the progress or the outcome of an operation to the user
+    fn notify(&mut self, notification: Notification);
+    /// Keep an error for the error panel
+    fn report_error(&mut self, message: String);
+    /// Connecting to the engine of another profile failed, the previous
+    /// engine staying in use
+    fn connection_failed(&mut self, error: String);
+    /// A container was replaced by a new one with its pending env overrides
+    fn recreated(&mut self, old_id: String, new_id: String);
+    fn update_images(&mut self, images: Vec<ImageSummary>);
+    fn update_volumes(&mut self, volumes: Vec<VolumeSummary>);
+    fn update_networks(&mut self, networks: Vec<NetworkSummary>);
+    fn add_engine_event(&mut self, event: EngineEvent);
+    /// A request of the HTTP probe of a service completed
+    fn probe_result(&mut self, service_key: String, result: ProbeResult);
+}
+
+#[cfg(test)]
+impl Container {
+    /// Running container named `name` without any usage, for the tests
+    pub(crate) fn named(name: &str) -> Self {
+        Self {
+            id: format!("{}-id", name),
+            status: ContainerStatus::Running,
+            health: None,
+            health_failing_streak: 0,
+            name: name.to_string(),
+            image: "busybox".to_string(),
+            cpu_usage: 0.0,
+            per_cpu_usage: Vec::new(),
+            memory_usage_bytes: 0.0,
+            memory_limit_bytes: 0.0,
+            memory: MemoryBreakdown::default(),
+            net_rx_rate: 0.0,
+            net_tx_rate: 0.0,
+            block_read_rate: 0.0,
+            block_write_rate: 0.0,
+            pids: 0,
+            pids_limit: None,
+            restart_count: 0,
+            platform: None,
+            runtime: None,
+            size_rw: None,
+            size_root_fs: None,
+            swarm_service: None,
+            swarm_stack: None,
+            compose_service: None,
+            compose_project: None,
+            labels: HashMap::new(),
+            ports: Vec::new(),
+            mounts: Vec::new(),
+            started_at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breakdown(usage: f32, cache: f32, inactive_file: f32) -> MemoryBreakdown {
+        MemoryBreakdown {
+            usage,
+            rss: None,
+            cache: Some(cache),
+            inactive_file: Some(inactive_file),
+            cgroup: Some(CgroupVersion::V2),
+        }
+    }
+
+    #[test]
+    fn used_leaves_the_excluded_memory_out() {
+        let memory = breakdown(1000.0, 500.0, 200.0);
+        assert_eq!(memory.used(MemoryCalculation::Cache), 500.0);
+        assert_eq!(memory.used(MemoryCalculation::DockerStats), 800.0);
+    }
+
+    #[test]
+    fn used_keeps_the_usage_when_the_excluded_memory_is_larger() {
+        let memory = breakdown(1000.0, 1500.0, 1200.0);
+        assert_eq!(memory.used(MemoryCalculation::Cache), 1000.0);
+        assert_eq!(memory.used(MemoryCalculation::DockerStats), 1000.0);
+    }
+
+    #[test]
+    fn used_without_details_is_the_usage() {
+        let memory = MemoryBreakdown {
+            usage: 1000.0,
+            ..Default::default()
+        };
+        assert_eq!(memory.used(MemoryCalculation::Cache), 1000.0);
+    }
+
+    #[test]
+    fn registry_of_docker_hub_images() {
+        assert_eq!(ImageDetails::registry_of("nginx:latest"), "docker.io");
+        assert_eq!(ImageDetails::registry_of("bitnami/redis:7"), "docker.io");
+    }
+
+    #[test]
+    fn registry_of_images_with_a_host() {
+        assert_eq!(ImageDetails::registry_of("ghcr.io/org/app:1.2"), "ghcr.io");
+        assert_eq!(
+            ImageDetails::registry_of("registry.local:5000/app"),
+            "registry.local:5000"
+        );
+        assert_eq!(ImageDetails::registry_of("localhost/app"), "localhost");
+    }
+
+    fn local(text: &str) -> DateTime<Local> {
+        let time = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&time).earliest().unwrap()
+    }
+
+    #[test]
+    fn log_range_relative() {
+        let now = local("2024-05-01 12:00");
+        let range = LogRange::parse("last 15m", now).unwrap();
+        assert_eq!(range.since, local("2024-05-01 11:45").timestamp());
+        assert_eq!(range.until, None);
+        let range = LogRange::parse("2h", now).unwrap();
+        assert_eq!(range.since, local("2024-05-01 10:00").timestamp());
+        let range = LogRange::parse("1d", now).unwrap();
+        assert_eq!(range.since, local("2024-04-30 12:00").timestamp());
+    }
+
+    #[test]
+    fn log_range_absolute() {
+        let now = local("2024-05-02 12:00");
+        let range = LogRange::parse("2024-05-01 10:00 to 11:30", now).unwrap();
+        assert_eq!(range.since, local("2024-05-01 10:00").timestamp());
+        assert_eq!(range.until, Some(local("2024-05-01 11:30").timestamp()));
+        let range = LogRange::parse("2024-05-01", now).unwrap();
+        assert_eq!(range.since, local("2024-05-01 00:00").timestamp());
+        assert_eq!(range.until, None);
+    }
+
+    #[test]
+    fn log_range_errors() {
+        let now = local("2024-05-02 12:00");
+        assert!(LogRange::parse("2024-05-01 10:00 to 09:00", now).is_err());
+        assert!(LogRange::parse("yesterday", now).is_err());
+        assert!(LogRange::parse("2024-05-01 10:00 to later", now).is_err());
+        assert!(LogRange::parse("15x", now).is_err());
+    }
 }
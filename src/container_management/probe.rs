@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use log::{debug, info};
+use tokio::sync::Mutex;
+
+use super::{ContainerManagement, ProbeResult};
+
+/// Requests slower than this count as failed
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request `url` every `interval` and report the status and latency under
+/// `key`. Runs until the task is aborted.
+pub async fn start_http_probe(
+    key: String,
+    url: String,
+    interval: Duration,
+    manager: Arc<Mutex<impl ContainerManagement + std::marker::Send + 'static>>,
+) {
+    info!("Probing {} every {:?}", url, interval);
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(e) => {
+            let result = ProbeResult {
+                at: Utc::now(),
+                status: None,
+                latency: Duration::ZERO,
+                error: Some(e.to_string()),
+            };
+            manager.lock().await.probe_result(key, result);
+            return;
+        }
+    };
+    let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+    loop {
+        ticker.tick().await;
+        let started = Instant::now();
+        let response = client.get(&url).send().await;
+        let latency = started.elapsed();
+        let result = match response {
+            Ok(r) => ProbeResult {
+                at: Utc::now(),
+                status: Some(r.status().as_u16()),
+                latency,
+                error: None,
+            },
+            Err(e) => {
+                debug!("Probe of {} failed: {}", url, e);
+                ProbeResult {
+                    at: Utc::now(),
+                    status: None,
+                    latency,
+                    error: Some(if e.is_timeout() {
+                        "timeout".to_string()
+                    } else if e.is_connect() {
+                        "connection refused".to_string()
+                    } else {
+                        e.to_string()
+                    }),
+                }
+            }
+        };
+        manager.lock().await.probe_result(key.clone(), result);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use eyre::Result;
+use log::{info, warn};
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::app::App;
+use crate::config::DaemonConfig;
+use crate::io::IoEvent;
+
+/// Time between two evaluations of the alerts
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run the backend only: follow the containers and report the alerts raised
+/// and cleared, along with the engine events, until interrupted.
+pub async fn start_daemon(app: &Arc<Mutex<App>>) -> Result<()> {
+    let sink = {
+        let mut app = app.lock().await;
+        app.dispatch(IoEvent::StartMonitoring).await;
+        app.dispatch(IoEvent::WatchEvents).await;
+        Sink::new(app.config().daemon.clone())
+    };
+    info!("Daemon started");
+
+    let mut tick = tokio::time::interval(CHECK_INTERVAL);
+    let mut reporter = Reporter::default();
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {
+                let events = {
+                    let mut app = app.lock().await;
+                    app.update_on_tick().await;
+                    reporter.events(&app)
+                };
+                for event in events {
+                    sink.send(event).await;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    info!("Daemon stopped");
+    Ok(())
+}
+
+/// What was already reported, to only send what changed
+#[derive(Debug, Default)]
+struct Reporter {
+    /// Name and reason of the containers in alert, by id
+    alerts: HashMap<String, (String, String)>,
+    /// Engine events received so far
+    engine_events: usize,
+}
+
+impl Reporter {
+    fn events(&mut self, app: &App) -> Vec<Value> {
+        let now = Utc::now().to_rfc3339();
+        let mut events = Vec::new();
+        let mut alerts = HashMap::new();
+        for c in app.containers() {
+            if let Some(reason) = app.alert(c) {
+                if self.alerts.get(&c.id).map(|(_, r)| r) != Some(&reason) {
+                    events.push(json!({
+                        "at": now,
+                        "kind": "alert",
+                        "container": c.name,
+                        "id": c.id,
+                        "message": reason,
+                    }));
+                }
+                alerts.insert(c.id.clone(), (c.name.clone(), reason));
+            }
+        }
+        for (id, (name, reason)) in &self.alerts {
+            if !alerts.contains_key(id) {
+                events.push(json!({
+                    "at": now,
+                    "kind": "resolved",
+                    "container": name,
+                    "id": id,
+                    "message": reason,
+                }));
+            }
+        }
+        self.alerts = alerts;
+
+        // Older events may have been dropped from the list already
+        let total = app.engine_events_total();
+        let recent = app.engine_events();
+        let new = (total - self.engine_events).min(recent.len());
+        events.extend(recent.iter().skip(recent.len() - new).map(|e| {
+            json!({
+                "at": e.at.to_rfc3339(),
+                "kind": "event",
+                "type": e.kind,
+                "action": e.action,
+                "actor": e.actor,
+            })
+        }));
+        self.engine_events = total;
+        events
+    }
+}
+
+/// Where the events go: a webhook, a file of JSON lines, or the standard output
+/// when neither is configured
+struct Sink {
+    config: DaemonConfig,
+    client: reqwest::Client,
+}
+
+impl Sink {
+    fn new(config: DaemonConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn send(&self, event: Value) {
+        if self.config.webhook.is_none() && self.config.file.is_none() {
+            println!("{}", event);
+            return;
+        }
+        if let Some(path) = &self.config.file {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut f| writeln!(f, "{}", event));
+            if let Err(e) = written {
+                warn!("Error writing event to {}: {}", path.display(), e);
+            }
+        }
+        if let Some(url) = &self.config.webhook {
+            let sent = self
+                .client
+                .post(url)
+                .json(&event)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status());
+            if let Err(e) = sent {
+                warn!("Error sending event to {}: {}", url, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{BTreeMap, HashMap};
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::store;
+
+/// Changes to the environment of a service, applied when bctop recreates its
+/// container
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvOverride {
+    /// Variables added or replaced
+    pub set: BTreeMap<String, String>,
+    /// Variables removed
+    pub unset: Vec<String>,
+}
+
+impl EnvOverride {
+    /// Parse the text of the editor, one `KEY=value` or `-KEY` per line
+    pub fn parse(text: &str) -> Self {
+        let mut overrides = Self::default();
+        for line in text.lines().map(str::trim) {
+            if let Some(key) = line.strip_prefix('-') {
+                if !key.is_empty() {
+                    overrides.unset.push(key.to_string());
+                }
+            } else if let Some((key, value)) = line.split_once('=') {
+                if !key.is_empty() {
+                    overrides.set.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+        overrides
+    }
+
+    /// Text shown in the editor, the reverse of `parse`
+    pub fn to_text(&self) -> String {
+        self.set
+            .iter()
+            .map(|(k, v)| format!("{}={}\n", k, v))
+            .chain(self.unset.iter().map(|k| format!("-{}\n", k)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty()
+    }
+
+    /// Environment of the recreated container, from the `KEY=value` entries
+    /// of the current one
+    pub fn apply(&self, env: &[String]) -> Vec<String> {
+        let mut result: Vec<String> = env
+            .iter()
+            .filter(|v| {
+                let key = v.split_once('=').map_or(v.as_str(), |(k, _)| k);
+                !self.unset.iter().any(|k| k == key) && !self.set.contains_key(key)
+            })
+            .cloned()
+            .collect();
+        result.extend(self.set.iter().map(|(k, v)| format!("{}={}", k, v)));
+        result
+    }
+}
+
+/// Pending environment changes keyed by service name, kept until the
+/// container is recreated. Stored in `<data dir>/bctop/env_overrides.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvOverrides(HashMap<String, EnvOverride>);
+
+impl EnvOverrides {
+    /// Load the saved overrides, starting empty when there are none or the file
+    /// can not be parsed.
+    pub fn load() -> Self {
+        store::load("env_overrides.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save("env_overrides.json", self)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&EnvOverride> {
+        self.0.get(key)
+    }
+
+    /// Replace the overrides of a service, removing them when empty
+    pub fn set(&mut self, key: &str, overrides: EnvOverride) {
+        if overrides.is_empty() {
+            self.0.remove(key);
+        } else {
+            self.0.insert(key.to_string(), overrides);
+        }
+    }
+}
@@ -15,6 +15,8 @@ pub struct Events {
     _tx: tokio::sync::mpsc::Sender<InputEvent>,
     // To stop the loop
     stop_capture: Arc<AtomicBool>,
+    // To leave the terminal to another program for a while
+    paused: Arc<AtomicBool>,
 }
 
 impl Events {
@@ -22,11 +24,17 @@ impl Events {
     pub fn new(tick_rate: Duration) -> Events {
         let (tx, rx) = tokio::sync::mpsc::channel(100);
         let stop_capture = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
 
         let event_tx = tx.clone();
         let event_stop_capture = stop_capture.clone();
+        let event_paused = paused.clone();
         tokio::spawn(async move {
             loop {
+                if event_paused.load(Ordering::Relaxed) {
+                    tokio::time::sleep(tick_rate).await;
+                    continue;
+                }
                 // poll for tick rate duration, if no event, sent tick event.
                 if crossterm::event::poll(tick_rate).unwrap() {
                     match crossterm::event::read().unwrap() {
@@ -37,8 +45,17 @@ impl Events {
                             }
                         }
                         crossterm::event::Event::Mouse(mouse) => {
-                            let key = Key::from(mouse);
-                            if let Err(_err) = event_tx.send(InputEvent::Input(key)).await {
+                            let event = match mouse.kind {
+                                crossterm::event::MouseEventKind::Down(
+                                    crossterm::event::MouseButton::Left,
+                                ) => InputEvent::Click {
+                                    column: mouse.column,
+                                    row: mouse.row,
+                                },
+                                // The wheel scrolls like the arrow keys
+                                _ => InputEvent::Input(Key::from(mouse)),
+                            };
+                            if let Err(_err) = event_tx.send(event).await {
                                 // error!("Oops!, {}", err);
                             }
                         }
@@ -58,6 +75,7 @@ impl Events {
             rx,
             _tx: tx,
             stop_capture,
+            paused,
         }
     }
 
@@ -70,4 +88,14 @@ impl Events {
     pub fn close(&mut self) {
         self.stop_capture.store(true, Ordering::Relaxed)
     }
+
+    /// Stop reading the terminal, the keys going to the program using it
+    /// meanwhile. A read already waiting ends within the tick rate.
+    pub fn pause(&mut self) {
+        self.paused.store(true, Ordering::Relaxed)
+    }
+
+    pub fn resume(&mut self) {
+        self.paused.store(false, Ordering::Relaxed)
+    }
 }
@@ -1,4 +1,5 @@
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
 use crossterm::event;
 
@@ -15,6 +16,8 @@ pub enum Key {
     Right,
     Up,
     Down,
+    CtrlUp,
+    CtrlDown,
 
     Ins,
     Delete,
@@ -85,6 +88,8 @@ impl Display for Key {
             Key::Alt(' ') => write!(f, "<Alt+Space>"),
             Key::Ctrl(' ') => write!(f, "<Ctrl+Space>"),
             Key::Char(' ') => write!(f, "<Space>"),
+            Key::CtrlUp => write!(f, "<Ctrl+Up>"),
+            Key::CtrlDown => write!(f, "<Ctrl+Down>"),
             Key::Alt(c) => write!(f, "<Alt+{}>", c),
             Key::Ctrl(c) => write!(f, "<Ctrl+{}>", c),
             Key::Char(c) => write!(f, "<{}>", c),
@@ -93,6 +98,58 @@ impl Display for Key {
     }
 }
 
+/// Parse keys written in the configuration, e.g. `ctrl+a`, `alt+x`, `f1`,
+/// `esc` or a single character
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        let single = |rest: &str| {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(format!("Unknown key: {}", s)),
+            }
+        };
+        match lower.as_str() {
+            "ctrl+up" => return Ok(Key::CtrlUp),
+            "ctrl+down" => return Ok(Key::CtrlDown),
+            _ => {}
+        }
+        if let Some(rest) = lower.strip_prefix("ctrl+") {
+            return single(rest).map(Key::Ctrl);
+        }
+        if let Some(rest) = lower.strip_prefix("alt+") {
+            return single(rest).map(Key::Alt);
+        }
+        let key = match lower.as_str() {
+            "enter" => Key::Enter,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            "esc" => Key::Esc,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "insert" => Key::Ins,
+            "delete" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "space" => Key::Char(' '),
+            f if f.starts_with('f') && f.len() > 1 => match f[1..].parse::<u8>() {
+                Ok(n) if n <= 12 => Key::from_f(n),
+                _ => return Err(format!("Unknown key: {}", s)),
+            },
+            // Characters keep their case, `G` is not `g`
+            _ => Key::Char(single(s)?),
+        };
+        Ok(key)
+    }
+}
+
 impl From<event::KeyEvent> for Key {
     fn from(key_event: event::KeyEvent) -> Self {
         match key_event {
@@ -112,6 +169,16 @@ impl From<event::KeyEvent> for Key {
                 code: event::KeyCode::Right,
                 ..
             } => Key::Right,
+            event::KeyEvent {
+                code: event::KeyCode::Up,
+                modifiers: event::KeyModifiers::CONTROL,
+                ..
+            } => Key::CtrlUp,
+            event::KeyEvent {
+                code: event::KeyCode::Down,
+                modifiers: event::KeyModifiers::CONTROL,
+                ..
+            } => Key::CtrlDown,
             event::KeyEvent {
                 code: event::KeyCode::Up,
                 ..
@@ -191,10 +258,6 @@ impl From<event::MouseEvent> for Key {
                 kind: event::MouseEventKind::ScrollUp,
                 ..
             } => Key::Up,
-            event::MouseEvent {
-                kind: event::MouseEventKind::Down(event::MouseButton::Left),
-                ..
-            } => Key::Up,
             _ => Key::Unknown,
         }
     }
@@ -5,5 +5,10 @@ use self::key::Key;
 
 pub enum InputEvent {
     Input(Key),
+    /// Left click at a cell of the terminal
+    Click {
+        column: u16,
+        row: u16,
+    },
     Tick,
 }
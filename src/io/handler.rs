@@ -1,69 +1,211 @@
-use eyre::Result;
-use log::{error, info};
+use eyre::{bail, Result};
+use log::{debug, error, info};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
 use super::IoEvent;
 
+use crate::agent::{AgentClient, Operation, Request, PREVIEW_LOG_LINES};
 use crate::app::App;
+use crate::config::EngineProfile;
 use crate::container_management::{
-    pause_container, start_management_process, start_monitoring_logs, stop_container,
+    connect, exec_command, export_logs, inspect_container, inspect_image, list_images,
+    list_networks, list_processes, list_volumes, pause_container, recreate_container,
+    remove_container, remove_image, remove_volume, restart_container, start_http_probe,
+    start_management_process, start_monitoring_logs, start_port_forward, start_preview_logs,
+    start_stack_logs, start_tailing_file, stop_container, watch_events, ContainerManagement,
+    Docker, LogRange,
 };
+use crate::telemetry;
 
 pub struct IoAsyncHandler {
     app: Arc<Mutex<App>>,
+    docker: Docker,
     active_task: Option<JoinHandle<()>>,
+    /// Port forwards by local port, living alongside the active task
+    forwards: HashMap<u16, JoinHandle<()>>,
+    /// HTTP probes by service key
+    probes: HashMap<String, JoinHandle<()>>,
+    /// Events of the engine, followed once the events view was opened
+    events: Option<JoinHandle<()>>,
+    /// Logs of the container selected in the split view
+    preview: Option<JoinHandle<()>>,
+    /// Agent the events go to instead of the engine, with a `bctop://` profile
+    agent: Option<AgentClient>,
 }
 
 impl IoAsyncHandler {
-    pub fn new(app: Arc<tokio::sync::Mutex<App>>) -> Self {
+    pub fn new(
+        app: Arc<tokio::sync::Mutex<App>>,
+        docker: Docker,
+        profile: Option<&EngineProfile>,
+    ) -> Self {
+        let agent = profile.and_then(|p| AgentClient::connect(p, Arc::clone(&app)));
         Self {
             app,
+            docker,
             active_task: None,
+            forwards: HashMap::new(),
+            probes: HashMap::new(),
+            events: None,
+            preview: None,
+            agent,
         }
     }
 
     /// We could be async here
     pub async fn handle_io_event(&mut self, io_event: IoEvent) {
+        let started = SystemTime::now();
+        let name = io_event.name();
         let result = match io_event {
+            IoEvent::Connect(profile) => self.connect(profile).await,
+            io_event if self.agent.is_some() => self.forward_to_agent(io_event).await,
             IoEvent::StartMonitoring => self.start_management().await,
-            IoEvent::ShowLogs(container_id) => self.start_logs_monitoring(container_id).await,
+            IoEvent::ShowLogs {
+                container,
+                range,
+                tail,
+            } => self.start_logs_monitoring(container, range, tail).await,
+            IoEvent::ShowStackLogs { containers, tail } => {
+                self.start_stack_logs(containers, tail).await
+            }
+            IoEvent::ExportLogs { container, path } => self.export_logs(container, path).await,
+            IoEvent::PreviewLogs(container_id) => self.preview_logs(container_id).await,
+            IoEvent::TailFile { container, path } => self.start_file_tail(container, path).await,
             IoEvent::StopContainer(container_id) => self.stop_container(container_id).await,
             IoEvent::PauseContainer(container_id) => self.pause_container(container_id).await,
+            IoEvent::RestartContainer(container_id) => self.restart_container(container_id).await,
+            IoEvent::RemoveContainer(container_id) => self.remove_container(container_id).await,
+            IoEvent::RemoveImage(image_id) => self.remove_image(image_id).await,
+            IoEvent::RemoveVolume(name) => self.remove_volume(name).await,
+            IoEvent::RecreateContainer(container_id) => self.recreate_container(container_id).await,
+            IoEvent::InspectContainer(container_id) => self.inspect_container(container_id).await,
+            IoEvent::ListProcesses(container_id) => self.list_processes(container_id).await,
+            IoEvent::InspectImage(image) => self.inspect_image(image).await,
+            IoEvent::Exec { container, command } => self.exec(container, command).await,
+            IoEvent::StartForward {
+                container,
+                local_port,
+                container_port,
+            } => {
+                self.start_forward(container, local_port, container_port)
+                    .await
+            }
+            IoEvent::StopForward(local_port) => self.stop_forward(local_port).await,
+            IoEvent::StartProbe {
+                service_key,
+                url,
+                interval,
+            } => self.start_probe(service_key, url, interval).await,
+            IoEvent::StopProbe(service_key) => self.stop_probe(service_key).await,
+            IoEvent::ListImages => self.list_images().await,
+            IoEvent::ListVolumes => self.list_volumes().await,
+            IoEvent::ListNetworks => self.list_networks().await,
+            IoEvent::WatchEvents => self.watch_events().await,
         };
 
+        telemetry::record(
+            "io_event",
+            started,
+            result.as_ref().err().map(|e| e.to_string()),
+            &[("event", name)],
+        );
         if let Err(err) = result {
             error!("Oops, something wrong happen: {:?}", err);
+            self.app
+                .lock()
+                .await
+                .report_error(format!("{} failed: {}", name, err));
         }
     }
 
     async fn abort_current_task(&mut self) {
         if let Some(task) = self.active_task.take() {
             task.abort();
-            match task.await {
-                Ok(_) => return,
-                Err(_) => return,
-            };
+            let _ = task.await;
         }
     }
 
     async fn start_management(&mut self) -> Result<()> {
         self.abort_current_task().await;
+        let docker = self.docker.clone();
         let app = Arc::clone(&self.app);
         let t = tokio::spawn(async move {
-            start_management_process(app).await;
+            start_management_process(docker, app).await;
         });
         self.active_task = Some(t);
         Ok(())
     }
 
-    async fn start_logs_monitoring(&mut self, container_id: String) -> Result<()> {
+    async fn start_logs_monitoring(
+        &mut self,
+        container_id: String,
+        range: Option<LogRange>,
+        tail: Option<usize>,
+    ) -> Result<()> {
         self.abort_current_task().await;
         info!("Start monitoring logs for container: {}", container_id);
+        let docker = self.docker.clone();
         let app = Arc::clone(&self.app);
         let t = tokio::spawn(async move {
-            start_monitoring_logs(container_id, app).await;
+            start_monitoring_logs(docker, container_id, range, tail, app).await;
+        });
+        self.active_task = Some(t);
+        Ok(())
+    }
+
+    async fn start_stack_logs(
+        &mut self,
+        containers: Vec<(String, String)>,
+        tail: Option<usize>,
+    ) -> Result<()> {
+        self.abort_current_task().await;
+        info!("Start monitoring logs of {} containers", containers.len());
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        let t = tokio::spawn(async move {
+            start_stack_logs(docker, containers, tail, app).await;
+        });
+        self.active_task = Some(t);
+        Ok(())
+    }
+
+    async fn export_logs(&mut self, container_id: String, path: String) -> Result<()> {
+        info!("Export logs of container {} to {}", container_id, path);
+        // Long logs take a while to fetch, keep handling events meanwhile
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        tokio::spawn(async move {
+            export_logs(docker, container_id, path, app).await;
+        });
+        Ok(())
+    }
+
+    async fn preview_logs(&mut self, container_id: Option<String>) -> Result<()> {
+        if let Some(task) = self.preview.take() {
+            task.abort();
+        }
+        if let Some(container_id) = container_id {
+            debug!("Preview logs of container: {}", container_id);
+            let docker = self.docker.clone();
+            let app = Arc::clone(&self.app);
+            self.preview = Some(tokio::spawn(async move {
+                start_preview_logs(docker, container_id, app).await;
+            }));
+        }
+        Ok(())
+    }
+
+    async fn start_file_tail(&mut self, container_id: String, path: String) -> Result<()> {
+        self.abort_current_task().await;
+        info!("Start following {} in container: {}", path, container_id);
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        let t = tokio::spawn(async move {
+            start_tailing_file(docker, container_id, path, app).await;
         });
         self.active_task = Some(t);
         Ok(())
@@ -71,13 +213,264 @@ impl IoAsyncHandler {
 
     async fn stop_container(&mut self, container_id: String) -> Result<()> {
         info!("Stop container: {}", container_id);
-        stop_container(container_id).await;
+        stop_container(self.docker.clone(), container_id, Arc::clone(&self.app)).await;
         Ok(())
     }
 
     async fn pause_container(&mut self, container_id: String) -> Result<()> {
         info!("Pause container: {}", container_id);
-        pause_container(container_id).await;
+        pause_container(self.docker.clone(), container_id, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn restart_container(&mut self, container_id: String) -> Result<()> {
+        info!("Restart container: {}", container_id);
+        restart_container(self.docker.clone(), container_id, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn recreate_container(&mut self, container_id: String) -> Result<()> {
+        info!("Recreate container: {}", container_id);
+        let overrides = self
+            .app
+            .lock()
+            .await
+            .pending_env_of(&container_id)
+            .cloned()
+            .unwrap_or_default();
+        recreate_container(
+            self.docker.clone(),
+            container_id,
+            overrides,
+            Arc::clone(&self.app),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn remove_container(&mut self, container_id: String) -> Result<()> {
+        info!("Remove container: {}", container_id);
+        remove_container(self.docker.clone(), container_id, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn remove_image(&mut self, image_id: String) -> Result<()> {
+        info!("Remove image: {}", image_id);
+        remove_image(self.docker.clone(), image_id, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn remove_volume(&mut self, name: String) -> Result<()> {
+        info!("Remove volume: {}", name);
+        remove_volume(self.docker.clone(), name, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn inspect_container(&mut self, container_id: String) -> Result<()> {
+        info!("Inspect container: {}", container_id);
+        inspect_container(self.docker.clone(), container_id, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn list_processes(&mut self, container_id: String) -> Result<()> {
+        debug!("List processes of container: {}", container_id);
+        list_processes(self.docker.clone(), container_id, Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn inspect_image(&mut self, image: String) -> Result<()> {
+        info!("Inspect image: {}", image);
+        // The registry may be slow, don't hold the event loop nor stop the monitoring
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        tokio::spawn(async move {
+            inspect_image(docker, image, app).await;
+        });
+        Ok(())
+    }
+
+    async fn exec(&mut self, container_id: String, command: String) -> Result<()> {
+        info!("Exec in container {}: {}", container_id, command);
+        // Commands may run for a while, keep handling events meanwhile
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        tokio::spawn(async move {
+            exec_command(docker, container_id, command, app).await;
+        });
+        Ok(())
+    }
+
+    async fn start_forward(
+        &mut self,
+        container_id: String,
+        local_port: u16,
+        container_port: u16,
+    ) -> Result<()> {
+        info!(
+            "Forward port {} to {}:{}",
+            local_port, container_id, container_port
+        );
+        if let Some(task) = self.forwards.remove(&local_port) {
+            task.abort();
+        }
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        let t = tokio::spawn(async move {
+            start_port_forward(docker, container_id, local_port, container_port, app).await;
+        });
+        self.forwards.insert(local_port, t);
+        Ok(())
+    }
+
+    async fn stop_forward(&mut self, local_port: u16) -> Result<()> {
+        info!("Stop forwarding port {}", local_port);
+        if let Some(task) = self.forwards.remove(&local_port) {
+            task.abort();
+        }
+        self.app.lock().await.forward_stopped(local_port, None);
+        Ok(())
+    }
+
+    async fn start_probe(
+        &mut self,
+        service_key: String,
+        url: String,
+        interval: Duration,
+    ) -> Result<()> {
+        info!("Probe {} for {}", url, service_key);
+        if let Some(task) = self.probes.remove(&service_key) {
+            task.abort();
+        }
+        let app = Arc::clone(&self.app);
+        let key = service_key.clone();
+        let t = tokio::spawn(async move {
+            start_http_probe(key, url, interval, app).await;
+        });
+        self.probes.insert(service_key, t);
         Ok(())
     }
+
+    async fn stop_probe(&mut self, service_key: String) -> Result<()> {
+        info!("Stop probing {}", service_key);
+        if let Some(task) = self.probes.remove(&service_key) {
+            task.abort();
+        }
+        Ok(())
+    }
+
+    async fn connect(&mut self, profile: Option<EngineProfile>) -> Result<()> {
+        let name = profile.as_ref().map_or("local", |p| p.name.as_str());
+        info!("Connect to engine: {}", name);
+        // The previous engine stops filling the table right away
+        self.abort_current_task().await;
+        self.docker = match connect(profile.as_ref()) {
+            Ok(docker) => docker,
+            Err(e) => {
+                let message = format!("Error connecting to {}: {}", name, e);
+                error!("{}", message);
+                self.app.lock().await.connection_failed(message);
+                // Back to the previous engine, an agent sends its snapshots by itself
+                if self.agent.is_some() {
+                    return Ok(());
+                }
+                return self.start_management().await;
+            }
+        };
+        self.agent = profile
+            .as_ref()
+            .and_then(|p| AgentClient::connect(p, Arc::clone(&self.app)));
+        // Keep following the events, from the new engine, the agent does not
+        // send them
+        let events = self.events.take();
+        if let Some(task) = &events {
+            task.abort();
+        }
+        if self.agent.is_some() {
+            return Ok(());
+        }
+        if events.is_some() {
+            self.watch_events().await?;
+        }
+        self.start_management().await
+    }
+
+    /// Send the event to the agent, for the ones it can handle
+    async fn forward_to_agent(&mut self, io_event: IoEvent) -> Result<()> {
+        let operation = |operation, container| Request::Operation {
+            operation,
+            container,
+        };
+        let request = match io_event {
+            // The snapshots keep coming, only the logs stop
+            IoEvent::StartMonitoring => Request::StopLogs { preview: false },
+            IoEvent::ShowLogs { range: Some(_), .. } => {
+                bail!("time ranges are not available through the agent")
+            }
+            IoEvent::ShowLogs {
+                container, tail, ..
+            } => Request::Logs {
+                container,
+                tail,
+                preview: false,
+            },
+            IoEvent::PreviewLogs(Some(container)) => Request::Logs {
+                container,
+                tail: Some(PREVIEW_LOG_LINES),
+                preview: true,
+            },
+            IoEvent::PreviewLogs(None) => Request::StopLogs { preview: true },
+            IoEvent::StopContainer(container) => operation(Operation::Stop, container),
+            IoEvent::PauseContainer(container) => operation(Operation::Pause, container),
+            IoEvent::RestartContainer(container) => operation(Operation::Restart, container),
+            IoEvent::RemoveContainer(container) => operation(Operation::Remove, container),
+            _ => bail!("not available through the agent"),
+        };
+        match &self.agent {
+            Some(agent) => agent.send(request).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn list_images(&mut self) -> Result<()> {
+        debug!("List images");
+        list_images(self.docker.clone(), Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn list_volumes(&mut self) -> Result<()> {
+        debug!("List volumes");
+        list_volumes(self.docker.clone(), Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn list_networks(&mut self) -> Result<()> {
+        debug!("List networks");
+        list_networks(self.docker.clone(), Arc::clone(&self.app)).await;
+        Ok(())
+    }
+
+    async fn watch_events(&mut self) -> Result<()> {
+        if self.events.is_some() {
+            return Ok(());
+        }
+        info!("Follow engine events");
+        let docker = self.docker.clone();
+        let app = Arc::clone(&self.app);
+        self.events = Some(tokio::spawn(async move {
+            watch_events(docker, app).await;
+        }));
+        Ok(())
+    }
+}
+
+impl Drop for IoAsyncHandler {
+    /// Close the forwarded ports and stop the probes along with bctop
+    fn drop(&mut self) {
+        for task in self.forwards.values().chain(self.probes.values()) {
+            task.abort();
+        }
+        for task in self.events.iter().chain(self.preview.iter()) {
+            task.abort();
+        }
+    }
 }
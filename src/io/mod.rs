@@ -1,9 +1,114 @@
 pub mod handler;
 
+use std::time::Duration;
+
+use crate::config::EngineProfile;
+use crate::container_management::LogRange;
+
 #[derive(Debug)]
 pub enum IoEvent {
     StartMonitoring,
-    ShowLogs(String),
+    /// Show the logs of a container, only the ones of the range when given
+    ShowLogs {
+        container: String,
+        range: Option<LogRange>,
+        /// Last lines to fetch, all of them when unset
+        tail: Option<usize>,
+    },
+    /// Show the logs of several containers together, each line prefixed by
+    /// the service name given with the container id
+    ShowStackLogs {
+        containers: Vec<(String, String)>,
+        /// Last lines to fetch of each container, all of them when unset
+        tail: Option<usize>,
+    },
+    /// Fetch every log line of a container and write them to a file
+    ExportLogs {
+        container: String,
+        path: String,
+    },
+    /// Follow the logs shown under the table, `None` to stop
+    PreviewLogs(Option<String>),
+    /// Follow a file of a container in the log viewer
+    TailFile {
+        container: String,
+        path: String,
+    },
     StopContainer(String),
     PauseContainer(String),
+    RestartContainer(String),
+    RemoveContainer(String),
+    /// Remove an image or a volume by id or name, the engine refusing the
+    /// ones still used
+    RemoveImage(String),
+    RemoveVolume(String),
+    /// Replace a container by a new one with its pending env overrides
+    RecreateContainer(String),
+    /// Fetch the configuration and state of a container for the detail view
+    InspectContainer(String),
+    /// Fetch the processes of a container for the detail view
+    ListProcesses(String),
+    /// Fetch digest and provenance of an image reference
+    InspectImage(String),
+    /// Run a shell command in a container
+    Exec {
+        container: String,
+        command: String,
+    },
+    /// Proxy a local port to a port of a container
+    StartForward {
+        container: String,
+        local_port: u16,
+        container_port: u16,
+    },
+    StopForward(u16),
+    /// Request an URL periodically on behalf of a service
+    StartProbe {
+        service_key: String,
+        url: String,
+        interval: Duration,
+    },
+    StopProbe(String),
+    /// Fetch the images, volumes or networks for their views
+    ListImages,
+    ListVolumes,
+    ListNetworks,
+    /// Follow the events of the engine, until bctop exits
+    WatchEvents,
+    /// Reconnect to another engine, `None` being the local defaults
+    Connect(Option<EngineProfile>),
+}
+
+impl IoEvent {
+    /// Name of the event, without the container or command it applies to
+    pub fn name(&self) -> &'static str {
+        match self {
+            IoEvent::StartMonitoring => "start_monitoring",
+            IoEvent::ShowLogs { .. } => "show_logs",
+            IoEvent::ShowStackLogs { .. } => "show_stack_logs",
+            IoEvent::ExportLogs { .. } => "export_logs",
+            IoEvent::PreviewLogs(_) => "preview_logs",
+            IoEvent::TailFile { .. } => "tail_file",
+            IoEvent::StopContainer(_) => "stop_container",
+            IoEvent::PauseContainer(_) => "pause_container",
+            IoEvent::RestartContainer(_) => "restart_container",
+            IoEvent::RemoveContainer(_) => "remove_container",
+            IoEvent::RemoveImage(_) => "remove_image",
+            IoEvent::RemoveVolume(_) => "remove_volume",
+            IoEvent::RecreateContainer(_) => "recreate_container",
+            IoEvent::InspectContainer(_) => "inspect_container",
+            IoEvent::ListProcesses(_) => "list_processes",
+            IoEvent::InspectImage(_) => "inspect_image",
+            IoEvent::Exec { .. } => "exec",
+            IoEvent::StartForward { .. } => "start_forward",
+            IoEvent::StopForward(_) => "stop_forward",
+            IoEvent::StartProbe { .. } => "start_probe",
+            IoEvent::StopProbe(_) => "stop_probe",
+            IoEvent::ListImages => "list_images",
+            IoEvent::ListVolumes => "list_volumes",
+            IoEvent::ListNetworks => "list_networks",
+            IoEvent::WatchEvents => "watch_events",
+            IoEvent::Connect(_) => "connect",
+        }
+    }
 }
@@ -1,18 +1,39 @@
+pub mod accessible;
+pub mod agent;
 pub mod app;
+pub mod cli;
+pub mod clipboard;
+pub mod config;
 pub mod container_management;
+pub mod daemon;
+pub mod env_overrides;
 pub mod inputs;
 pub mod io;
+pub mod log_filters;
+pub mod logging;
+pub mod notes;
+pub mod opener;
+pub mod session;
+pub mod store;
+pub mod telemetry;
 
 use app::{ui, App, AppReturn};
+use container_management::{ContainerManagement, Docker};
 use eyre::Result;
 use inputs::{events::Events, InputEvent};
 use io::IoEvent;
 use std::{io::stdout, sync::Arc, time::Duration};
 
-pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>) -> Result<()> {
+/// Run the interface until quit. `docker` runs the exec sessions, which are
+/// not available without it, through an agent.
+pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>, docker: Option<Docker>) -> Result<()> {
     let mut stdout_ = stdout();
     crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(stdout_, crossterm::terminal::EnterAlternateScreen)?;
+    crossterm::execute!(
+        stdout_,
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::event::EnableMouseCapture
+    )?;
     let backend = tui::backend::CrosstermBackend::new(stdout_);
     let mut terminal = tui::Terminal::new(backend)?;
     terminal.clear()?;
@@ -28,27 +49,117 @@ pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>) -> Result<()> {
         app.dispatch(IoEvent::StartMonitoring).await;
     }
 
+    // Reported once the interface is back
+    let mut suspended_error = None;
     loop {
         let mut app = app.lock().await;
-        terminal.draw(|rect| ui::draw(rect, &mut app))?;
+        if let Some(message) = suspended_error.take() {
+            app.report_error(message);
+        }
+        app.set_screen_size(terminal.size()?);
+        terminal.draw(|rect| ui::draw(rect, &app))?;
 
         let result = match events.next().await {
             InputEvent::Input(key) => app.do_action(key).await,
+            InputEvent::Click { column, row } => app.click(column, row, terminal.size()?).await,
             InputEvent::Tick => app.update_on_tick().await,
         };
 
         // Check if we should exit
-        if result == AppReturn::Exit {
-            events.close();
-            break;
+        match result {
+            AppReturn::Exit => {
+                events.close();
+                break;
+            }
+            AppReturn::Page(path) => {
+                // The interface keeps being updated meanwhile
+                drop(app);
+                events.pause();
+                tokio::time::sleep(tick_rate).await;
+                crossterm::terminal::disable_raw_mode()?;
+                crossterm::execute!(
+                    stdout(),
+                    crossterm::event::DisableMouseCapture,
+                    crossterm::terminal::LeaveAlternateScreen,
+                    crossterm::cursor::Show
+                )?;
+                let result = tokio::task::block_in_place(|| opener::page(&path));
+                // A copy of the buffer, not worth keeping
+                let _ = std::fs::remove_file(&path);
+                crossterm::terminal::enable_raw_mode()?;
+                crossterm::execute!(
+                    stdout(),
+                    crossterm::terminal::EnterAlternateScreen,
+                    crossterm::event::EnableMouseCapture
+                )?;
+                terminal.clear()?;
+                terminal.hide_cursor()?;
+                events.resume();
+                suspended_error = result
+                    .err()
+                    .map(|e| format!("Error opening the pager: {}", e));
+            }
+            AppReturn::Session { container, command } => {
+                let name = app
+                    .containers()
+                    .iter()
+                    .find(|c| c.id == container)
+                    .map_or_else(|| container.clone(), |c| app.short_name(c));
+                let detach = app.detach_key();
+                let passthrough = app.passthrough_keys();
+                let keys = ui::session_keys(detach, &passthrough);
+                drop(app);
+                let docker = match &docker {
+                    Some(docker) => docker,
+                    None => {
+                        suspended_error = Some(format!(
+                            "Error running {} in {}: not available through the agent",
+                            command, name
+                        ));
+                        continue;
+                    }
+                };
+                // Raw mode stays on, the keys go to the session as typed
+                events.pause();
+                tokio::time::sleep(tick_rate).await;
+                crossterm::execute!(
+                    stdout(),
+                    crossterm::event::DisableMouseCapture,
+                    crossterm::terminal::LeaveAlternateScreen,
+                    crossterm::cursor::Show
+                )?;
+                let banner = format!("[bctop] {} in {}: {}", command, name, keys);
+                let result =
+                    session::run(docker, &container, &command, &banner, detach, &passthrough).await;
+                crossterm::execute!(
+                    stdout(),
+                    crossterm::terminal::EnterAlternateScreen,
+                    crossterm::event::EnableMouseCapture
+                )?;
+                terminal.clear()?;
+                terminal.hide_cursor()?;
+                events.resume();
+                suspended_error = match result {
+                    Ok(Some(code)) if code != 0 => {
+                        Some(format!("{} exited with {} in {}", command, code, name))
+                    }
+                    Ok(_) => None,
+                    Err(e) => Some(format!("Error running {} in {}: {}", command, name, e)),
+                };
+            }
+            AppReturn::Continue => {}
         }
     }
 
     terminal.clear()?;
     terminal.show_cursor()?;
     crossterm::terminal::disable_raw_mode()?;
-    crossterm::execute!(stdout(), crossterm::terminal::LeaveAlternateScreen)?;
+    crossterm::execute!(
+        stdout(),
+        crossterm::event::DisableMouseCapture,
+        crossterm::terminal::LeaveAlternateScreen
+    )?;
 
-    println!("");
+    println!();
     Ok(())
 }
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::store;
+
+/// Patterns deciding which log lines of a service are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// When not empty, only the lines matching one of them are kept
+    pub include: Vec<String>,
+    /// Lines matching one of them are dropped
+    pub exclude: Vec<String>,
+}
+
+impl LogFilter {
+    /// Parse the text of the editor, one `+regex` or `-regex` per line
+    pub fn parse(text: &str) -> Self {
+        let mut filter = Self::default();
+        for line in text.lines() {
+            if let Some(pattern) = line.strip_prefix('+') {
+                filter.include.push(pattern.to_string());
+            } else if let Some(pattern) = line.strip_prefix('-') {
+                filter.exclude.push(pattern.to_string());
+            }
+        }
+        filter
+    }
+
+    /// Text shown in the editor, the reverse of `parse`
+    pub fn to_text(&self) -> String {
+        self.include
+            .iter()
+            .map(|p| format!("+{}\n", p))
+            .chain(self.exclude.iter().map(|p| format!("-{}\n", p)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Compile the patterns, leaving out the invalid ones
+    pub fn compile(&self) -> CompiledLogFilter {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .filter_map(|p| match Regex::new(p) {
+                    Ok(re) => Some(re),
+                    Err(e) => {
+                        warn!("Invalid log filter {}: {}", p, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        CompiledLogFilter {
+            include: compile(&self.include),
+            exclude: compile(&self.exclude),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledLogFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl CompiledLogFilter {
+    pub fn keeps(&self, line: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|re| re.is_match(line)))
+            && !self.exclude.iter().any(|re| re.is_match(line))
+    }
+}
+
+/// Log filters keyed by service name so they survive container recreation.
+/// Stored in `<data dir>/bctop/log_filters.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogFilters(HashMap<String, LogFilter>);
+
+impl LogFilters {
+    /// Load the saved filters, starting empty when there are none or the file
+    /// can not be parsed.
+    pub fn load() -> Self {
+        store::load("log_filters.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save("log_filters.json", self)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&LogFilter> {
+        self.0.get(key)
+    }
+
+    /// Replace the filter of a service, removing it when it has no pattern
+    pub fn set(&mut self, key: &str, filter: LogFilter) {
+        if filter.is_empty() {
+            self.0.remove(key);
+        } else {
+            self.0.insert(key.to_string(), filter);
+        }
+    }
+}
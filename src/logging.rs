@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Local;
+use directories::BaseDirs;
+use eyre::Result;
+use log::{Level, LevelFilter, Record};
+use log4rs::append::file::FileAppender;
+use log4rs::append::Append;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+
+/// Number of records kept in memory for the debug console
+const MEMORY_RECORDS: usize = 500;
+
+static RECORDS: Mutex<VecDeque<(Level, String)>> = Mutex::new(VecDeque::new());
+
+/// Most recent log records of the application, the newest last
+pub fn recent_records() -> Vec<(Level, String)> {
+    RECORDS
+        .lock()
+        .map(|records| records.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Keeps the last records in memory so they can be shown inside the UI
+#[derive(Debug)]
+struct MemoryAppender;
+
+impl Append for MemoryAppender {
+    fn append(&self, record: &Record) -> anyhow::Result<()> {
+        if let Ok(mut records) = RECORDS.lock() {
+            if records.len() >= MEMORY_RECORDS {
+                records.pop_front();
+            }
+            records.push_back((
+                record.level(),
+                format!("{} {}", Local::now().format("%H:%M:%S"), record.args()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn flush(&self) {}
+}
+
+/// Default location of the log file, `$XDG_DATA_HOME/bctop/bctop.log` on Linux
+pub fn default_path() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.data_dir().join("bctop").join("bctop.log"))
+}
+
+/// Setup the application logger.
+///
+/// Records are always kept in memory for the debug console. When there is no
+/// path or it can not be written, bctop keeps running without a log file
+/// instead of failing to start.
+pub fn init(path: Option<PathBuf>, level: LevelFilter) -> Result<()> {
+    let mut config =
+        Config::builder().appender(Appender::builder().build("memory", Box::new(MemoryAppender)));
+    let mut root = Root::builder().appender("memory");
+    if let Some(appender) = path.as_deref().and_then(file_appender) {
+        config = config.appender(Appender::builder().build("logfile", Box::new(appender)));
+        root = root.appender("logfile");
+    }
+    let config = config.build(root.build(level))?;
+    log4rs::init_config(config)?;
+    Ok(())
+}
+
+fn file_appender(path: &Path) -> Option<FileAppender> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).ok()?;
+    }
+    match FileAppender::builder()
+        .encoder(Box::new(PatternEncoder::new("{d} {l} - {m}\n")))
+        .build(path)
+    {
+        Ok(appender) => Some(appender),
+        Err(e) => {
+            eprintln!("Can not write logs to {}: {}", path.display(), e);
+            None
+        }
+    }
+}
@@ -1,46 +1,104 @@
+use bctop::accessible::start_accessible;
+use bctop::agent::start_agent;
 use bctop::app::App;
+use bctop::cli::{Args, Command};
+use bctop::config::Config;
+use bctop::container_management::connect;
+use bctop::daemon::start_daemon;
 use bctop::io::handler::IoAsyncHandler;
 use bctop::io::IoEvent;
+use bctop::logging;
 use bctop::start_ui;
+use bctop::telemetry;
 use eyre::Result;
 use std::sync::Arc;
-use tokio;
 
-use directories::BaseDirs;
+use clap::Parser;
 use log::LevelFilter;
-use log4rs::append::file::FileAppender;
-use log4rs::config::{Appender, Config, Root};
-use log4rs::encode::pattern::PatternEncoder;
-use reqwest;
 use serde::Deserialize;
 use std::error::Error;
+use std::str::FromStr;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let logfile = FileAppender::builder()
-        .encoder(Box::new(PatternEncoder::new("{l} - {m}\n")))
-        .build(BaseDirs::new().unwrap().data_dir().join("logs/bctop.log"))?;
+    let args = Args::parse();
+    let config = Config::load();
 
-    let config = Config::builder()
-        .appender(Appender::builder().build("logfile", Box::new(logfile)))
-        .build(Root::builder().appender("logfile").build(LevelFilter::Info))?;
+    let level = args
+        .log_level
+        .unwrap_or_else(|| LevelFilter::from_str(&config.log.level).unwrap_or(LevelFilter::Info));
+    let log_path = args
+        .log_file
+        .clone()
+        .or_else(|| config.log.path.clone())
+        .or_else(logging::default_path);
+    logging::init(log_path, level)?;
+    telemetry::start(&config.telemetry);
 
-    log4rs::init_config(config)?;
+    let profile = match &args.profile {
+        Some(name) => Some(
+            config
+                .profile_index(name)
+                .ok_or(format!("Unknown profile: {}", name))?,
+        ),
+        None => None,
+    };
+    let docker = connect(profile.map(|i| &config.profiles[i]))?;
+    let agent = profile
+        .map(|i| config.profiles[i].clone())
+        .filter(|p| p.agent().is_some());
+    let listen = match &args.command {
+        Some(Command::Agent { listen, token }) => Some((
+            listen.clone(),
+            token
+                .clone()
+                .or_else(|| std::env::var("BCTOP_AGENT_TOKEN").ok())
+                .filter(|t| !t.is_empty()),
+        )),
+        _ => None,
+    };
+    let agent_docker = docker.clone();
+    // Sessions need the engine itself
+    let session_docker = agent.is_none().then(|| docker.clone());
 
+    let accessible = args.accessible || config.accessible;
+    let daemon = matches!(args.command, Some(Command::Daemon));
     let (sync_io_tx, mut sync_io_rx) = tokio::sync::mpsc::channel::<IoEvent>(100);
-    let app = Arc::new(tokio::sync::Mutex::new(App::new(sync_io_tx.clone())));
+    let mut app = App::new(sync_io_tx.clone(), config, profile);
+    if let Some(pattern) = args.pattern {
+        app.start_filtered(pattern);
+    }
+    if args.dry_run {
+        app.start_dry_run();
+    }
+    // The accessible mode only follows the containers
+    if let (Some(Command::Logs { name }), false) = (args.command, accessible) {
+        app.start_logging(name);
+    }
+    let app = Arc::new(tokio::sync::Mutex::new(app));
     let app_ui = Arc::clone(&app);
 
     tokio::spawn(async move {
-        let mut handler = IoAsyncHandler::new(app);
+        let mut handler = IoAsyncHandler::new(app, docker, agent.as_ref());
         while let Some(io_event) = sync_io_rx.recv().await {
             handler.handle_io_event(io_event).await;
         }
     });
 
-    start_ui(&app_ui).await?;
+    if let Some((listen, token)) = listen {
+        start_agent(&app_ui, agent_docker, &listen, token).await?;
+        return Ok(());
+    } else if daemon {
+        // Nobody reads the update check of an unattended run
+        start_daemon(&app_ui).await?;
+        return Ok(());
+    } else if accessible {
+        start_accessible(&app_ui).await?;
+    } else {
+        start_ui(&app_ui, session_docker).await?;
+    }
     // Check for updates and print to stdout.
     println!("Checking for updates...");
     let cli = reqwest::Client::new();
@@ -96,9 +154,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
         // asume that the new latest is major than the one installed.
         if last_release.name != format!("v{}", VERSION) {
             println!(
-                "New version available: {} (from: {}) \n Download here: {}",
-                last_release.name, VERSION, last_release.assets[0].browser_download_url
+                "New version available: {} (from: {})",
+                last_release.name, VERSION
             );
+            let target = target_triple();
+            match pick_asset(&last_release.assets, &target) {
+                Some(asset) => println!(
+                    "Asset for {}: {}\n Download here: {}",
+                    target, asset.name, asset.browser_download_url
+                ),
+                None => println!(
+                    "No build for {} in this release, see: {}",
+                    target, last_release.html_url
+                ),
+            }
             return Ok(());
         }
     }
@@ -109,12 +178,49 @@ async fn main() -> Result<(), Box<dyn Error>> {
 #[derive(Debug, Deserialize)]
 struct Release {
     name: String, // version
+    html_url: String,
     assets: Vec<Asset>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Asset {
-    // name: String,
+    name: String,
     browser_download_url: String,
     // state: String,
 }
+
+/// Target triple bctop was built for, as used in the release asset names
+fn target_triple() -> String {
+    let system = if cfg!(target_os = "linux") {
+        if cfg!(target_env = "musl") {
+            "unknown-linux-musl"
+        } else {
+            "unknown-linux-gnu"
+        }
+    } else if cfg!(target_os = "macos") {
+        "apple-darwin"
+    } else if cfg!(target_os = "windows") {
+        "pc-windows-msvc"
+    } else {
+        std::env::consts::OS
+    };
+    format!("{}-{}", std::env::consts::ARCH, system)
+}
+
+/// Release asset for a target: its own build, else the static musl build of
+/// the same architecture on Linux. Releases before the per-target assets only
+/// had `bctop`, an x86_64 musl build.
+fn pick_asset<'a>(assets: &'a [Asset], target: &str) -> Option<&'a Asset> {
+    let named = |triple: &str| assets.iter().find(|a| a.name.contains(triple));
+    named(target)
+        .or_else(|| {
+            let musl = target.replace("-linux-gnu", "-linux-musl");
+            (musl != target).then(|| named(&musl)).flatten()
+        })
+        .or_else(|| {
+            target
+                .starts_with("x86_64-unknown-linux")
+                .then(|| assets.iter().find(|a| a.name == "bctop"))
+                .flatten()
+        })
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::store;
+
+/// Free text notes written during an incident, keyed by service name so they
+/// survive container recreation. Stored in `<data dir>/bctop/notes.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Notes(HashMap<String, String>);
+
+impl Notes {
+    /// Load the saved notes, starting empty when there are none or the file
+    /// can not be parsed.
+    pub fn load() -> Self {
+        store::load("notes.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save("notes.json", self)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.get(key)
+    }
+
+    /// Replace the note of a service, removing it when the text is empty
+    pub fn set(&mut self, key: &str, text: String) {
+        if text.trim().is_empty() {
+            self.0.remove(key);
+        } else {
+            self.0.insert(key.to_string(), text);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Open a URL with the desktop default handler (`xdg-open`, or `open` on macOS)
+pub fn open(url: &str) -> io::Result<()> {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    // Output of the handler would draw over the UI
+    Command::new(program)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// Show a file in `$PAGER`, `less` when unset, until it is closed
+pub fn page(path: &Path) -> io::Result<()> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    // The pager may come with its arguments, like `less -S`
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+    let status = Command::new(program).args(words).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )));
+    }
+    Ok(())
+}
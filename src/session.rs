@@ -0,0 +1,215 @@
+use std::io::{stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use eyre::{eyre, Result};
+use futures::StreamExt;
+use log::warn;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+use crate::container_management::{
+    resize_session, session_exit_code, start_session, Docker, ExecSession,
+};
+use crate::inputs::key::Key;
+
+/// Time a read of the terminal waits before checking whether the session ended
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Terminal events read but not sent to the session yet
+const EVENT_QUEUE: usize = 64;
+
+/// Run a command in a container with the terminal attached to it, every key
+/// but `detach` going to the command, until it exits or is detached from.
+/// When `detach` is in `passthrough` it goes to the command too, unless it is
+/// pressed twice in a row. Returns the exit code of the command when it exited.
+///
+/// The terminal must be in raw mode and left by the interface.
+pub async fn run(
+    docker: &Docker,
+    container_id: &str,
+    command: &str,
+    banner: &str,
+    detach: Key,
+    passthrough: &[Key],
+) -> Result<Option<i64>> {
+    let session = start_session(docker, container_id, command)
+        .await?
+        .ok_or_else(|| eyre!("the engine did not attach to the command"))?;
+    if let Ok((columns, lines)) = crossterm::terminal::size() {
+        if let Err(e) = resize_session(docker, &session.id, columns, lines).await {
+            warn!("Error resizing the session in {}: {}", container_id, e);
+        }
+    }
+    let mut out = stdout();
+    write!(out, "{}\r\n", banner)?;
+    out.flush()?;
+
+    // Reading the terminal blocks, it is done aside and stopped with the
+    // session
+    let (tx, mut events) = mpsc::channel(EVENT_QUEUE);
+    let stop = Arc::new(AtomicBool::new(false));
+    let reader_stop = Arc::clone(&stop);
+    let reader = tokio::task::spawn_blocking(move || {
+        while !reader_stop.load(Ordering::Relaxed) {
+            match event::poll(POLL_INTERVAL) {
+                Ok(false) => {}
+                Ok(true) => match event::read() {
+                    Ok(e) => {
+                        if tx.blocking_send(e).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                },
+                Err(_) => break,
+            }
+        }
+    });
+    let detach = if passthrough.contains(&detach) {
+        Detach::Twice(detach)
+    } else {
+        Detach::Once(detach)
+    };
+    let result = forward(docker, session, &mut events, detach).await;
+    stop.store(true, Ordering::Relaxed);
+    let _ = reader.await;
+    result
+}
+
+/// How the detach key leaves the session
+#[derive(Debug, Clone, Copy)]
+enum Detach {
+    Once(Key),
+    /// The key is passed through, pressing it twice detaches
+    Twice(Key),
+}
+
+/// Copy the output of the session to the terminal and the keys to its stdin
+async fn forward(
+    docker: &Docker,
+    mut session: ExecSession,
+    events: &mut mpsc::Receiver<Event>,
+    detach: Detach,
+) -> Result<Option<i64>> {
+    let mut out = stdout();
+    let mut application_keys = false;
+    // Passed through detach key waiting to know whether it is pressed again
+    let mut held: Option<KeyEvent> = None;
+    loop {
+        tokio::select! {
+            chunk = session.output.next() => match chunk {
+                Some(chunk) => {
+                    let bytes = chunk?.into_bytes();
+                    application_keys = cursor_keys_mode(&bytes, application_keys);
+                    out.write_all(&bytes)?;
+                    out.flush()?;
+                }
+                None => return Ok(session_exit_code(docker, &session.id).await),
+            },
+            event = events.recv() => match event {
+                // Some terminals also report releasing the keys
+                Some(Event::Key(key)) if key.kind != KeyEventKind::Release => {
+                    match detach {
+                        Detach::Once(detach) if Key::from(key) == detach => return Ok(None),
+                        Detach::Twice(detach) if Key::from(key) == detach => {
+                            if held.take().is_some() {
+                                return Ok(None);
+                            }
+                            held = Some(key);
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    let mut bytes = held
+                        .take()
+                        .map(|held| key_bytes(held, application_keys))
+                        .unwrap_or_default();
+                    bytes.extend(key_bytes(key, application_keys));
+                    session.input.write_all(&bytes).await?;
+                    session.input.flush().await?;
+                }
+                Some(Event::Resize(columns, lines)) => {
+                    if let Err(e) = resize_session(docker, &session.id, columns, lines).await {
+                        warn!("Error resizing the session: {}", e);
+                    }
+                }
+                Some(_) => {}
+                None => return Err(eyre!("the terminal can not be read anymore")),
+            },
+        }
+    }
+}
+
+/// Bytes a terminal sends for a key, the arrows going by `ESC O` rather than
+/// `ESC [` once the program asked for application cursor keys
+fn key_bytes(key: KeyEvent, application_keys: bool) -> Vec<u8> {
+    let arrow = |c: u8| {
+        let prefix = if application_keys { b'O' } else { b'[' };
+        vec![0x1b, prefix, c]
+    };
+    let mut bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+                ' ' | '@' | '2' => vec![0],
+                '[' | '3' => vec![0x1b],
+                '\\' | '4' => vec![0x1c],
+                ']' | '5' => vec![0x1d],
+                '^' | '6' => vec![0x1e],
+                '_' | '-' | '7' => vec![0x1f],
+                c => c.to_string().into_bytes(),
+            }
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => arrow(b'A'),
+        KeyCode::Down => arrow(b'B'),
+        KeyCode::Right => arrow(b'C'),
+        KeyCode::Left => arrow(b'D'),
+        KeyCode::Home => arrow(b'H'),
+        KeyCode::End => arrow(b'F'),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::F(n) => match n {
+            1 => b"\x1bOP".to_vec(),
+            2 => b"\x1bOQ".to_vec(),
+            3 => b"\x1bOR".to_vec(),
+            4 => b"\x1bOS".to_vec(),
+            5 => b"\x1b[15~".to_vec(),
+            6 => b"\x1b[17~".to_vec(),
+            7 => b"\x1b[18~".to_vec(),
+            8 => b"\x1b[19~".to_vec(),
+            9 => b"\x1b[20~".to_vec(),
+            10 => b"\x1b[21~".to_vec(),
+            11 => b"\x1b[23~".to_vec(),
+            12 => b"\x1b[24~".to_vec(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    // Meta sends the key after an escape
+    if key.modifiers.contains(KeyModifiers::ALT) && !bytes.is_empty() {
+        bytes.insert(0, 0x1b);
+    }
+    bytes
+}
+
+/// Whether the program asked for application cursor keys (DECCKM) in its
+/// output, the mode staying `current` when it did not change it
+fn cursor_keys_mode(output: &[u8], current: bool) -> bool {
+    let last = |sequence: &[u8]| output.windows(sequence.len()).rposition(|w| w == sequence);
+    match (last(b"\x1b[?1h"), last(b"\x1b[?1l")) {
+        (Some(set), Some(reset)) => set > reset,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => current,
+    }
+}
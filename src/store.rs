@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::PathBuf;
+
+use directories::BaseDirs;
+use eyre::Result;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Location of a file kept in `<data dir>/bctop`
+pub fn path(file: &str) -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.data_dir().join("bctop").join(file))
+}
+
+/// Load a saved JSON file of the data dir, starting from the default when
+/// there is none or it can not be parsed.
+pub fn load<T: DeserializeOwned + Default>(file: &str) -> T {
+    let path = match path(file) {
+        Some(p) => p,
+        None => return T::default(),
+    };
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Invalid file {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Write a JSON file of the data dir, creating the directory when needed
+pub fn save<T: Serialize>(file: &str, value: &T) -> Result<()> {
+    if let Some(path) = path(file) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(value)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+use serde_json::{json, Value};
+
+use crate::config::TelemetryConfig;
+
+/// Spans kept while the collector can not be reached, the oldest are dropped
+const MAX_PENDING_SPANS: usize = 2000;
+/// Durations of the latest engine requests, for the debug console
+const MAX_LATENCIES: usize = 500;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SPANS: Mutex<Vec<SpanRecord>> = Mutex::new(Vec::new());
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+static ENGINE_LATENCIES: Mutex<Vec<Duration>> = Mutex::new(Vec::new());
+
+/// Latency of the latest engine requests
+#[derive(Debug, Clone, Copy)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    /// Requests they are computed from
+    pub count: usize,
+}
+
+/// One operation of bctop: an engine request, an IO event or a user action
+#[derive(Debug)]
+struct SpanRecord {
+    name: String,
+    start: SystemTime,
+    end: SystemTime,
+    error: Option<String>,
+    attributes: Vec<(String, String)>,
+}
+
+/// Start exporting the recorded spans when an endpoint is configured
+pub fn start(config: &TelemetryConfig) {
+    let endpoint = match &config.endpoint {
+        Some(e) => e.trim_end_matches('/').to_string(),
+        None => return,
+    };
+    let url = if endpoint.ends_with("/v1/traces") {
+        endpoint
+    } else {
+        format!("{}/v1/traces", endpoint)
+    };
+    ENABLED.store(true, Ordering::Relaxed);
+    let config = config.clone();
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        loop {
+            tokio::time::sleep(Duration::from_secs(config.interval_seconds.max(1))).await;
+            export(&client, &url, &config).await;
+        }
+    });
+}
+
+/// Record an operation that started at `start` and ends now
+pub fn record(name: &str, start: SystemTime, error: Option<String>, attributes: &[(&str, &str)]) {
+    // Kept even without a collector, the debug console shows them
+    if name == "engine_request" {
+        if let (Ok(mut latencies), Ok(elapsed)) = (ENGINE_LATENCIES.lock(), start.elapsed()) {
+            if latencies.len() >= MAX_LATENCIES {
+                latencies.remove(0);
+            }
+            latencies.push(elapsed);
+        }
+    }
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Ok(mut spans) = SPANS.lock() {
+        if spans.len() >= MAX_PENDING_SPANS {
+            spans.remove(0);
+        }
+        spans.push(SpanRecord {
+            name: name.to_string(),
+            start,
+            end: SystemTime::now(),
+            error,
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        });
+    }
+}
+
+/// Percentiles of the latest engine requests, `None` before the first one
+pub fn engine_latency() -> Option<Percentiles> {
+    let mut latencies = ENGINE_LATENCIES.lock().ok()?.clone();
+    if latencies.is_empty() {
+        return None;
+    }
+    latencies.sort();
+    let at = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+    Some(Percentiles {
+        p50: at(50),
+        p95: at(95),
+        p99: at(99),
+        count: latencies.len(),
+    })
+}
+
+/// Send the pending spans as OTLP JSON, keeping them for the next try on failure
+async fn export(client: &reqwest::Client, url: &str, config: &TelemetryConfig) {
+    let spans = match SPANS.lock() {
+        Ok(mut spans) if !spans.is_empty() => std::mem::take(&mut *spans),
+        _ => return,
+    };
+    let mut request = client.post(url).json(&payload(&spans, config));
+    for (key, value) in &config.headers {
+        request = request.header(key, value);
+    }
+    match request.send().await.and_then(|r| r.error_for_status()) {
+        Ok(_) => debug!("Exported {} spans to {}", spans.len(), url),
+        Err(e) => {
+            warn!("Error exporting telemetry to {}: {}", url, e);
+            if let Ok(mut pending) = SPANS.lock() {
+                let mut spans = spans;
+                spans.append(&mut pending);
+                let excess = spans.len().saturating_sub(MAX_PENDING_SPANS);
+                spans.drain(..excess);
+                *pending = spans;
+            }
+        }
+    }
+}
+
+fn payload(spans: &[SpanRecord], config: &TelemetryConfig) -> Value {
+    // Operations are independent, each one is its own trace
+    let spans = spans
+        .iter()
+        .map(|s| {
+            let mut span = json!({
+                "traceId": random_hex(2),
+                "spanId": random_hex(1),
+                "name": s.name,
+                "kind": 1,
+                "startTimeUnixNano": unix_nanos(s.start),
+                "endTimeUnixNano": unix_nanos(s.end),
+                "attributes": s.attributes.iter().map(|(k, v)| attribute(k, v)).collect::<Vec<_>>(),
+            });
+            span["status"] = match &s.error {
+                Some(message) => json!({ "code": 2, "message": message }),
+                None => json!({ "code": 1 }),
+            };
+            span
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", &config.service_name),
+                    attribute("service.version", env!("CARGO_PKG_VERSION")),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "bctop" },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Nanoseconds since the epoch, as a string like the OTLP JSON encoding expects
+fn unix_nanos(t: SystemTime) -> String {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+/// Hex id made of `words` random 64 bits words
+fn random_hex(words: usize) -> String {
+    (0..words)
+        .map(|_| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}